@@ -0,0 +1,368 @@
+//! Alarms starting a playback context at scheduled times
+
+use std::{fmt, str::FromStr};
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use rspotify::model::{AlbumId, Id, PlaylistId};
+use serde::{Deserialize, Serialize};
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Maximum number of days searched for the next occurrence of a schedule,
+/// long enough to find a schedule running on February 29th only
+const MAX_SEARCHED_DAYS: i64 = 366 * 4 + 1;
+
+/// An alarm, starting a context on a device at scheduled times
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alarm {
+    pub schedule: Schedule,
+    /// the context to play, either `liked-tracks` or a playlist/album URI
+    pub context: AlarmContext,
+    /// name or ID of the device to play on, the active device if not specified
+    pub device: Option<String>,
+    /// volume of the playback, the device's current volume if not specified
+    pub volume: Option<u8>,
+    /// duration of the volume fade-in, from silence to the alarm's volume
+    #[serde(default)]
+    pub fade_in_secs: u64,
+    #[serde(default)]
+    pub shuffle: bool,
+}
+
+/// An alarm added with the `alarm add` CLI command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAlarm {
+    pub id: u32,
+    #[serde(flatten)]
+    pub alarm: Alarm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AlarmContext {
+    Playlist(PlaylistId<'static>),
+    Album(AlbumId<'static>),
+    LikedTracks,
+}
+
+/// When an alarm goes off, either as a cron expression (`minute hour day-of-month month day-of-week`)
+/// or as `[DAYS] HH:MM` with `DAYS` being `daily`, `weekdays`, `weekends` or a list of week days
+/// like `mon,wed,fri` or `mon-thu`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+    source: String,
+    /// bit sets of the matching values of each field
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// whether the days of month/week are restricted, a date matching either of them if both are
+    restricted_days: bool,
+    restricted_weekdays: bool,
+}
+
+impl Schedule {
+    /// Get the first time strictly after `t` matching the schedule
+    pub fn next_after(&self, t: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = t.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for offset in 0..MAX_SEARCHED_DAYS {
+            let date = start.date() + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            let from = if offset == 0 {
+                (start.hour(), start.minute())
+            } else {
+                (0, 0)
+            };
+            for hour in from.0..24 {
+                if !has(self.hours, hour) {
+                    continue;
+                }
+                let first_minute = if hour == from.0 { from.1 } else { 0 };
+                if let Some(minute) = (first_minute..60).find(|&m| has(self.minutes, m)) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: chrono::NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.restricted_days && self.restricted_weekdays {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    fn from_cron(source: &str, fields: &[&str]) -> Result<Self> {
+        let [minutes, hours, days, months, weekdays] = fields else {
+            anyhow::bail!("a cron expression must have 5 fields");
+        };
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minutes, 0, 59, &[])?,
+            hours: parse_field(hours, 0, 23, &[])?,
+            days: parse_field(days, 1, 31, &[])?,
+            months: parse_field(months, 1, 12, &MONTH_NAMES)?,
+            weekdays: parse_weekdays(weekdays)?,
+            restricted_days: *days != "*",
+            restricted_weekdays: *weekdays != "*",
+        })
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let (days, time) = match fields[..] {
+            [_, _, _, _, _] => return Self::from_cron(s, &fields),
+            [time] | ["daily" | "everyday", time] => ("*", time),
+            ["weekdays", time] => ("mon-fri", time),
+            ["weekends", time] => ("sat,sun", time),
+            [days, time] => (days, time),
+            _ => anyhow::bail!("invalid alarm schedule {s}"),
+        };
+
+        let (hour, minute) = time
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("invalid time {time}, expected HH:MM"))?;
+        let mut schedule = Self::from_cron(s, &[minute, hour, "*", "*", days])?;
+        if schedule.minutes.count_ones() != 1 || schedule.hours.count_ones() != 1 {
+            anyhow::bail!("invalid time {time}, expected HH:MM");
+        }
+        schedule.source = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        Ok(schedule)
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.source
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl FromStr for AlarmContext {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "liked-tracks" {
+            Ok(Self::LikedTracks)
+        } else if let Ok(id) = PlaylistId::from_uri(s) {
+            Ok(Self::Playlist(id.into_static()))
+        } else if let Ok(id) = AlbumId::from_uri(s) {
+            Ok(Self::Album(id.into_static()))
+        } else {
+            anyhow::bail!(
+                "invalid alarm context {s}, expected `liked-tracks` or a playlist/album URI"
+            )
+        }
+    }
+}
+
+impl TryFrom<String> for AlarmContext {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<AlarmContext> for String {
+    fn from(context: AlarmContext) -> Self {
+        context.to_string()
+    }
+}
+
+impl fmt::Display for AlarmContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Playlist(id) => write!(f, "{}", id.uri()),
+            Self::Album(id) => write!(f, "{}", id.uri()),
+            Self::LikedTracks => write!(f, "liked-tracks"),
+        }
+    }
+}
+
+impl fmt::Display for Alarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.schedule, self.context)?;
+        if self.shuffle {
+            write!(f, " (shuffled)")?;
+        }
+        if let Some(device) = &self.device {
+            write!(f, " on {device}")?;
+        }
+        if let Some(volume) = self.volume {
+            write!(f, ", volume {volume}%")?;
+        }
+        if self.fade_in_secs > 0 {
+            write!(f, ", fade-in {}s", self.fade_in_secs)?;
+        }
+        Ok(())
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse the day-of-week field of a cron expression, both `0` and `7` meaning Sunday
+fn parse_weekdays(field: &str) -> Result<u64> {
+    let set = parse_field(field, 0, 7, &WEEKDAY_NAMES)?;
+    Ok((set | (set >> 7)) & 0x7f)
+}
+
+/// Parse a cron field made of comma-separated `*`, values or ranges with an optional `/step`.
+///
+/// `names` are aliases of the values, starting from `min`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |s: &str| -> Result<u32> {
+        let v = match names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            Some(i) => min + u32::try_from(i)?,
+            None => s
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid value {s} in schedule field {field}"))?,
+        };
+        if v < min || v > max {
+            anyhow::bail!("value {v} out of range {min}-{max} in schedule field {field}");
+        }
+        Ok(v)
+    };
+
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>()?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            anyhow::bail!("invalid step in schedule field {field}");
+        }
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            None => (value(range)?, value(range)?),
+        };
+        if start > end {
+            anyhow::bail!("invalid range {range} in schedule field {field}");
+        }
+        for v in (start..=end).step_by(step) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(schedule: &str, after: &str) -> Option<NaiveDateTime> {
+        schedule
+            .parse::<Schedule>()
+            .unwrap()
+            .next_after(time(after))
+    }
+
+    #[test]
+    fn parse_schedules() {
+        // 2026-10-16 is a Friday
+        assert_eq!(
+            next("weekdays 07:30", "2026-10-16 07:30"),
+            Some(time("2026-10-19 07:30"))
+        );
+        assert_eq!(
+            next("weekends 9:00", "2026-10-16 07:30"),
+            Some(time("2026-10-17 09:00"))
+        );
+        assert_eq!(
+            next("07:30", "2026-10-16 07:29"),
+            Some(time("2026-10-16 07:30"))
+        );
+        assert_eq!(
+            next("mon,wed 22:15", "2026-10-16 00:00"),
+            Some(time("2026-10-19 22:15"))
+        );
+        assert_eq!(
+            next("*/20 8-9 * * 1-5", "2026-10-16 08:45"),
+            Some(time("2026-10-16 09:00"))
+        );
+        assert_eq!(
+            next("0 6 * * sun", "2026-10-16 00:00"),
+            Some(time("2026-10-18 06:00"))
+        );
+        assert_eq!(
+            next("0 6 * * 7", "2026-10-16 00:00"),
+            Some(time("2026-10-18 06:00"))
+        );
+        // either the day of month or the day of week matches if both are restricted
+        assert_eq!(
+            next("0 12 1 * mon", "2026-10-16 00:00"),
+            Some(time("2026-10-19 12:00"))
+        );
+        assert_eq!(
+            next("0 0 29 feb *", "2026-10-16 00:00"),
+            Some(time("2028-02-29 00:00"))
+        );
+        assert_eq!(next("0 0 30 feb *", "2026-10-16 00:00"), None);
+
+        for invalid in [
+            "",
+            "7:30pm",
+            "weekdays",
+            "funday 07:30",
+            "*:30",
+            "60 * * * *",
+        ] {
+            assert!(invalid.parse::<Schedule>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_alarm_contexts() {
+        assert_eq!(
+            "liked-tracks".parse::<AlarmContext>().ok(),
+            Some(AlarmContext::LikedTracks)
+        );
+        let uri = "spotify:playlist:37i9dQZF1DXcBWIGoYBM5M";
+        let context = uri.parse::<AlarmContext>().unwrap();
+        assert!(matches!(context, AlarmContext::Playlist(_)));
+        assert_eq!(context.to_string(), uri);
+        assert!("spotify:track:4uLU6hMCjMI75M1A2tKUQC"
+            .parse::<AlarmContext>()
+            .is_err());
+    }
+}
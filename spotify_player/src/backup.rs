@@ -0,0 +1,248 @@
+//! A versioned snapshot of the user's library, used to back it up and restore it
+
+use std::{collections::HashSet, hash::Hash};
+
+use rspotify::model::UserId;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Album, Artist, Playlist, Show, Track};
+
+/// Version of the backup document, bumped whenever its format changes incompatibly
+pub const BACKUP_VERSION: u32 = 1;
+
+/// A snapshot of the user's library, built from the same data stored in the file caches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryBackup {
+    pub version: u32,
+    /// creation time of the backup, in RFC 3339 format
+    pub created_at: String,
+    pub user_id: UserId<'static>,
+    /// liked tracks, sorted by liked date (newest first)
+    pub saved_tracks: Vec<Track>,
+    pub saved_albums: Vec<Album>,
+    pub saved_shows: Vec<Show>,
+    pub followed_artists: Vec<Artist>,
+    /// owned and followed playlists
+    pub playlists: Vec<PlaylistBackup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaylistBackup {
+    pub playlist: Playlist,
+    pub tracks: Vec<Track>,
+}
+
+/// Items of a backup that are missing from the live library
+#[derive(Debug, Default)]
+pub struct LibraryDiff<'a> {
+    /// missing liked tracks, sorted by liked date (oldest first) to be liked in the original order
+    pub saved_tracks: Vec<&'a Track>,
+    pub saved_albums: Vec<&'a Album>,
+    pub saved_shows: Vec<&'a Show>,
+    pub followed_artists: Vec<&'a Artist>,
+    /// playlists that are no longer followed
+    pub playlists: Vec<&'a PlaylistBackup>,
+}
+
+impl LibraryBackup {
+    /// Check that the backup can be restored by this version of the application
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.version > BACKUP_VERSION {
+            anyhow::bail!(
+                "unsupported backup version {}, the latest supported version is {BACKUP_VERSION}",
+                self.version
+            );
+        }
+        Ok(())
+    }
+
+    /// Compute the items of the backup missing from the current library.
+    ///
+    /// `current` is only required to have the top-level items, playlists' tracks are ignored.
+    pub fn diff<'a>(&'a self, current: &LibraryBackup) -> LibraryDiff<'a> {
+        let mut saved_tracks = missing(&self.saved_tracks, &current.saved_tracks, Track::uri);
+        saved_tracks.reverse();
+        // the sort is stable, so tracks without a liked date keep the backup's (reversed) order
+        saved_tracks.sort_by_key(|t| t.added_at);
+
+        LibraryDiff {
+            saved_tracks,
+            saved_albums: missing(&self.saved_albums, &current.saved_albums, |a| a.id.clone()),
+            saved_shows: missing(&self.saved_shows, &current.saved_shows, |s| s.id.clone()),
+            followed_artists: missing(&self.followed_artists, &current.followed_artists, |a| {
+                a.id.clone()
+            }),
+            playlists: missing(&self.playlists, &current.playlists, |p| {
+                p.playlist.id.clone()
+            }),
+        }
+    }
+}
+
+impl PlaylistBackup {
+    /// Get the tracks of the backed up playlist that are missing from its current tracks
+    pub fn missing_tracks<'a>(&'a self, current: &[Track]) -> Vec<&'a Track> {
+        missing(&self.tracks, current, Track::uri)
+    }
+}
+
+/// Get the items of `backup` whose key is not found in `current`
+fn missing<'a, T, K, F>(backup: &'a [T], current: &[T], key: F) -> Vec<&'a T>
+where
+    K: Eq + Hash,
+    F: Fn(&T) -> K,
+{
+    let current = current.iter().map(&key).collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    backup
+        .iter()
+        .filter(|item| {
+            let key = key(item);
+            !current.contains(&key) && seen.insert(key)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspotify::model::{ArtistId, PlaylistId, TrackId};
+
+    const IDS: [&str; 3] = [
+        "4uLU6hMCjMI75M1A2tKUQC",
+        "0eGsygTp906u18L0Oimnem",
+        "1301WleyT98MSxVHPZCA6M",
+    ];
+
+    fn track(n: usize, added_at: u64) -> Track {
+        Track {
+            id: Some(TrackId::from_id(IDS[n]).unwrap()),
+            name: format!("track {n}"),
+            artists: vec![],
+            album: None,
+            duration: std::time::Duration::from_mins(1),
+            explicit: false,
+            added_at,
+            isrc: None,
+            kind: crate::state::TrackKind::Track,
+        }
+    }
+
+    fn artist(n: usize) -> Artist {
+        Artist {
+            id: ArtistId::from_id(IDS[n]).unwrap(),
+            name: format!("artist {n}"),
+        }
+    }
+
+    fn playlist(n: usize, tracks: Vec<Track>) -> PlaylistBackup {
+        PlaylistBackup {
+            playlist: Playlist {
+                id: PlaylistId::from_id(IDS[n]).unwrap(),
+                collaborative: false,
+                name: format!("playlist {n}"),
+                owner: (String::new(), UserId::from_id("user").unwrap()),
+                desc: String::new(),
+                current_folder_id: 0,
+                snapshot_id: String::new(),
+            },
+            tracks,
+        }
+    }
+
+    fn backup(
+        saved_tracks: Vec<Track>,
+        followed_artists: Vec<Artist>,
+        playlists: Vec<PlaylistBackup>,
+    ) -> LibraryBackup {
+        LibraryBackup {
+            version: BACKUP_VERSION,
+            created_at: String::new(),
+            user_id: UserId::from_id("user").unwrap(),
+            saved_tracks,
+            saved_albums: vec![],
+            saved_shows: vec![],
+            followed_artists,
+            playlists,
+        }
+    }
+
+    #[test]
+    fn missing_skips_existing_and_duplicate_items() {
+        let backup = [track(0, 0), track(1, 0), track(1, 0), track(2, 0)];
+        let current = [track(2, 0)];
+
+        let names = missing(&backup, &current, Track::uri)
+            .into_iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["track 0", "track 1"]);
+    }
+
+    #[test]
+    fn diff_library() {
+        // liked tracks are sorted newest first
+        let old = backup(
+            vec![track(0, 300), track(1, 200), track(2, 100)],
+            vec![artist(0), artist(1)],
+            vec![playlist(0, vec![]), playlist(1, vec![])],
+        );
+        let current = backup(
+            vec![track(1, 400)],
+            vec![artist(1)],
+            vec![playlist(1, vec![])],
+        );
+
+        let diff = old.diff(&current);
+        let liked = diff
+            .saved_tracks
+            .iter()
+            .map(|t| t.added_at)
+            .collect::<Vec<_>>();
+        assert_eq!(liked, [100, 300]);
+        assert_eq!(diff.followed_artists.len(), 1);
+        assert_eq!(diff.followed_artists[0].id, artist(0).id);
+        assert_eq!(diff.playlists.len(), 1);
+        assert_eq!(
+            diff.playlists[0].playlist.id,
+            playlist(0, vec![]).playlist.id
+        );
+    }
+
+    #[test]
+    fn playlist_missing_tracks() {
+        let p = playlist(0, vec![track(0, 0), track(1, 0)]);
+        let missing = p.missing_tracks(&[track(0, 0)]);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].id, track(1, 0).id);
+    }
+
+    #[test]
+    fn liked_dates_are_kept() {
+        let data = serde_json::to_string(&backup(vec![track(0, 100)], vec![], vec![])).unwrap();
+        let backup: LibraryBackup = serde_json::from_str(&data).unwrap();
+        assert_eq!(backup.saved_tracks[0].added_at, 100);
+    }
+
+    #[test]
+    fn playlist_local_files_are_kept() {
+        let local = Track {
+            id: None,
+            kind: crate::state::TrackKind::Local {
+                uri: "spotify:local:artist:album:track:60".to_string(),
+                artists: "artist".to_string(),
+                album: "album".to_string(),
+            },
+            ..track(2, 0)
+        };
+        let p = playlist(0, vec![track(0, 0), local.clone()]);
+        let data = serde_json::to_string(&backup(vec![], vec![], vec![p])).unwrap();
+        let backup: LibraryBackup = serde_json::from_str(&data).unwrap();
+
+        let missing = backup.playlists[0].missing_tracks(&[track(0, 0)]);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].kind, local.kind);
+        // local files are reported as skipped when restoring
+        assert!(missing[0].playable_id().is_none());
+    }
+}
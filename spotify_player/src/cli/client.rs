@@ -1,46 +1,59 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::Write as _,
     fs::{create_dir_all, remove_dir_all},
     io::Write,
-    net::SocketAddr,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context as _, Result};
 use rand::seq::SliceRandom;
-use tokio::net::UdpSocket;
 use tracing::Instrument;
 
 use crate::{
+    backup::{LibraryBackup, PlaylistBackup, BACKUP_VERSION},
     cli::Request,
     client::{AppClient, PlayerRequest},
-    config::{self, get_cache_folder_path},
+    config::{self, get_cache_folder_path, TokenScope},
+    export,
+    import::{self, ImportEntry, ImportReport},
+    playlist_edit::{self, SetOperation},
     state::{
-        AlbumId, ArtistId, Context, ContextId, Id, PlayableId, Playback, PlaybackMetadata,
-        PlaylistId, SharedState, TrackId,
+        AlbumId, ArtistId, Context, ContextId, CustomQueue, Episode, EpisodeId, Id, PlayableId,
+        Playback, PlaybackMetadata, PlayerSnapshot, Playlist, PlaylistId, SharedState, ShowId,
+        StateEvent, TimeRange, Track, TrackId, TrackKind, UserId,
     },
 };
-use rspotify::prelude::{BaseClient, OAuthClient};
+use rspotify::{
+    model::PlayableItem,
+    prelude::{BaseClient, OAuthClient},
+};
+
+#[cfg(feature = "daemon")]
+use crate::{
+    alarm::{Alarm, AlarmContext, StoredAlarm},
+    config::MissedAlarmPolicy,
+};
 
 use super::{
-    Command, Deserialize, EditAction, GetRequest, IdOrName, ItemId, ItemType, Key, PlaylistCommand,
-    Response, Serialize, MAX_REQUEST_SIZE,
+    completion,
+    socket::{self, AsyncStream, Listener},
+    Command, CompletionKind, Deserialize, EditAction, GetRequest, IdOrName, ItemId, ItemType, Key,
+    LibraryCommand, PlaylistCommand, QueueCommand, QueueItemType, Response, Serialize,
 };
+#[cfg(feature = "daemon")]
+use super::{AlarmCommand, AlarmContextType, ContextType};
 
 pub async fn start_socket(
-    client: &AppClient,
-    state: Option<&SharedState>,
-    socket: Option<tokio::net::UdpSocket>,
+    client: AppClient,
+    state: Option<SharedState>,
+    listener: Option<Listener>,
 ) {
-    let socket = if let Some(s) = socket {
-        s
+    let listener = if let Some(l) = listener {
+        l
     } else {
-        let configs = config::get_config();
-        let port = configs.app_config.client_port;
-        tracing::info!("Starting a client socket at 127.0.0.1:{port}");
-
-        match tokio::net::UdpSocket::bind(("127.0.0.1", port)).await {
-            Ok(socket) => socket,
+        match Listener::bind(config::get_config()).await {
+            Ok(listener) => listener,
             Err(err) => {
                 tracing::warn!(
                     "Failed to create a client socket for handling CLI commands: {err:#}"
@@ -49,66 +62,177 @@ pub async fn start_socket(
             }
         }
     };
+    accept_connections(client, state, listener, false).await;
+}
 
-    let mut buf = [0; MAX_REQUEST_SIZE];
+/// Handle CLI commands from other machines on `client_listen_address`, if configured
+pub async fn start_remote_socket(client: AppClient, state: Option<SharedState>) {
+    let app_config = &config::get_config().app_config;
+    let Some(ref addr) = app_config.client_listen_address else {
+        return;
+    };
+    if app_config.client_tokens.is_empty() {
+        tracing::warn!(
+            "No `client_tokens` configured, not starting the remote client socket at {addr}"
+        );
+        return;
+    }
 
-    loop {
-        match socket.recv_from(&mut buf).await {
-            Err(err) => tracing::warn!("Failed to receive from the socket: {err:#}"),
-            Ok((n_bytes, dest_addr)) => {
-                if n_bytes == 0 {
-                    // received a connection request from the destination address
-                    socket.send_to(&[], dest_addr).await.unwrap_or_default();
-                    continue;
-                }
+    match Listener::bind_remote(addr).await {
+        Ok(listener) => accept_connections(client, state, listener, true).await,
+        Err(err) => {
+            tracing::warn!("Failed to create a remote client socket at {addr}: {err:#}");
+        }
+    }
+}
 
-                let req_buf = &buf[0..n_bytes];
-                let request: Request = match serde_json::from_slice(req_buf) {
-                    Ok(v) => v,
-                    Err(err) => {
-                        tracing::error!("Cannot deserialize the socket request: {err:#}");
-                        continue;
+async fn accept_connections(
+    client: AppClient,
+    state: Option<SharedState>,
+    listener: Listener,
+    remote: bool,
+) {
+    loop {
+        match listener.accept().await {
+            Err(err) => tracing::warn!("Failed to accept a socket connection: {err:#}"),
+            Ok(stream) => {
+                // each connection is handled separately so that a slow request
+                // doesn't block requests from other connections
+                tokio::task::spawn({
+                    let client = client.clone();
+                    let state = state.clone();
+                    async move {
+                        if let Err(err) =
+                            handle_connection(&client, state.as_ref(), stream, remote).await
+                        {
+                            tracing::warn!("Failed to handle a socket connection: {err:#}");
+                        }
                     }
-                };
+                });
+            }
+        }
+    }
+}
 
-                let span = tracing::info_span!("socket_request", request = ?request, dest_addr = ?dest_addr);
+/// Handle the requests of a socket connection.
+///
+/// Requests of a `remote` connection are authorized by the token sent in the handshake.
+/// The remote socket is a plain TCP socket, so both the token and the requests travel unencrypted.
+async fn handle_connection(
+    client: &AppClient,
+    state: Option<&SharedState>,
+    mut stream: Box<dyn AsyncStream>,
+    remote: bool,
+) -> Result<()> {
+    let token = socket::accept_handshake(&mut stream).await?;
 
-                async {
-                    let response = match handle_socket_request(client, state, request).await {
-                        Err(err) => {
-                            tracing::error!("Failed to handle socket request: {err:#}");
-                            let msg = format!("Bad request: {err:#}");
-                            Response::Err(msg.into_bytes())
-                        }
-                        Ok(data) => Response::Ok(data),
-                    };
-                    send_response(response, &socket, dest_addr)
-                        .await
-                        .unwrap_or_default();
+    // connections to the local socket are trusted,
+    // remote ones can only send requests allowed by their token's scope
+    let scope = if remote {
+        token.and_then(|t| config::get_config().app_config.client_token_scope(&t))
+    } else {
+        Some(TokenScope::Library)
+    };
+    let max_frame_size = if scope.is_some() {
+        socket::MAX_FRAME_SIZE
+    } else {
+        socket::MAX_UNAUTHORIZED_FRAME_SIZE
+    };
 
-                    tracing::info!("Successfully handled the socket request.",);
-                }
-                .instrument(span)
-                .await;
+    while let Some(data) = socket::read_frame(&mut stream, max_frame_size).await? {
+        let request: Request = match serde_json::from_slice(&data) {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::error!("Cannot deserialize the socket request: {err:#}");
+                let msg = format!("Bad request: cannot deserialize the request: {err:#}");
+                socket::write_message(&mut stream, &Response::Err(msg.into_bytes())).await?;
+                continue;
             }
+        };
+
+        let required_scope = request.required_scope();
+        if scope.is_none_or(|scope| scope < required_scope) {
+            tracing::warn!("Rejected an unauthorized socket request: {request:?}");
+            let msg = match scope {
+                None => "Unauthorized: missing or invalid token".to_string(),
+                Some(scope) => format!(
+                    "Unauthorized: the request requires the {required_scope:?} scope, the token only has the {scope:?} scope"
+                ),
+            };
+            socket::write_message(&mut stream, &Response::Err(msg.into_bytes())).await?;
+            // close the connection so that a peer can't keep guessing tokens or scopes through it
+            return Ok(());
+        }
+
+        if let Request::Subscribe = request {
+            return handle_subscription(state, stream).await;
         }
+
+        let span = tracing::info_span!("socket_request", request = ?request);
+
+        async {
+            let response = match handle_socket_request(client, state, request).await {
+                Err(err) => {
+                    tracing::error!("Failed to handle socket request: {err:#}");
+                    let msg = format!("Bad request: {err:#}");
+                    Response::Err(msg.into_bytes())
+                }
+                Ok(data) => Response::Ok(data),
+            };
+            socket::write_message(&mut stream, &response).await?;
+
+            tracing::info!("Successfully handled the socket request.");
+            anyhow::Ok(())
+        }
+        .instrument(span)
+        .await?;
     }
+
+    Ok(())
 }
 
-async fn send_response(
-    response: Response,
-    socket: &UdpSocket,
-    dest_addr: SocketAddr,
+/// Stream the application's state events to a subscribed connection until the peer disconnects
+async fn handle_subscription(
+    state: Option<&SharedState>,
+    mut stream: Box<dyn AsyncStream>,
 ) -> Result<()> {
-    let data = serde_json::to_vec(&response)?;
+    let Some(state) = state else {
+        let msg = "Bad request: subscribing to events requires a running `spotify_player` instance";
+        socket::write_message(&mut stream, &Response::Err(msg.as_bytes().to_vec())).await?;
+        return Ok(());
+    };
 
-    // as the result data can be large and may not be sent in a single UDP datagram,
-    // split it into smaller chunks
-    for chunk in data.chunks(4096) {
-        socket.send_to(chunk, dest_addr).await?;
+    tracing::info!("New subscriber connected to the client socket");
+
+    let events = state.subscribers.subscribe();
+    // start with the events describing the current player's state
+    let initial_events = PlayerSnapshot::default().changes(&state.player.read().snapshot());
+
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let write_events = async move {
+        for event in initial_events {
+            let data = serde_json::to_vec(&event)?;
+            socket::write_message(&mut writer, &Response::Ok(data)).await?;
+        }
+        while let Ok(event) = events.recv_async().await {
+            let data = serde_json::to_vec(&event)?;
+            socket::write_message(&mut writer, &Response::Ok(data)).await?;
+        }
+        anyhow::Ok(())
+    };
+
+    // the subscriber isn't expected to send anything,
+    // so reading from the connection only returns once the subscriber has disconnected
+    tokio::select! {
+        _ = socket::read_frame(&mut reader, socket::MAX_FRAME_SIZE) => {}
+        result = write_events => {
+            if let Err(err) = result {
+                tracing::debug!("Stopped writing events to the subscriber: {err:#}");
+            }
+        }
     }
-    // send an empty buffer to indicate end of chunk
-    socket.send_to(&[], dest_addr).await?;
+
+    tracing::info!("Subscriber disconnected from the client socket");
     Ok(())
 }
 
@@ -132,7 +256,9 @@ async fn handle_socket_request(
     request: super::Request,
 ) -> Result<Vec<u8>> {
     match request {
-        Request::Get(GetRequest::Key(key)) => handle_get_key_request(client, state, key).await,
+        Request::Get(GetRequest::Key(key, time_range)) => {
+            handle_get_key_request(client, state, key, time_range.unwrap_or_default()).await
+        }
         Request::Get(GetRequest::Item(item_type, id_or_name)) => {
             handle_get_item_request(client, item_type, id_or_name).await
         }
@@ -174,17 +300,34 @@ async fn handle_socket_request(
             };
 
             if let Some(id) = track.and_then(|t| t.id.clone()) {
+                let uri = id.uri();
                 if unlike {
                     client.current_user_saved_tracks_delete([id]).await?;
                 } else {
                     client.current_user_saved_tracks_add([id]).await?;
                 }
+                if let Some(state) = state {
+                    state.subscribers.publish(&[StateEvent::LikeToggled {
+                        uri,
+                        liked: !unlike,
+                    }]);
+                }
             }
 
             Ok(Vec::new())
         }
         Request::Playlist(command) => {
-            let resp = handle_playlist_request(client, command).await?;
+            let resp = handle_playlist_request(client, state, command).await?;
+            Ok(resp.into_bytes())
+        }
+        Request::Queue(command) => {
+            let resp = handle_queue_request(client, state, command).await?;
+            Ok(resp.into_bytes())
+        }
+        Request::Library(command) => handle_library_request(client, command).await,
+        #[cfg(feature = "daemon")]
+        Request::Alarm(command) => {
+            let resp = handle_alarm_request(client, command).await?;
             Ok(resp.into_bytes())
         }
         Request::Search { query } => {
@@ -192,6 +335,8 @@ async fn handle_socket_request(
             Ok(resp)
         }
         Request::Lyrics { id_or_name } => handle_lyrics_request(client, state, id_or_name).await,
+        Request::Complete(kind) => handle_complete_request(client, state, kind).await,
+        Request::Subscribe => anyhow::bail!("subscription requests must be handled separately"),
     }
 }
 
@@ -199,6 +344,7 @@ async fn handle_get_key_request(
     client: &AppClient,
     state: Option<&SharedState>,
     key: Key,
+    time_range: TimeRange,
 ) -> Result<Vec<u8>> {
     Ok(match key {
         Key::Playback => {
@@ -218,9 +364,13 @@ async fn handle_get_key_request(
             serde_json::to_vec(&tracks)?
         }
         Key::UserTopTracks => {
-            let tracks = client.current_user_top_tracks().await?;
+            let tracks = client.current_user_top_tracks(time_range).await?;
             serde_json::to_vec(&tracks)?
         }
+        Key::UserTopArtists => {
+            let artists = client.current_user_top_artists(time_range).await?;
+            serde_json::to_vec(&artists)?
+        }
         Key::UserSavedAlbums => {
             let albums = client.current_user_saved_albums().await?;
             serde_json::to_vec(&albums)?
@@ -229,6 +379,14 @@ async fn handle_get_key_request(
             let artists = client.current_user_followed_artists().await?;
             serde_json::to_vec(&artists)?
         }
+        Key::UserSavedShows => {
+            let shows = client.current_user_saved_shows().await?;
+            serde_json::to_vec(&shows)?
+        }
+        Key::UserSavedAudiobooks => {
+            let audiobooks = client.current_user_saved_audiobooks().await?;
+            serde_json::to_vec(&audiobooks)?
+        }
         Key::Queue => {
             let queue = client.current_user_queue().await?;
             serde_json::to_vec(&queue)?
@@ -316,6 +474,42 @@ async fn get_spotify_id(client: &AppClient, typ: ItemType, id_or_name: IdOrName)
                 }
             }
         },
+        ItemType::Show => match id_or_name {
+            IdOrName::Id(id) => ItemId::Show(ShowId::from_id(id)?),
+            IdOrName::Name(name) => {
+                let results = client
+                    .search_specific_type(&name, rspotify::model::SearchType::Show)
+                    .await?;
+
+                match results {
+                    rspotify::model::SearchResult::Shows(page) => {
+                        if page.items.is_empty() {
+                            anyhow::bail!("Cannot find show with name='{name}'");
+                        }
+                        ItemId::Show(page.items[0].id.clone())
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        },
+        ItemType::Episode => match id_or_name {
+            IdOrName::Id(id) => ItemId::Episode(EpisodeId::from_id(id)?),
+            IdOrName::Name(name) => {
+                let results = client
+                    .search_specific_type(&name, rspotify::model::SearchType::Episode)
+                    .await?;
+
+                match results {
+                    rspotify::model::SearchResult::Episodes(page) => {
+                        if page.items.is_empty() {
+                            anyhow::bail!("Cannot find episode with name='{name}'");
+                        }
+                        ItemId::Episode(page.items[0].id.clone())
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        },
     };
 
     Ok(sid)
@@ -332,9 +526,39 @@ async fn handle_get_item_request(
         ItemId::Album(id) => serde_json::to_vec(&client.album_context(id).await?)?,
         ItemId::Artist(id) => serde_json::to_vec(&client.artist_context(id).await?)?,
         ItemId::Track(id) => serde_json::to_vec(&client.track(id).await?)?,
+        ItemId::Show(id) => serde_json::to_vec(&client.show_context(id).await?)?,
+        ItemId::Episode(id) => {
+            let episode: Episode = client.get_an_episode(id, None).await?.into();
+            serde_json::to_vec(&episode)?
+        }
     })
 }
 
+async fn handle_complete_request(
+    client: &AppClient,
+    state: Option<&SharedState>,
+    kind: CompletionKind,
+) -> Result<Vec<u8>> {
+    let candidates = if kind == CompletionKind::Devices {
+        client
+            .available_devices()
+            .await?
+            .into_iter()
+            .map(|d| d.name)
+            .collect()
+    } else {
+        let state = state.context("no running application to get completion candidates from")?;
+        let data = state.data.read();
+        completion::library_candidates(
+            kind,
+            &data.user_data.playlists,
+            &data.user_data.followed_artists,
+            &data.user_data.saved_albums,
+        )
+    };
+    Ok(serde_json::to_vec(&candidates)?)
+}
+
 async fn handle_search_request(client: &AppClient, query: String) -> Result<Vec<u8>> {
     let search_result = client.search(&query).await?;
 
@@ -356,10 +580,13 @@ async fn handle_playback_request(
     let player_request = match command {
         Command::StartRadio(item_type, id_or_name) => {
             let sid = get_spotify_id(client, item_type, id_or_name).await?;
+            if let ItemId::Show(_) | ItemId::Episode(_) = sid {
+                anyhow::bail!("Radio playback is not supported for shows and episodes");
+            }
             let tracks = client.radio_tracks(sid.uri()).await?;
 
             PlayerRequest::StartPlayback(
-                Playback::URIs(tracks.into_iter().map(|t| t.id.into()).collect(), None),
+                Playback::URIs(tracks.iter().filter_map(Track::playable_id).collect(), None),
                 None,
             )
         }
@@ -372,14 +599,14 @@ async fn handle_playback_request(
                     .user_data
                     .saved_tracks
                     .values()
-                    .map(|t| t.id.clone().into())
+                    .filter_map(Track::playable_id)
                     .collect()
             } else {
                 client
                     .current_user_saved_tracks()
                     .await?
-                    .into_iter()
-                    .map(|t| t.id.into())
+                    .iter()
+                    .filter_map(Track::playable_id)
                     .collect()
             };
 
@@ -396,16 +623,33 @@ async fn handle_playback_request(
             context_type,
             id_or_name,
             shuffle,
+            episode,
         } => {
             let sid = get_spotify_id(client, context_type.into(), id_or_name).await?;
             let context_id = match sid {
                 ItemId::Playlist(id) => ContextId::Playlist(id),
                 ItemId::Album(id) => ContextId::Album(id),
                 ItemId::Artist(id) => ContextId::Artist(id),
-                ItemId::Track(_) => unreachable!(),
+                ItemId::Show(id) => ContextId::Show(id),
+                ItemId::Track(_) | ItemId::Episode(_) => unreachable!(),
+            };
+
+            let offset = match episode {
+                None => None,
+                Some(_) if !matches!(context_id, ContextId::Show(_)) => {
+                    anyhow::bail!("A starting episode can only be specified for a show context")
+                }
+                Some(episode) => {
+                    let ItemId::Episode(id) =
+                        get_spotify_id(client, ItemType::Episode, episode).await?
+                    else {
+                        anyhow::bail!("Unable to get episode id")
+                    };
+                    Some(rspotify::model::Offset::Uri(id.uri()))
+                }
             };
 
-            PlayerRequest::StartPlayback(Playback::Context(context_id, None), Some(shuffle))
+            PlayerRequest::StartPlayback(Playback::Context(context_id, offset), Some(shuffle))
         }
         Command::StartTrack(id_or_name) => {
             let ItemId::Track(id) = get_spotify_id(client, ItemType::Track, id_or_name).await?
@@ -414,7 +658,18 @@ async fn handle_playback_request(
             };
 
             let track = client.track(id).await?;
-            PlayerRequest::StartPlayback(Playback::URIs(vec![track.id.into()], None), None)
+            PlayerRequest::StartPlayback(
+                Playback::URIs(track.playable_id().into_iter().collect(), None),
+                None,
+            )
+        }
+        Command::StartEpisode(id_or_name) => {
+            let ItemId::Episode(id) = get_spotify_id(client, ItemType::Episode, id_or_name).await?
+            else {
+                anyhow::bail!("Unable to get episode id")
+            };
+
+            PlayerRequest::StartPlayback(Playback::URIs(vec![id.into()], None), None)
         }
         Command::PlayPause => PlayerRequest::ResumePause,
         Command::Play => PlayerRequest::Resume,
@@ -436,6 +691,17 @@ async fn handle_playback_request(
             };
             PlayerRequest::Volume(percent.try_into()?)
         }
+        Command::Sleep { mode, fade_secs } => {
+            let state = state.context("sleep timer requires a running application instance")?;
+            let fade = std::time::Duration::from_secs(
+                fade_secs.unwrap_or(config::get_config().app_config.sleep_timer_fade_secs),
+            );
+            if let Some(volume) = state.update_player(|player| player.set_sleep_timer(mode, fade)) {
+                PlayerRequest::Volume(volume)
+            } else {
+                return Ok(());
+            }
+        }
         Command::Seek(position_offset_ms) => {
             // Playback's progress cannot be computed trivially without knowing the `playback` variable in
             // the function scope is from the application's state (cached) or the `current_playback` API.
@@ -463,7 +729,7 @@ async fn handle_playback_request(
                 match client.handle_player_request(player_request, playback).await {
                     Ok(playback) => {
                         // update application's states
-                        state.player.write().buffered_playback = playback;
+                        state.update_player(|player| player.buffered_playback = playback);
                         client.update_playback(&state);
                     }
                     Err(err) => {
@@ -483,7 +749,11 @@ async fn handle_playback_request(
     Ok(())
 }
 
-async fn handle_playlist_request(client: &AppClient, command: PlaylistCommand) -> Result<String> {
+async fn handle_playlist_request(
+    client: &AppClient,
+    state: Option<&SharedState>,
+    command: PlaylistCommand,
+) -> Result<String> {
     let uid = client.current_user().await?.id;
 
     match command {
@@ -570,48 +840,61 @@ async fn handle_playlist_request(client: &AppClient, command: PlaylistCommand) -
             Ok(result)
         }
         PlaylistCommand::Sync { id, delete } => {
-            // Get import dir/file
-            let imports_dir = get_cache_folder_path()?.join("imports");
-
             let mut result = String::new();
 
-            // Iterate through the playlist `imports` folder in the cache folder to
-            // get all playlists' import data represented as subdirectories with `import_to` name.
-            // Inside each `import_to` subdirectory, an import `import_from -> import_to`
-            // data is represented as a file with `import_from` name.
-            for dir in imports_dir.read_dir()? {
-                let to_dir = dir?.path();
-                let to_id = PlaylistId::from_id(to_dir.file_name().unwrap().to_str().unwrap())?;
-
-                // If a playlist id is specified, only consider sync imports of that playlist
-                if let Some(id) = &id {
-                    if to_id != *id {
-                        continue;
-                    }
-                }
-
-                let pl_follow = client
-                    .playlist_check_follow(to_id.as_ref(), &[uid.as_ref()])
-                    .await?
-                    .pop()
-                    .unwrap();
-
-                if pl_follow {
-                    for i in to_dir.read_dir()? {
-                        let from_id =
-                            PlaylistId::from_id(i?.file_name().to_str().unwrap().to_owned())?;
-                        result +=
-                            &playlist_import(client, from_id, to_id.clone_static(), delete).await?;
-                        result += "\n";
-                    }
-                } else {
-                    remove_dir_all(&to_dir)?;
-                    writeln!(result, "Not following playlist '{}'. Deleted its import data in the cache folder...", to_id.id()).unwrap();
-                }
+            // If a playlist id is specified, only consider sync imports of that playlist
+            for (to_id, to_dir) in playlist_import_dirs(id.as_slice())? {
+                result += &playlist_sync(client, &uid, to_id, &to_dir, delete).await?;
             }
 
             Ok(result)
         }
+        PlaylistCommand::Dedupe { id, by, dry_run } => {
+            let (playlist, duplicates) = client.dedupe_playlist(state, id, by, dry_run).await?;
+            if duplicates.is_empty() {
+                return Ok(format!(
+                    "No duplicates found in playlist '{}'",
+                    playlist.name
+                ));
+            }
+
+            let mut out = format!(
+                "{} {} duplicates from playlist '{}':\n",
+                if dry_run { "Would delete" } else { "Deleted" },
+                duplicates.len(),
+                playlist.name
+            );
+            for (pos, track) in duplicates {
+                writeln!(out, "{}: {track}", pos + 1).unwrap();
+            }
+            Ok(out.trim().to_string())
+        }
+        PlaylistCommand::Sort { id, by, reverse } => {
+            let (playlist, moves) = client.sort_playlist(state, id, by, reverse).await?;
+            Ok(format!(
+                "Sorted playlist '{}' by {by:?} with {moves} moves",
+                playlist.name
+            ))
+        }
+        PlaylistCommand::Combine {
+            op,
+            playlists,
+            into,
+            preserve_order,
+            dedupe,
+        } => playlist_combine(client, op, playlists, &into, preserve_order, dedupe).await,
+        PlaylistCommand::ImportFile {
+            to,
+            entries,
+            accept_low_confidence,
+        } => {
+            let report = playlist_import_file(client, to, entries, accept_low_confidence).await?;
+            Ok(serde_json::to_string(&report)?)
+        }
+        PlaylistCommand::Export { source, format } => {
+            let (name, tracks) = client.export_source_tracks(source, state).await?;
+            export::export_tracks(&name, &tracks, format)
+        }
         PlaylistCommand::Edit {
             playlist_id,
             action,
@@ -708,6 +991,844 @@ async fn handle_playlist_request(client: &AppClient, command: PlaylistCommand) -
 
 const TRACK_BUFFER_CAP: usize = 100;
 
+/// Import the entries of a file into a playlist, matching each entry
+/// to the best scored track of its search results
+async fn playlist_import_file(
+    client: &AppClient,
+    import_to: PlaylistId<'static>,
+    entries: Vec<ImportEntry>,
+    accept_low_confidence: bool,
+) -> Result<ImportReport> {
+    let (playlist, tracks) = playlist_tracks(client, import_to.as_ref()).await?;
+    // URIs of the playlist's tracks and of the tracks matched so far, used to skip duplicates
+    let (mut seen_uris, playlist_name) = (
+        tracks.iter().map(Track::uri).collect::<HashSet<_>>(),
+        playlist.name,
+    );
+
+    // entries specifying a Spotify track are matched to that track directly
+    let direct_tracks = client
+        .full_tracks(entries.iter().filter_map(|e| e.id.clone()).collect())
+        .await?
+        .into_iter()
+        .filter_map(|t| Some((t.id.clone()?, t)))
+        .collect::<HashMap<_, _>>();
+
+    let mut report = ImportReport {
+        playlist_name,
+        ..Default::default()
+    };
+    let mut new_ids = vec![];
+    for entry in entries {
+        let best = match entry.id {
+            Some(ref id) => direct_tracks.get(id).map(|t| (1.0, t.clone())),
+            None => entry.best_match(client.search_tracks(&entry.query()).await?),
+        };
+
+        match best {
+            Some((score, track))
+                if score >= import::CONFIDENT_SCORE
+                    || (accept_low_confidence && score >= import::MIN_SCORE) =>
+            {
+                let matched = entry.matched(score, &track);
+                if seen_uris.insert(track.uri()) {
+                    new_ids.extend(track.playable_id());
+                    report.added.push(matched);
+                } else {
+                    report.duplicates.push(matched);
+                }
+            }
+            Some((score, track)) if score >= import::MIN_SCORE => {
+                report.low_confidence.push(entry.matched(score, &track));
+            }
+            _ => report.missing.push(entry.line),
+        }
+    }
+
+    client
+        .add_items_to_playlist(None, import_to.as_ref(), &new_ids)
+        .await?;
+
+    Ok(report)
+}
+
+/// Combine the tracks of playlists with a set operation and add the result to the `into` playlist.
+///
+/// `into` is matched against the IDs and names of the user's playlists,
+/// a new playlist named `into` is created if none matches.
+async fn playlist_combine(
+    client: &AppClient,
+    op: SetOperation,
+    playlist_ids: Vec<PlaylistId<'static>>,
+    into: &str,
+    preserve_order: bool,
+    dedupe: bool,
+) -> Result<String> {
+    let mut playlists = Vec::with_capacity(playlist_ids.len());
+    for id in playlist_ids {
+        let (_, tracks) = playlist_tracks(client, id.as_ref())
+            .await
+            .context(format!("Could not find playlist '{}'", id.id()))?;
+        playlists.push(tracks);
+    }
+    let mut tracks = playlist_edit::combine(op, &playlists, preserve_order, dedupe);
+
+    let user = client.current_user().await?;
+    let into_id = PlaylistId::from_id_or_uri(into).ok();
+    let target = client
+        .current_user_playlists()
+        .await?
+        .into_iter()
+        .find(|p| p.owner.1 == user.id && (Some(&p.id) == into_id.as_ref() || p.name == into));
+
+    let (target_id, target_name) = if let Some(p) = target {
+        if dedupe {
+            let existing = playlist_tracks(client, p.id.as_ref())
+                .await?
+                .1
+                .iter()
+                .map(Track::uri)
+                .collect::<HashSet<_>>();
+            tracks.retain(|t| !existing.contains(&t.uri()));
+        }
+        (p.id, p.name)
+    } else {
+        let p = client
+            .user_playlist_create(user.id, into, Some(false), Some(false), None)
+            .await?;
+        (p.id, p.name)
+    };
+
+    let track_ids = tracks
+        .iter()
+        .filter_map(Track::playable_id)
+        .collect::<Vec<_>>();
+    client
+        .add_items_to_playlist(None, target_id.as_ref(), &track_ids)
+        .await?;
+
+    Ok(format!(
+        "Added {} tracks to playlist '{target_name}' with id '{}'",
+        track_ids.len(),
+        target_id.id()
+    ))
+}
+
+/// Get a playlist and all its entries, including episodes and local files
+async fn playlist_entries(
+    client: &AppClient,
+    playlist_id: PlaylistId<'_>,
+) -> Result<(Playlist, Vec<Track>)> {
+    match client.playlist_context(playlist_id).await? {
+        Context::Playlist { playlist, tracks } => Ok((playlist, tracks)),
+        _ => unreachable!(),
+    }
+}
+
+/// Get a playlist and its tracks, leaving out the playlist's episodes and local files
+async fn playlist_tracks(
+    client: &AppClient,
+    playlist_id: PlaylistId<'_>,
+) -> Result<(Playlist, Vec<Track>)> {
+    let (playlist, mut tracks) = playlist_entries(client, playlist_id).await?;
+    tracks.retain(|t| t.kind == TrackKind::Track);
+    Ok((playlist, tracks))
+}
+
+/// Report the entries of a backed up playlist that can't be restored,
+/// i.e. local files, which can't be added to a playlist through the API
+fn write_skipped_entries(out: &mut String, playlist_name: &str, skipped: usize) {
+    if skipped > 0 {
+        writeln!(
+            out,
+            "Skipped {skipped} local files of playlist '{playlist_name}': they can't be restored"
+        )
+        .unwrap();
+    }
+}
+
+/// Check if an error is caused by a Spotify API response with the `404 Not Found` status
+fn is_not_found_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<rspotify::ClientError>() {
+        Some(rspotify::ClientError::Http(err)) => matches!(
+            err.as_ref(),
+            rspotify::http::HttpError::StatusCode(response)
+                if response.status() == reqwest::StatusCode::NOT_FOUND
+        ),
+        _ => false,
+    }
+}
+
+async fn handle_library_request(client: &AppClient, command: LibraryCommand) -> Result<Vec<u8>> {
+    match command {
+        LibraryCommand::Backup => {
+            let backup = library_backup(client, true).await?;
+            Ok(serde_json::to_vec_pretty(&backup)?)
+        }
+        LibraryCommand::Restore { backup, dry_run } => {
+            let resp = library_restore(client, &backup, dry_run).await?;
+            Ok(resp.into_bytes())
+        }
+    }
+}
+
+/// Build a snapshot of the user's library, including the playlists' tracks if `with_tracks` is set
+async fn library_backup(client: &AppClient, with_tracks: bool) -> Result<LibraryBackup> {
+    let user_id = client.current_user().await?.id;
+    let (saved_tracks, saved_albums, saved_shows, followed_artists, playlists) = tokio::try_join!(
+        client.current_user_saved_tracks(),
+        client.current_user_saved_albums(),
+        client.current_user_saved_shows(),
+        client.current_user_followed_artists(),
+        client.current_user_playlists(),
+    )?;
+
+    let mut playlist_backups = Vec::with_capacity(playlists.len());
+    for playlist in playlists {
+        let tracks = if with_tracks {
+            playlist_entries(client, playlist.id.as_ref()).await?.1
+        } else {
+            vec![]
+        };
+        playlist_backups.push(PlaylistBackup { playlist, tracks });
+    }
+
+    Ok(LibraryBackup {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        user_id,
+        saved_tracks,
+        saved_albums,
+        saved_shows,
+        followed_artists,
+        playlists: playlist_backups,
+    })
+}
+
+/// Restore the items of a backup that are missing from the user's library.
+///
+/// Existing items are never removed. Playlists that are no longer followed are followed again
+/// (or recreated if they were owned by the user and no longer exist), and missing tracks
+/// are appended to the user's own playlists.
+async fn library_restore(
+    client: &AppClient,
+    backup: &LibraryBackup,
+    dry_run: bool,
+) -> Result<String> {
+    backup.validate()?;
+    let current = library_backup(client, false).await?;
+    let diff = backup.diff(&current);
+
+    let verb = |done: &'static str, todo: &'static str| if dry_run { todo } else { done };
+    let mut out = String::new();
+    let mut changes = 0;
+
+    if !dry_run {
+        for chunk in diff.saved_tracks.chunks(50) {
+            client
+                .current_user_saved_tracks_add(
+                    chunk.iter().filter_map(|t| Some(t.id.as_ref()?.as_ref())),
+                )
+                .await?;
+        }
+        for chunk in diff.saved_albums.chunks(20) {
+            client
+                .current_user_saved_albums_add(chunk.iter().map(|a| a.id.as_ref()))
+                .await?;
+        }
+        for chunk in diff.saved_shows.chunks(50) {
+            client
+                .save_shows(chunk.iter().map(|s| s.id.as_ref()))
+                .await?;
+        }
+        for chunk in diff.followed_artists.chunks(50) {
+            client
+                .user_follow_artists(chunk.iter().map(|a| a.id.as_ref()))
+                .await?;
+        }
+    }
+    for (count, done, todo, items) in [
+        (diff.saved_tracks.len(), "Liked", "Would like", "tracks"),
+        (diff.saved_albums.len(), "Saved", "Would save", "albums"),
+        (diff.saved_shows.len(), "Saved", "Would save", "shows"),
+        (
+            diff.followed_artists.len(),
+            "Followed",
+            "Would follow",
+            "artists",
+        ),
+    ] {
+        if count > 0 {
+            changes += count;
+            writeln!(out, "{} {count} {items}", verb(done, todo)).unwrap();
+        }
+    }
+
+    for p in &backup.playlists {
+        let name = &p.playlist.name;
+        let owned = p.playlist.owner.1 == current.user_id;
+        let followed = !diff
+            .playlists
+            .iter()
+            .any(|d| d.playlist.id == p.playlist.id);
+        if followed && !owned {
+            continue;
+        }
+
+        // the playlist's current entries, `None` if the playlist no longer exists
+        let current_tracks = match playlist_entries(client, p.playlist.id.as_ref()).await {
+            Ok((_, tracks)) => Some(tracks),
+            Err(err) if is_not_found_error(&err) => None,
+            Err(err) => {
+                return Err(err.context(format!("get playlist {}", p.playlist.id)));
+            }
+        };
+
+        match current_tracks {
+            Some(tracks) => {
+                if !followed {
+                    if !dry_run {
+                        client.playlist_follow(p.playlist.id.as_ref(), None).await?;
+                    }
+                    changes += 1;
+                    writeln!(
+                        out,
+                        "{} playlist '{name}'",
+                        verb("Followed", "Would follow")
+                    )
+                    .unwrap();
+                }
+                if owned {
+                    let missing = p.missing_tracks(&tracks);
+                    let ids = missing
+                        .iter()
+                        .filter_map(|t| t.playable_id())
+                        .collect::<Vec<_>>();
+                    if !ids.is_empty() {
+                        if !dry_run {
+                            client
+                                .add_items_to_playlist(None, p.playlist.id.as_ref(), &ids)
+                                .await?;
+                        }
+                        changes += ids.len();
+                        writeln!(
+                            out,
+                            "{} {} tracks to playlist '{name}'",
+                            verb("Added", "Would add"),
+                            ids.len()
+                        )
+                        .unwrap();
+                    }
+                    write_skipped_entries(&mut out, name, missing.len() - ids.len());
+                }
+            }
+            None if owned => {
+                let ids = p
+                    .tracks
+                    .iter()
+                    .filter_map(Track::playable_id)
+                    .collect::<Vec<_>>();
+                if !dry_run {
+                    let playlist = client
+                        .user_playlist_create(
+                            current.user_id.as_ref(),
+                            name,
+                            None,
+                            Some(p.playlist.collaborative),
+                            Some(&p.playlist.desc),
+                        )
+                        .await?;
+                    client
+                        .add_items_to_playlist(None, playlist.id.as_ref(), &ids)
+                        .await?;
+                }
+                changes += 1;
+                writeln!(
+                    out,
+                    "{} playlist '{name}' with {} tracks",
+                    verb("Recreated", "Would recreate"),
+                    ids.len()
+                )
+                .unwrap();
+                write_skipped_entries(&mut out, name, p.tracks.len() - ids.len());
+            }
+            None => {
+                writeln!(out, "Skipped playlist '{name}': it is no longer available").unwrap();
+            }
+        }
+    }
+
+    if changes == 0 {
+        writeln!(out, "The library already contains all items of the backup").unwrap();
+    } else if dry_run {
+        writeln!(out, "Dry run: no changes were made to the library").unwrap();
+    }
+
+    Ok(out.trim().to_string())
+}
+
+/// Get the playlists' import data stored in the `imports` folder in the cache folder,
+/// only the import data of `ids` playlists if specified.
+///
+/// Each playlist's import data is represented as a subdirectory with `import_to` name.
+/// Inside each `import_to` subdirectory, an import `import_from -> import_to`
+/// data is represented as a file with `import_from` name.
+fn playlist_import_dirs(ids: &[PlaylistId<'_>]) -> Result<Vec<(PlaylistId<'static>, PathBuf)>> {
+    let imports_dir = get_cache_folder_path()?.join("imports");
+    if !imports_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut dirs = vec![];
+    for dir in imports_dir.read_dir()? {
+        let to_dir = dir?.path();
+        let to_id =
+            PlaylistId::from_id(to_dir.file_name().unwrap().to_str().unwrap())?.into_static();
+        if ids.is_empty() || ids.contains(&to_id) {
+            dirs.push((to_id, to_dir));
+        }
+    }
+    Ok(dirs)
+}
+
+/// Re-run the imports into a playlist whose import data is stored in `to_dir`.
+///
+/// The import data is deleted if the user no longer follows the playlist.
+async fn playlist_sync(
+    client: &AppClient,
+    uid: &UserId<'_>,
+    to_id: PlaylistId<'static>,
+    to_dir: &Path,
+    delete: bool,
+) -> Result<String> {
+    let mut result = String::new();
+
+    let pl_follow = client
+        .playlist_check_follow(to_id.as_ref(), &[uid.as_ref()])
+        .await?
+        .pop()
+        .unwrap();
+
+    if pl_follow {
+        for i in to_dir.read_dir()? {
+            let from_id = PlaylistId::from_id(i?.file_name().to_str().unwrap().to_owned())?;
+            result += &playlist_import(client, from_id, to_id.clone_static(), delete).await?;
+            result += "\n";
+        }
+    } else {
+        remove_dir_all(to_dir)?;
+        writeln!(
+            result,
+            "Not following playlist '{}'. Deleted its import data in the cache folder...",
+            to_id.id()
+        )
+        .unwrap();
+    }
+
+    Ok(result)
+}
+
+/// Periodically sync the playlists' imports, as configured by the `playlist_sync` config.
+///
+/// The results of each sync are appended to the `sync_journal.log` file in the cache folder.
+#[cfg(feature = "daemon")]
+pub async fn start_playlist_sync_scheduler(client: AppClient) {
+    let sync_config = &config::get_config().app_config.playlist_sync;
+    if sync_config.interval_in_mins == 0 {
+        return;
+    }
+
+    let ids = match sync_config
+        .playlists
+        .iter()
+        .map(|id| PlaylistId::from_id_or_uri(id).map(PlaylistId::into_static))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(ids) => ids,
+        Err(err) => {
+            tracing::error!("Invalid playlist ID in the `playlist_sync` config: {err:#}");
+            return;
+        }
+    };
+
+    let period = std::time::Duration::from_secs(sync_config.interval_in_mins * 60);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+
+        tracing::info!("Running scheduled playlist sync...");
+        let journal = match scheduled_playlist_sync(&client, &ids, sync_config.delete).await {
+            Ok(journal) => journal,
+            Err(err) => {
+                tracing::error!("Failed to run scheduled playlist sync: {err:#}");
+                format!(
+                    "[{}] failed to run playlist sync: {err:#}\n",
+                    chrono::Local::now().to_rfc3339()
+                )
+            }
+        };
+        if let Err(err) = append_sync_journal(&journal) {
+            tracing::error!("Failed to write the playlist sync journal: {err:#}");
+        }
+    }
+}
+
+/// Sync the imports of `ids` playlists (all playlists if empty), returning the sync's journal entries
+#[cfg(feature = "daemon")]
+async fn scheduled_playlist_sync(
+    client: &AppClient,
+    ids: &[PlaylistId<'static>],
+    delete: bool,
+) -> Result<String> {
+    let uid = client.current_user().await?.id;
+
+    let mut journal = String::new();
+    let (mut synced, mut failed) = (0, 0);
+    for (to_id, to_dir) in playlist_import_dirs(ids)? {
+        let now = chrono::Local::now().to_rfc3339();
+        match playlist_sync(client, &uid, to_id.clone_static(), &to_dir, delete).await {
+            Ok(result) => {
+                synced += 1;
+                writeln!(journal, "[{now}] synced playlist {}:\n{result}", to_id.id()).unwrap();
+            }
+            Err(err) => {
+                failed += 1;
+                tracing::error!("Failed to sync playlist {}: {err:#}", to_id.id());
+                writeln!(
+                    journal,
+                    "[{now}] failed to sync playlist {}: {err:#}",
+                    to_id.id()
+                )
+                .unwrap();
+            }
+        }
+    }
+    tracing::info!("Scheduled playlist sync: {synced} playlists synced, {failed} failed");
+
+    #[cfg(feature = "notify")]
+    if config::get_config().app_config.playlist_sync.notify && synced + failed > 0 {
+        if let Err(err) = notify_rust::Notification::new()
+            .appname("spotify_player")
+            .summary("Playlist sync")
+            .body(&format!("{synced} playlists synced, {failed} failed"))
+            .show()
+        {
+            tracing::error!("Failed to send the playlist sync notification: {err:#}");
+        }
+    }
+
+    Ok(journal)
+}
+
+/// Append entries to the playlist sync journal in the cache folder
+#[cfg(feature = "daemon")]
+fn append_sync_journal(entries: &str) -> Result<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_cache_folder_path()?.join("sync_journal.log"))?;
+    f.write_all(entries.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(feature = "daemon")]
+async fn handle_alarm_request(client: &AppClient, command: AlarmCommand) -> Result<String> {
+    let mut alarms = load_stored_alarms()?;
+
+    match command {
+        AlarmCommand::Add {
+            schedule,
+            context_type,
+            id_or_name,
+            device,
+            volume,
+            fade_in_secs,
+            shuffle,
+        } => {
+            let context = match (context_type, id_or_name) {
+                (AlarmContextType::LikedTracks, _) => AlarmContext::LikedTracks,
+                (AlarmContextType::Playlist, Some(id_or_name)) => {
+                    let ItemId::Playlist(id) =
+                        get_spotify_id(client, ItemType::Playlist, id_or_name).await?
+                    else {
+                        anyhow::bail!("Unable to get playlist id")
+                    };
+                    AlarmContext::Playlist(id)
+                }
+                (AlarmContextType::Album, Some(id_or_name)) => {
+                    let ItemId::Album(id) =
+                        get_spotify_id(client, ItemType::Album, id_or_name).await?
+                    else {
+                        anyhow::bail!("Unable to get album id")
+                    };
+                    AlarmContext::Album(id)
+                }
+                (_, None) => anyhow::bail!("A playlist or album must be specified"),
+            };
+
+            let id = alarms.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+            let alarm = Alarm {
+                schedule,
+                context,
+                device,
+                volume,
+                fade_in_secs,
+                shuffle,
+            };
+            let result = format!(
+                "Added alarm {id}: {alarm}, next {}",
+                next_alarm_time(&alarm)
+            );
+            alarms.push(StoredAlarm { id, alarm });
+            save_stored_alarms(&alarms)?;
+            Ok(result)
+        }
+        AlarmCommand::List => {
+            let mut result = String::new();
+            for alarm in &config::get_config().app_config.alarm.alarms {
+                writeln!(result, "[config] {alarm}, next {}", next_alarm_time(alarm)).unwrap();
+            }
+            for StoredAlarm { id, alarm } in &alarms {
+                writeln!(result, "[{id}] {alarm}, next {}", next_alarm_time(alarm)).unwrap();
+            }
+            if result.is_empty() {
+                result = "No alarm found".to_string();
+            }
+            Ok(result.trim_end().to_string())
+        }
+        AlarmCommand::Remove { id } => {
+            let Some(pos) = alarms.iter().position(|a| a.id == id) else {
+                anyhow::bail!("No alarm with id={id} found");
+            };
+            let removed = alarms.remove(pos);
+            save_stored_alarms(&alarms)?;
+            Ok(format!("Removed alarm {id}: {}", removed.alarm))
+        }
+    }
+}
+
+/// Format the next time an alarm goes off
+#[cfg(feature = "daemon")]
+fn next_alarm_time(alarm: &Alarm) -> String {
+    alarm
+        .schedule
+        .next_after(chrono::Local::now().naive_local())
+        .map_or_else(
+            || "never".to_string(),
+            |t| t.format("%a %Y-%m-%d %H:%M").to_string(),
+        )
+}
+
+/// Path of the file storing the alarms added with the `alarm add` CLI command
+#[cfg(feature = "daemon")]
+fn alarms_file_path() -> Result<PathBuf> {
+    Ok(get_cache_folder_path()?.join("alarms.json"))
+}
+
+#[cfg(feature = "daemon")]
+fn load_stored_alarms() -> Result<Vec<StoredAlarm>> {
+    let path = alarms_file_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        std::fs::read(&path).with_context(|| format!("read alarms file {}", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| format!("parse alarms file {}", path.display()))
+}
+
+#[cfg(feature = "daemon")]
+fn save_stored_alarms(alarms: &[StoredAlarm]) -> Result<()> {
+    let path = alarms_file_path()?;
+    std::fs::write(&path, serde_json::to_vec_pretty(alarms)?)
+        .with_context(|| format!("write alarms file {}", path.display()))
+}
+
+/// Interval between two checks of the alarms' schedules
+#[cfg(feature = "daemon")]
+const ALARM_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Delay after which a due alarm is considered missed, e.g. because the system was suspended
+#[cfg(feature = "daemon")]
+const ALARM_MISSED_DELAY_SECS: i64 = 60;
+
+/// Interval between two volume raises during an alarm's fade-in
+#[cfg(feature = "daemon")]
+const ALARM_FADE_STEP: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Run the alarms defined in the `alarm` config and the ones added with the `alarm add` CLI command.
+///
+/// Alarms missed while the application was unable to run them, e.g. during a suspend,
+/// are handled according to the `alarm.missed_policy` config.
+#[cfg(feature = "daemon")]
+pub async fn start_alarm_scheduler(client: AppClient) {
+    let alarm_config = &config::get_config().app_config.alarm;
+
+    let mut last_check = chrono::Local::now().naive_local();
+    let mut interval = tokio::time::interval(ALARM_CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+
+        let now = chrono::Local::now().naive_local();
+        if now <= last_check {
+            // the clock went backward, e.g. at the end of the daylight saving time
+            continue;
+        }
+
+        // stored alarms are reloaded on each check to pick up the changes of `alarm` CLI commands
+        let stored = load_stored_alarms().unwrap_or_else(|err| {
+            tracing::error!("Failed to load the stored alarms: {err:#}");
+            Vec::new()
+        });
+        for alarm in alarm_config
+            .alarms
+            .iter()
+            .chain(stored.iter().map(|a| &a.alarm))
+        {
+            // the latest occurrence of the alarm since the last check
+            let Some(mut at) = alarm.schedule.next_after(last_check).filter(|&t| t <= now) else {
+                continue;
+            };
+            while let Some(t) = alarm.schedule.next_after(at).filter(|&t| t <= now) {
+                at = t;
+            }
+
+            let delay = now - at;
+            if delay.num_seconds() > ALARM_MISSED_DELAY_SECS {
+                let grace = alarm_config.missed_grace_in_mins;
+                if alarm_config.missed_policy == MissedAlarmPolicy::Skip
+                    || (grace > 0 && u64::try_from(delay.num_minutes()).unwrap_or(0) > grace)
+                {
+                    tracing::info!("Skipped alarm `{alarm}` missed at {at}");
+                    continue;
+                }
+                tracing::info!("Running alarm `{alarm}` missed at {at}...");
+            }
+
+            tokio::task::spawn({
+                let client = client.clone();
+                let alarm = alarm.clone();
+                async move {
+                    if let Err(err) = run_alarm(&client, &alarm).await {
+                        tracing::error!("Failed to run alarm `{alarm}`: {err:#}");
+                    }
+                }
+            });
+        }
+        last_check = now;
+    }
+}
+
+/// Start an alarm's context on its device, raising the volume gradually if the alarm has a fade-in
+#[cfg(feature = "daemon")]
+async fn run_alarm(client: &AppClient, alarm: &Alarm) -> Result<()> {
+    tracing::info!("Running alarm `{alarm}`...");
+
+    let device_id = match alarm.device {
+        None => None,
+        Some(ref device) => {
+            let id = client
+                .available_devices()
+                .await?
+                .into_iter()
+                .find(|d| d.id.as_ref() == Some(device) || d.name == *device)
+                .and_then(|d| d.id)
+                .with_context(|| format!("No device with name or id={device} found"))?;
+            client
+                .handle_player_request(PlayerRequest::TransferPlayback(id.clone(), false), None)
+                .await?;
+            wait_for_active_device(client, &id).await?;
+            Some(id)
+        }
+    };
+
+    let fade = std::time::Duration::from_secs(alarm.fade_in_secs);
+    // the volume to fade in to, falling back to the configured device's volume
+    // if the playback's device doesn't report its volume
+    let volume = match alarm.volume {
+        Some(volume) => volume,
+        None => client
+            .current_playback2()
+            .await?
+            .and_then(|p| p.device.volume_percent)
+            .and_then(|v| u8::try_from(v).ok())
+            .unwrap_or(config::get_config().app_config.device.volume),
+    };
+    if !fade.is_zero() {
+        client.volume(0, device_id.as_deref()).await?;
+    } else if let Some(volume) = alarm.volume {
+        client.volume(volume, device_id.as_deref()).await?;
+    }
+
+    let command = match alarm.context {
+        AlarmContext::Playlist(ref id) => Command::StartContext {
+            context_type: ContextType::Playlist,
+            id_or_name: IdOrName::Id(id.id().to_string()),
+            shuffle: alarm.shuffle,
+            episode: None,
+        },
+        AlarmContext::Album(ref id) => Command::StartContext {
+            context_type: ContextType::Album,
+            id_or_name: IdOrName::Id(id.id().to_string()),
+            shuffle: alarm.shuffle,
+            episode: None,
+        },
+        AlarmContext::LikedTracks => Command::StartLikedTracks {
+            limit: usize::MAX,
+            random: alarm.shuffle,
+        },
+    };
+    handle_playback_request(client, None, command).await?;
+
+    if !fade.is_zero() {
+        let start = tokio::time::Instant::now();
+        let mut last_step = 0;
+        loop {
+            tokio::time::sleep(ALARM_FADE_STEP).await;
+
+            // stop fading in once the user pauses the playback or changes its volume,
+            // allowing a small difference as devices may round the volume
+            let playback = client.current_playback2().await?;
+            let interrupted = match playback {
+                Some(ref p) if p.is_playing => p
+                    .device
+                    .volume_percent
+                    .is_some_and(|v| v.abs_diff(u32::from(last_step)) > 1),
+                _ => true,
+            };
+            if interrupted {
+                tracing::info!("Stopped the fade-in of alarm `{alarm}` after a playback change");
+                break;
+            }
+
+            let elapsed = start.elapsed().min(fade);
+            let step = u128::from(volume) * elapsed.as_millis() / fade.as_millis();
+            last_step = u8::try_from(step).unwrap_or(volume);
+            client.volume(last_step, device_id.as_deref()).await?;
+            if elapsed == fade {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait for a device to become the playback's device after a playback transfer,
+/// as the playback returned by the API may be briefly out of date
+#[cfg(feature = "daemon")]
+async fn wait_for_active_device(client: &AppClient, id: &str) -> Result<()> {
+    for _ in 0..10 {
+        let playback = client.current_playback2().await?;
+        if playback.is_some_and(|p| p.device.id.as_deref() == Some(id)) {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    anyhow::bail!("device with id={id} didn't become active after a playback transfer")
+}
+
 /// Imports a playlist into another playlist.
 ///
 /// All tracks from the `import_from` playlist are added to the `import_to` playlist if they are not in there already.
@@ -726,25 +1847,25 @@ async fn playlist_import(
         name: String,
     }
     // Get playlists' info
-    let (from_tracks, from_name) = match client.playlist_context(import_from.clone()).await? {
-        Context::Playlist { tracks, playlist } => (
-            tracks.into_iter().map(|t| TrackData {
-                id: t.id,
+    let (from_tracks, from_name) = {
+        let (playlist, tracks) = playlist_tracks(client, import_from.as_ref()).await?;
+        let tracks = tracks.into_iter().filter_map(|t| {
+            Some(TrackData {
+                id: t.id?,
                 name: t.name,
-            }),
-            playlist.name,
-        ),
-        _ => unreachable!(),
+            })
+        });
+        (tracks, playlist.name)
     };
-    let (to_tracks, to_name) = match client.playlist_context(import_to.clone()).await? {
-        Context::Playlist { tracks, playlist } => (
-            tracks.into_iter().map(|t| TrackData {
-                id: t.id,
+    let (to_tracks, to_name) = {
+        let (playlist, tracks) = playlist_tracks(client, import_to.as_ref()).await?;
+        let tracks = tracks.into_iter().filter_map(|t| {
+            Some(TrackData {
+                id: t.id?,
                 name: t.name,
-            }),
-            playlist.name,
-        ),
-        _ => unreachable!(),
+            })
+        });
+        (tracks, playlist.name)
     };
 
     // Get import dir/file
@@ -910,3 +2031,201 @@ async fn handle_lyrics_request(
 
     Ok(output.into_bytes())
 }
+
+/// Get the playable items to add to the queue from a queue item's `IdOrName` representation
+async fn get_queue_items(
+    client: &AppClient,
+    typ: QueueItemType,
+    id_or_name: IdOrName,
+) -> Result<Vec<PlayableId<'static>>> {
+    let item_type = match typ {
+        QueueItemType::Track => ItemType::Track,
+        QueueItemType::Episode => ItemType::Episode,
+        QueueItemType::Album => ItemType::Album,
+        QueueItemType::Playlist => ItemType::Playlist,
+    };
+
+    let tracks = match get_spotify_id(client, item_type, id_or_name).await? {
+        ItemId::Track(id) => return Ok(vec![PlayableId::Track(id)]),
+        ItemId::Episode(id) => return Ok(vec![PlayableId::Episode(id)]),
+        ItemId::Album(id) => match client.album_context(id).await? {
+            Context::Album { tracks, .. } => tracks,
+            _ => unreachable!(),
+        },
+        ItemId::Playlist(id) => playlist_tracks(client, id).await?.1,
+        ItemId::Artist(_) | ItemId::Show(_) => unreachable!(),
+    };
+    Ok(tracks.iter().filter_map(Track::playable_id).collect())
+}
+
+/// Get a display name for each of the given playable items
+async fn playable_names(client: &AppClient, ids: &[PlayableId<'static>]) -> Result<Vec<String>> {
+    let mut names = HashMap::new();
+
+    let track_ids = ids.iter().filter_map(|id| match id {
+        PlayableId::Track(id) => Some(id.clone()),
+        PlayableId::Episode(_) => None,
+    });
+    for chunk in track_ids.collect::<Vec<_>>().chunks(50) {
+        for track in client.tracks(chunk.to_vec(), None).await? {
+            if let Some(id) = track.id.clone() {
+                names.insert(id.uri(), playable_name(&PlayableItem::Track(track)));
+            }
+        }
+    }
+
+    let episode_ids = ids.iter().filter_map(|id| match id {
+        PlayableId::Episode(id) => Some(id.clone()),
+        PlayableId::Track(_) => None,
+    });
+    for chunk in episode_ids.collect::<Vec<_>>().chunks(50) {
+        for episode in client.get_several_episodes(chunk.to_vec(), None).await? {
+            names.insert(
+                episode.id.uri(),
+                playable_name(&PlayableItem::Episode(episode)),
+            );
+        }
+    }
+
+    Ok(ids
+        .iter()
+        .map(|id| names.remove(&id.uri()).unwrap_or_else(|| id.uri()))
+        .collect())
+}
+
+fn playable_name(item: &PlayableItem) -> String {
+    match item {
+        PlayableItem::Track(track) => format!(
+            "{} • {}",
+            track.name,
+            track
+                .artists
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        PlayableItem::Episode(episode) => format!("{} • {}", episode.name, episode.show.name),
+        PlayableItem::Unknown(_) => "Unknown".to_string(),
+    }
+}
+
+async fn handle_queue_request(
+    client: &AppClient,
+    state: Option<&SharedState>,
+    command: QueueCommand,
+) -> Result<String> {
+    // queue operations act on the app-managed custom queue when it's active,
+    // otherwise on Spotify's queue, which only supports adding items
+    let custom_queue_state = state.filter(|state| state.player.read().custom_queue.is_some());
+    let require_custom_queue = || {
+        custom_queue_state.context(
+            "this operation requires the custom queue to be active, Spotify's queue only supports adding items",
+        )
+    };
+    let edit_custom_queue = |f: &mut dyn FnMut(&mut CustomQueue) -> Result<()>| {
+        require_custom_queue()?.update_player(|player| {
+            let queue = player.custom_queue.as_mut().context("no custom queue")?;
+            f(queue)
+        })
+    };
+
+    match command {
+        QueueCommand::Add(typ, id_or_name) => {
+            let mut items = get_queue_items(client, typ, id_or_name).await?;
+            if custom_queue_state.is_some() {
+                edit_custom_queue(&mut |queue| {
+                    queue.append(std::mem::take(&mut items));
+                    Ok(())
+                })?;
+            } else {
+                for id in items {
+                    client.add_item_to_queue(id, None).await?;
+                }
+                if let Some(state) = state.filter(|state| !state.subscribers.is_empty()) {
+                    client.refresh_queue(state).await?;
+                }
+            }
+        }
+        QueueCommand::PlayNext(typ, id_or_name) => {
+            require_custom_queue()?;
+            let mut items = get_queue_items(client, typ, id_or_name).await?;
+            edit_custom_queue(&mut |queue| {
+                queue.insert_next(std::mem::take(&mut items));
+                Ok(())
+            })?;
+        }
+        QueueCommand::Remove { position } => {
+            edit_custom_queue(&mut |queue| {
+                position
+                    .checked_sub(1)
+                    .and_then(|i| queue.remove_upcoming(i))
+                    .with_context(|| format!("no item at position {position} in the queue"))?;
+                Ok(())
+            })?;
+        }
+        QueueCommand::Clear => {
+            edit_custom_queue(&mut |queue| {
+                queue.clear_upcoming();
+                Ok(())
+            })?;
+        }
+        QueueCommand::Move { from, to } => {
+            edit_custom_queue(&mut |queue| {
+                if from == 0 || to == 0 || !queue.move_upcoming(from - 1, to - 1) {
+                    anyhow::bail!("invalid positions {from} and {to} for the queue");
+                }
+                Ok(())
+            })?;
+        }
+        QueueCommand::Save { name } => {
+            let (playlist, count, skipped) =
+                client.save_queue_as_playlist(state, &name, "").await?;
+            let mut resp = format!(
+                "Saved {count} items of the queue into playlist '{}' with id '{}'",
+                playlist.name,
+                playlist.id.id()
+            );
+            if skipped > 0 {
+                write!(
+                    resp,
+                    ", skipped {skipped} items without a Spotify ID (e.g. local files)"
+                )
+                .unwrap();
+            }
+            return Ok(resp);
+        }
+        QueueCommand::List => {
+            let (current, upcoming) = if let Some(state) = custom_queue_state {
+                let ids = {
+                    let player = state.player.read();
+                    let queue = player.custom_queue.as_ref().context("no custom queue")?;
+                    std::iter::once(queue.current_track())
+                        .chain(queue.remaining_tracks())
+                        .cloned()
+                        .collect::<Vec<_>>()
+                };
+                let mut names = playable_names(client, &ids).await?;
+                let upcoming = names.split_off(1);
+                (names.pop(), upcoming)
+            } else {
+                let queue = client.current_user_queue().await?;
+                (
+                    queue.currently_playing.as_ref().map(playable_name),
+                    queue.queue.iter().map(playable_name).collect(),
+                )
+            };
+
+            let mut out = String::new();
+            if let Some(current) = current {
+                writeln!(out, "Now playing: {current}").unwrap();
+            }
+            for (i, name) in upcoming.iter().enumerate() {
+                writeln!(out, "{}: {name}", i + 1).unwrap();
+            }
+            return Ok(out.trim().to_string());
+        }
+    }
+
+    Ok(String::new())
+}
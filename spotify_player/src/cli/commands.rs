@@ -1,9 +1,17 @@
 use clap::{builder::EnumValueParser, value_parser, Arg, ArgAction, ArgGroup, Command};
 use clap_complete::Shell;
 
-use crate::cli::EditAction;
+use crate::{
+    cli::EditAction,
+    export::ExportFormat,
+    import::ImportFileFormat,
+    playlist_edit::{DedupeKey, SetOperation},
+    state::{TimeRange, TrackOrder},
+};
 
-use super::{ContextType, ItemType, Key};
+#[cfg(feature = "daemon")]
+use super::AlarmContextType;
+use super::{ContextType, ItemType, Key, QueueItemType};
 
 pub fn init_connect_subcommand() -> Command {
     add_id_or_name_group(Command::new("connect").about("Connect to a Spotify device"))
@@ -14,11 +22,21 @@ pub fn init_get_subcommand() -> Command {
         .about("Get Spotify data")
         .subcommand_required(true)
         .subcommand(
-            Command::new("key").about("Get data by key").arg(
-                Arg::new("key")
-                    .value_parser(EnumValueParser::<Key>::new())
-                    .required(true),
-            ),
+            Command::new("key")
+                .about("Get data by key")
+                .arg(
+                    Arg::new("key")
+                        .value_parser(EnumValueParser::<Key>::new())
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("time_range")
+                        .long("time-range")
+                        .value_parser(EnumValueParser::<TimeRange>::new())
+                        .help(
+                            "Time range of the user's top tracks or artists (default: medium-term)",
+                        ),
+                ),
         )
         .subcommand(add_id_or_name_group(
             Command::new("item").about("Get a Spotify item's data").arg(
@@ -47,11 +65,25 @@ fn init_playback_start_subcommand() -> Command {
                         .long("shuffle")
                         .action(ArgAction::SetTrue)
                         .help("Shuffle tracks within the launched playback"),
-                ),
+                )
+                .arg(
+                    Arg::new("episode_id")
+                        .long("episode-id")
+                        .help("Id of the episode to start a show playback from"),
+                )
+                .arg(
+                    Arg::new("episode_name")
+                        .long("episode-name")
+                        .help("Name of the episode to start a show playback from"),
+                )
+                .group(ArgGroup::new("episode").args(["episode_id", "episode_name"])),
         ))
         .subcommand(add_id_or_name_group(
             Command::new("track").about("Start playback for a track"),
         ))
+        .subcommand(add_id_or_name_group(
+            Command::new("episode").about("Start playback for a podcast episode"),
+        ))
         .subcommand(
             Command::new("liked")
                 .about("Start a liked tracks playback")
@@ -130,6 +162,22 @@ pub fn init_playback_subcommand() -> Command {
                         .required(true),
                 ),
         )
+        .subcommand(
+            Command::new("sleep")
+                .about("Pause the playback after a duration or at the end of the current track/context")
+                .arg(
+                    Arg::new("timer")
+                        .help("A duration (e.g. `30m`, `1h30m`, `45` for 45 minutes), `end-of-track`, `end-of-context` or `off` to cancel the current timer")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("fade")
+                        .long("fade")
+                        .short('f')
+                        .value_parser(value_parser!(u64))
+                        .help("Lower the volume gradually over the last <FADE> seconds, restoring it after pausing"),
+                ),
+        )
 }
 
 pub fn init_search_command() -> Command {
@@ -165,6 +213,18 @@ pub fn init_generate_command() -> Command {
         )
 }
 
+pub fn init_complete_command() -> Command {
+    Command::new("complete")
+        .about("Print the dynamic shell completion candidates of the word following the given CLI arguments")
+        .hide(true)
+        .arg(
+            Arg::new("words")
+                .num_args(0..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true),
+        )
+}
+
 pub fn init_playlist_subcommand() -> Command {
     Command::new("playlist")
         .about("Playlist editing")
@@ -200,6 +260,34 @@ pub fn init_playlist_subcommand() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Deletes any previously imported tracks that are no longer in the imported playlist since last import."))
             .after_help("Import data for each playlist is stored inside the application's cache folder. If imported again, the command only imports new tracks since last import."))
+        .subcommand(Command::new("import-file").about("Imports tracks from a text, CSV or M3U file into a playlist.")
+            .arg(Arg::new("path")
+                .help("Path to the file to import")
+                .required(true)
+                .value_parser(value_parser!(std::path::PathBuf)))
+            .arg(Arg::new("to")
+                .long("to")
+                .short('t')
+                .help("ID of the playlist to import into")
+                .required(true)
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("format")
+                .long("format")
+                .short('f')
+                .value_parser(EnumValueParser::<ImportFileFormat>::new())
+                .help("Format of the file, detected from its extension if not specified"))
+            .arg(Arg::new("report")
+                .long("report")
+                .short('r')
+                .value_parser(value_parser!(std::path::PathBuf))
+                .help("File to write the entries that weren't imported to [default: {path}.report.txt]"))
+            .arg(Arg::new("accept_low_confidence")
+                .long("accept-low-confidence")
+                .action(clap::ArgAction::SetTrue)
+                .help("Also imports the best match of entries without a confident match"))
+            .after_help("Text files contain one \"Artist - Title\" entry per line. CSV files need a header row with a title (or name) column, \
+and optionally artist, duration_ms and uri columns. Entries are matched to the tracks with the closest title, artists and duration, \
+and lines with a Spotify track URI or URL are imported as-is."))
         .subcommand(Command::new("list").about("Lists all user playlists."))
         .subcommand(Command::new("fork").about("Creates a copy of a playlist and imports it.")
             .arg(Arg::new("id")
@@ -237,6 +325,179 @@ pub fn init_playlist_subcommand() -> Command {
                     .args(["track_id", "album_id"])
                     .required(true)
             ))
+        .subcommand(Command::new("dedupe").about("Deletes duplicate tracks from a playlist, keeping the earliest added occurrence of each track.")
+            .arg(Arg::new("id")
+                .help("Playlist ID")
+                .required(true)
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("by")
+                .long("by")
+                .short('b')
+                .default_value("id")
+                .value_parser(EnumValueParser::<DedupeKey>::new())
+                .help("How duplicate tracks are identified"))
+            .arg(Arg::new("dry_run")
+                .long("dry-run")
+                .action(clap::ArgAction::SetTrue)
+                .help("Lists the duplicates without deleting them")))
+        .subcommand(Command::new("sort").about("Sorts the tracks of a playlist, moving as few tracks as possible.")
+            .arg(Arg::new("id")
+                .help("Playlist ID")
+                .required(true)
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("by")
+                .long("by")
+                .short('b')
+                .required(true)
+                .value_parser(EnumValueParser::<TrackOrder>::new())
+                .help("The field to sort tracks by"))
+            .arg(Arg::new("reverse")
+                .long("reverse")
+                .short('r')
+                .action(clap::ArgAction::SetTrue)
+                .help("Sorts in descending order")))
+        .subcommand(Command::new("combine").about("Combines the tracks of playlists with a set operation into a new or an existing playlist.")
+            .arg(Arg::new("op")
+                .long("op")
+                .short('o')
+                .required(true)
+                .value_parser(EnumValueParser::<SetOperation>::new())
+                .help("The set operation. `intersect` and `difference` are applied to the first playlist's tracks"))
+            .arg(Arg::new("ids")
+                .help("Playlist IDs")
+                .required(true)
+                .num_args(2..)
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("into")
+                .long("into")
+                .short('i')
+                .required(true)
+                .help("ID or name of an owned playlist to add the tracks to. A new playlist with that name is created if none matches")
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("preserve_order")
+                .long("preserve-order")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keeps the order of the playlists and their tracks instead of sorting tracks by their added date"))
+            .arg(Arg::new("dedupe")
+                .long("dedupe")
+                .short('d')
+                .action(clap::ArgAction::SetTrue)
+                .help("Adds each track at most once, skipping tracks already in the target playlist")))
+        .subcommand(Command::new("export").about("Export the tracks of a playlist, an album, the liked tracks or the queue.")
+            .arg(Arg::new("id")
+                .help("Playlist ID")
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("album_id")
+                .long("album-id")
+                .short('a')
+                .help("Export an album instead of a playlist")
+                .value_parser(clap::builder::NonEmptyStringValueParser::new()))
+            .arg(Arg::new("liked")
+                .long("liked")
+                .short('l')
+                .action(clap::ArgAction::SetTrue)
+                .help("Export the liked tracks instead of a playlist"))
+            .arg(Arg::new("queue")
+                .long("queue")
+                .short('q')
+                .action(clap::ArgAction::SetTrue)
+                .help("Export the playback queue instead of a playlist"))
+            .group(
+                ArgGroup::new("source")
+                    .args(["id", "album_id", "liked", "queue"])
+                    .required(true)
+            )
+            .arg(Arg::new("format")
+                .long("format")
+                .short('f')
+                .default_value("csv")
+                .value_parser(EnumValueParser::<ExportFormat>::new())
+                .help("Format of the exported data"))
+            .arg(Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_parser(value_parser!(std::path::PathBuf))
+                .help("File to write the exported data to, instead of the standard output"))
+            .after_help("Each exported track includes its URI, title, artists, album, duration, added-at date and ISRC."))
+}
+
+pub fn init_queue_subcommand() -> Command {
+    let item_type = Arg::new("item_type")
+        .value_parser(EnumValueParser::<QueueItemType>::new())
+        .required(true);
+    let position = |name: &'static str| {
+        Arg::new(name)
+            .value_parser(value_parser!(usize))
+            .required(true)
+    };
+
+    Command::new("queue")
+        .about("Manage the playback queue")
+        .subcommand_required(true)
+        .subcommand(add_id_or_name_group(
+            Command::new("add")
+                .about("Add an item to the end of the queue")
+                .arg(item_type.clone()),
+        ))
+        .subcommand(add_id_or_name_group(
+            Command::new("play-next")
+                .about("Add an item to play right after the current track")
+                .arg(item_type),
+        ))
+        .subcommand(
+            Command::new("remove")
+                .about("Remove an item from the queue")
+                .arg(position("position").help("Position of the item, as listed by `queue list`")),
+        )
+        .subcommand(Command::new("clear").about("Remove all items after the current track"))
+        .subcommand(Command::new("list").about("List the items in the queue"))
+        .subcommand(
+            Command::new("move")
+                .about("Move an item to another position in the queue")
+                .arg(position("from").help("Current position of the item"))
+                .arg(position("to").help("New position of the item")),
+        )
+        .subcommand(
+            Command::new("save")
+                .about("Save the queue, starting with the current track, as a new playlist")
+                .arg(
+                    Arg::new("name")
+                        .long("name")
+                        .short('n')
+                        .required(true)
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .help("Name of the new playlist"),
+                ),
+        )
+        .after_help("Removing, clearing and moving items require the app-managed custom queue to be active, as Spotify's queue only supports adding items.")
+}
+
+pub fn init_library_subcommand() -> Command {
+    let file = Arg::new("file")
+        .value_parser(value_parser!(std::path::PathBuf))
+        .required(true);
+
+    Command::new("library")
+        .about("Back up and restore the user's library")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("backup")
+                .about("Save liked tracks, saved albums and shows, followed artists and playlists into a file")
+                .arg(file.clone().help("Path to the backup file")),
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("Restore the items of a backup that are missing from the library")
+                .arg(file.help("Path to the backup file"))
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the changes without applying them"),
+                )
+                .after_help("Restoring never removes items from the library. Unfollowed playlists are followed again, \
+and the user's own playlists that no longer exist are recreated."),
+        )
 }
 
 pub fn init_print_features_command() -> Command {
@@ -251,3 +512,97 @@ pub fn init_lyrics_command() -> Command {
         false,
     )
 }
+
+pub fn init_watch_command() -> Command {
+    Command::new("watch").about(
+        "Watch the running application's state, printing an event in JSON format for each change",
+    )
+}
+
+pub fn init_history_command() -> Command {
+    Command::new("history")
+        .about("Print the local listening history, most recent items last")
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .short('l')
+                .value_parser(value_parser!(usize))
+                .default_value("50")
+                .help("Maximum number of items to print, 0 to print all the items"),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .short('s')
+                .value_parser(value_parser!(chrono::NaiveDate))
+                .help("Only print the items played since a date, e.g. 2024-01-31"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the items in JSON format, one item per line"),
+        )
+}
+
+#[cfg(feature = "daemon")]
+pub fn init_alarm_subcommand() -> Command {
+    Command::new("alarm")
+        .about("Manage the alarms starting a playback at scheduled times, run by the application's daemon")
+        .subcommand_required(true)
+        .subcommand(add_id_or_name_group_optional(
+            Command::new("add")
+                .about("Add an alarm")
+                .arg(
+                    Arg::new("schedule")
+                        .required(true)
+                        .help("When the alarm goes off, e.g. `weekdays 07:30`, `sat,sun 09:00`, `06:45` or a cron expression like `30 7 * * 1-5`"),
+                )
+                .arg(
+                    Arg::new("context_type")
+                        .value_parser(EnumValueParser::<AlarmContextType>::new())
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("device")
+                        .long("device")
+                        .short('d')
+                        .help("Name or ID of the device to play on, the active device if not specified"),
+                )
+                .arg(
+                    Arg::new("volume")
+                        .long("volume")
+                        .short('v')
+                        .value_parser(value_parser!(u8).range(0..=100))
+                        .help("Volume percentage of the playback"),
+                )
+                .arg(
+                    Arg::new("fade_in")
+                        .long("fade-in")
+                        .short('f')
+                        .value_parser(value_parser!(u64))
+                        .default_value("0")
+                        .help("Raise the volume gradually from silence over <FADE_IN> seconds"),
+                )
+                .arg(
+                    Arg::new("shuffle")
+                        .long("shuffle")
+                        .short('s')
+                        .action(ArgAction::SetTrue)
+                        .help("Shuffle the context's tracks"),
+                ),
+            false,
+        ))
+        .subcommand(Command::new("list").about("List the alarms and their next occurrence"))
+        .subcommand(
+            Command::new("remove")
+                .about("Remove an alarm added with `alarm add`")
+                .arg(
+                    Arg::new("id")
+                        .value_parser(value_parser!(u32))
+                        .required(true)
+                        .help("ID of the alarm, as listed by `alarm list`"),
+                ),
+        )
+        .after_help("A playlist or album is specified with --id or --name. Alarms defined in the `alarm` config are listed but can't be removed with the CLI.")
+}
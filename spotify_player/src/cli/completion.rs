@@ -0,0 +1,239 @@
+//! Dynamic shell completion of names that can't be known statically by `clap_complete`,
+//! e.g. device and playlist names.
+//!
+//! The scripts generated by the `generate` command call the hidden `complete` command
+//! with the words before the completed one. The command prints a candidate per line,
+//! optionally followed by a tab and a description, or fails if the word isn't completed dynamically.
+
+use std::path::Path;
+
+use clap_complete::Shell;
+
+use rspotify::prelude::Id;
+
+use crate::state::{load_data_from_file_cache, Album, Artist, FileCacheKey, PlaylistFolderItem};
+
+use super::{socket::Connection, CompletionKind, Request, Response};
+
+/// Timeout for connecting to a running client, short so that a stuck client
+/// doesn't block the completion, which then falls back to the cached candidates
+const COMPLETION_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+/// Timeout for receiving completion candidates from a running client
+const COMPLETION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Get the kind of completion of the word following `words`, `None` if it's not completed dynamically.
+///
+/// `words` are the CLI arguments typed so far, excluding the program name.
+pub fn completion_kind(words: &[String]) -> Option<CompletionKind> {
+    let args = words.iter().map(String::as_str).collect::<Vec<_>>();
+    // positional arguments, i.e. subcommands and arguments that are not options or option values
+    let positionals = args
+        .iter()
+        .enumerate()
+        .filter(|&(i, arg)| !arg.starts_with('-') && (i == 0 || !takes_value(args[i - 1])))
+        .map(|(_, arg)| *arg)
+        .collect::<Vec<_>>();
+
+    match (positionals.as_slice(), args.last()) {
+        (["connect", ..], Some(&("--name" | "-n"))) => Some(CompletionKind::Devices),
+        (["playback", "start", "context", context_type, ..], Some(&("--name" | "-n"))) => {
+            match *context_type {
+                "playlist" => Some(CompletionKind::Playlists),
+                "artist" => Some(CompletionKind::Artists),
+                "album" => Some(CompletionKind::Albums),
+                _ => None,
+            }
+        }
+        (["playlist", "edit", _], Some(arg)) if !takes_value(arg) => {
+            Some(CompletionKind::PlaylistIds)
+        }
+        _ => None,
+    }
+}
+
+/// Whether an option of the commands completed dynamically takes a value
+fn takes_value(arg: &str) -> bool {
+    matches!(
+        arg,
+        "--id"
+            | "-i"
+            | "--name"
+            | "-n"
+            | "--track-id"
+            | "-t"
+            | "--album-id"
+            | "-a"
+            | "--episode-id"
+            | "--episode-name"
+            | "--theme"
+            | "--config-folder"
+            | "-c"
+            | "--cache-folder"
+            | "-C"
+            | "--config-override"
+            | "-o"
+    )
+}
+
+/// Get the completion candidates of the user's library data
+pub fn library_candidates(
+    kind: CompletionKind,
+    playlists: &[PlaylistFolderItem],
+    artists: &[Artist],
+    albums: &[Album],
+) -> Vec<String> {
+    let playlists = playlists.iter().filter_map(|item| match item {
+        PlaylistFolderItem::Playlist(p) => Some(p),
+        PlaylistFolderItem::Folder(_) => None,
+    });
+    match kind {
+        CompletionKind::Devices => vec![],
+        CompletionKind::Playlists => playlists.map(|p| p.name.clone()).collect(),
+        CompletionKind::PlaylistIds => playlists
+            .map(|p| format!("{}\t{}", p.id.id(), p.name))
+            .collect(),
+        CompletionKind::Artists => artists.iter().map(|a| a.name.clone()).collect(),
+        CompletionKind::Albums => albums.iter().map(|a| a.name.clone()).collect(),
+    }
+}
+
+/// Print the completion candidates of a kind, asking the running client if any
+/// or reading the file caches otherwise
+pub fn print_candidates(configs: &crate::config::Configs, kind: CompletionKind) {
+    let candidates = running_client_candidates(configs, kind)
+        .unwrap_or_else(|| cached_candidates(&configs.cache_folder, kind));
+    for candidate in candidates {
+        println!("{candidate}");
+    }
+}
+
+fn running_client_candidates(
+    configs: &crate::config::Configs,
+    kind: CompletionKind,
+) -> Option<Vec<String>> {
+    let mut conn = Connection::connect_with_timeout(configs, COMPLETION_CONNECT_TIMEOUT).ok()??;
+    conn.send(&Request::Complete(kind)).ok()?;
+    match conn.receive(COMPLETION_TIMEOUT).ok()? {
+        Response::Ok(data) => serde_json::from_slice(&data).ok(),
+        Response::Err(_) => None,
+    }
+}
+
+fn cached_candidates(cache_folder: &Path, kind: CompletionKind) -> Vec<String> {
+    let playlists: Vec<PlaylistFolderItem> =
+        load_data_from_file_cache(FileCacheKey::Playlists, cache_folder).unwrap_or_default();
+    let artists: Vec<Artist> =
+        load_data_from_file_cache(FileCacheKey::FollowedArtists, cache_folder).unwrap_or_default();
+    let albums: Vec<Album> =
+        load_data_from_file_cache(FileCacheKey::SavedAlbums, cache_folder).unwrap_or_default();
+    library_candidates(kind, &playlists, &artists, &albums)
+}
+
+/// Get the script hooking the dynamic completion into the static completion generated for a shell.
+///
+/// `name` is the program's name, the script is empty for unsupported shells.
+pub fn dynamic_completion_script(shell: Shell, name: &str) -> String {
+    let script = match shell {
+        Shell::Bash => BASH_SCRIPT,
+        Shell::Zsh => ZSH_SCRIPT,
+        Shell::Fish => FISH_SCRIPT,
+        _ => return String::new(),
+    };
+    script.replace("{name}", name)
+}
+
+const BASH_SCRIPT: &str = r#"
+_{name}_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" candidates candidate
+    if candidates="$({name} complete -- "${COMP_WORDS[@]:1:COMP_CWORD-1}" 2>/dev/null)"; then
+        COMPREPLY=()
+        while IFS= read -r candidate; do
+            candidate="${candidate%%$'\t'*}"
+            if [[ -n "$candidate" && "$candidate" == "$cur"* ]]; then
+                COMPREPLY+=("$(printf '%q' "$candidate")")
+            fi
+        done <<< "$candidates"
+        return 0
+    fi
+    _{name} "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _{name}_dynamic -o nosort -o bashdefault -o default {name}
+else
+    complete -F _{name}_dynamic -o bashdefault -o default {name}
+fi
+"#;
+
+const ZSH_SCRIPT: &str = r#"
+_{name}_dynamic() {
+    local output line value
+    local -a candidates
+    if output="$({name} complete -- "${(@)words[2,CURRENT-1]}" 2>/dev/null)"; then
+        for line in "${(@f)output}"; do
+            [[ -z "$line" ]] && continue
+            value="${${line%%$'\t'*}//:/\\:}"
+            if [[ "$line" == *$'\t'* ]]; then
+                candidates+=("$value:${line#*$'\t'}")
+            else
+                candidates+=("$value")
+            fi
+        done
+        _describe -t values 'value' candidates
+        return
+    fi
+    _{name} "$@"
+}
+
+compdef _{name}_dynamic {name}
+"#;
+
+const FISH_SCRIPT: &str = r#"
+function __{name}_complete_dynamic
+    # the candidates are stored for the completion's arguments, so the completer runs once
+    set -g __{name}_dynamic_candidates ({name} complete -- (commandline -opc)[2..-1] 2>/dev/null)
+end
+
+complete -c {name} -n __{name}_complete_dynamic -f -a '(printf "%s\n" $__{name}_dynamic_candidates)'
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(line: &str) -> Option<CompletionKind> {
+        completion_kind(
+            &line
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn dynamic_completion_kinds() {
+        assert_eq!(kind("connect --name"), Some(CompletionKind::Devices));
+        assert_eq!(kind("connect --id"), None);
+        assert_eq!(
+            kind("playback start context playlist -n"),
+            Some(CompletionKind::Playlists)
+        );
+        assert_eq!(
+            kind("playback start context --shuffle artist --name"),
+            Some(CompletionKind::Artists)
+        );
+        assert_eq!(kind("playback start context show --name"), None);
+        assert_eq!(kind("playback start track --name"), None);
+        assert_eq!(
+            kind("-t dracula playlist edit add"),
+            Some(CompletionKind::PlaylistIds)
+        );
+        assert_eq!(
+            kind("playlist edit --track-id abc add"),
+            Some(CompletionKind::PlaylistIds)
+        );
+        assert_eq!(kind("playlist edit add --album-id"), None);
+        assert_eq!(kind("playlist edit add 37i9dQZF1DXcBWIGoYBM5M"), None);
+        assert_eq!(kind("playlist edit"), None);
+    }
+}
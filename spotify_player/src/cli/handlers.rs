@@ -1,31 +1,27 @@
-use crate::{auth::AuthConfig, client};
+use crate::{
+    auth::AuthConfig,
+    backup::LibraryBackup,
+    client,
+    export::{ExportFormat, ExportSource},
+    import::{self, ImportFileFormat, ImportReport},
+    playlist_edit::{DedupeKey, SetOperation},
+    state::{SleepMode, TimeRange, TrackOrder},
+};
 
 use super::{
     config, init_cli, start_socket, AlbumId, Command, ContextType, EditAction, GetRequest,
-    IdOrName, ItemType, Key, PlaylistCommand, PlaylistId, Request, Response, TrackId,
-    MAX_REQUEST_SIZE,
+    IdOrName, ItemType, Key, LibraryCommand, PlaylistCommand, PlaylistId, QueueCommand,
+    QueueItemType, Request, Response, TrackId,
 };
+#[cfg(feature = "daemon")]
+use super::{AlarmCommand, AlarmContextType};
 use anyhow::{Context, Result};
 use clap::{ArgMatches, Id};
 use clap_complete::{generate, Shell};
-use std::net::UdpSocket;
-
-fn receive_response(socket: &UdpSocket) -> Result<Response> {
-    // read response from the server's socket, which can be split into
-    // smaller chunks of data
-    let mut data = Vec::new();
-    let mut buf = [0; 4096];
-    loop {
-        let (n_bytes, _) = socket.recv_from(&mut buf)?;
-        if n_bytes == 0 {
-            // end of chunk
-            break;
-        }
-        data.extend_from_slice(&buf[..n_bytes]);
-    }
+use std::{io::Write, path::PathBuf};
 
-    Ok(serde_json::from_slice(&data)?)
-}
+use super::completion;
+use super::socket::{self, Connection, Listener};
 
 fn get_id_or_name(args: &ArgMatches) -> IdOrName {
     try_get_id_or_name(args).expect("id_or_name group is required")
@@ -56,7 +52,8 @@ fn handle_get_subcommand(args: &ArgMatches) -> Request {
                 .get_one::<Key>("key")
                 .expect("key is required")
                 .to_owned();
-            Request::Get(GetRequest::Key(key))
+            let time_range = args.get_one::<TimeRange>("time_range").copied();
+            Request::Get(GetRequest::Key(key, time_range))
         }
         "item" => {
             let item_type = args
@@ -77,18 +74,31 @@ fn handle_playback_subcommand(args: &ArgMatches) -> Result<Request> {
     let command = match cmd {
         "start" => match args.subcommand() {
             Some(("track", args)) => Command::StartTrack(get_id_or_name(args)),
+            Some(("episode", args)) => Command::StartEpisode(get_id_or_name(args)),
             Some(("context", args)) => {
                 let context_type = args
                     .get_one::<ContextType>("context_type")
                     .expect("context_type is required")
                     .to_owned();
                 let shuffle = args.get_flag("shuffle");
+                let episode = match args.get_one::<Id>("episode").map(Id::as_str) {
+                    Some("episode_id") => args
+                        .get_one::<String>("episode_id")
+                        .cloned()
+                        .map(IdOrName::Id),
+                    Some("episode_name") => args
+                        .get_one::<String>("episode_name")
+                        .cloned()
+                        .map(IdOrName::Name),
+                    _ => None,
+                };
 
                 let id_or_name = get_id_or_name(args);
                 Command::StartContext {
                     context_type,
                     id_or_name,
                     shuffle,
+                    episode,
                 }
             }
             Some(("liked", args)) => {
@@ -133,51 +143,56 @@ fn handle_playback_subcommand(args: &ArgMatches) -> Result<Request> {
                 .expect("position_offset_ms is required");
             Command::Seek(*position_offset_ms)
         }
+        "sleep" => {
+            let timer = args
+                .get_one::<String>("timer")
+                .expect("timer arg is required");
+            let mode = match timer.as_str() {
+                "off" => None,
+                s => Some(s.parse::<SleepMode>()?),
+            };
+            Command::Sleep {
+                mode,
+                fade_secs: args.get_one::<u64>("fade").copied(),
+            }
+        }
         _ => unreachable!(),
     };
 
     Ok(Request::Playback(command))
 }
 
-/// Tries to connect to a running client, if exists, by sending a connection request
-/// to the client via a UDP socket.
+/// Tries to connect to a running client, if exists, via the client socket.
 /// If no running client found, create a new client running in a separate thread to
 /// handle the socket request.
-fn try_connect_to_client(socket: &UdpSocket, configs: &config::Configs) -> Result<()> {
-    let port = configs.app_config.client_port;
-    socket.connect(("127.0.0.1", port))?;
+fn try_connect_to_client(configs: &config::Configs) -> Result<Connection> {
+    if let Some(conn) = Connection::connect(configs)? {
+        return Ok(conn);
+    }
 
-    // send an empty buffer as a connection request to the client
-    socket.send(&[])?;
-    if let Err(err) = socket.recv(&mut [0; 1]) {
-        if let std::io::ErrorKind::ConnectionRefused = err.kind() {
-            // no running `spotify_player` instance found,
-            // initialize a new client to handle the current CLI command
+    // no running `spotify_player` instance found,
+    // initialize a new client to handle the current CLI command
 
-            let rt = tokio::runtime::Runtime::new()?;
+    let rt = tokio::runtime::Runtime::new()?;
 
-            // create a Spotify API client
-            let client = rt
-                .block_on(client::AppClient::new())
-                .context("construct app client")?;
-            rt.block_on(client.new_session(None, false))
-                .context("new session")?;
-
-            // create a client socket for handling CLI commands
-            // NOTE: the socket must be bound *before* spawning the thread to avoid a
-            // race condition where the caller sends a request before the socket is ready.
-            let client_socket = rt.block_on(tokio::net::UdpSocket::bind(("127.0.0.1", port)))?;
-
-            // spawn a thread to handle the CLI request
-            std::thread::spawn(move || {
-                rt.block_on(start_socket(&client, None, Some(client_socket)));
-            });
-        } else {
-            return Err(err.into());
-        }
-    }
+    // create a Spotify API client
+    let client = rt
+        .block_on(client::AppClient::new())
+        .context("construct app client")?;
+    rt.block_on(client.new_session(None, false))
+        .context("new session")?;
 
-    Ok(())
+    // create a client socket for handling CLI commands
+    // NOTE: the socket must be bound *before* spawning the thread to avoid a
+    // race condition where the caller connects before the socket is ready.
+    let (listener, addr) = rt.block_on(Listener::bind_local())?;
+
+    // spawn a thread to handle the CLI request
+    std::thread::spawn(move || {
+        rt.block_on(start_socket(client, None, Some(listener)));
+    });
+
+    Connection::connect_tcp(addr, None, socket::CONNECT_TIMEOUT)
 }
 
 pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
@@ -204,24 +219,49 @@ pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
                 .expect("shell argument is required");
             let mut cmd = init_cli()?;
             let name = cmd.get_name().to_string();
-            generate(gen, &mut cmd, name, &mut std::io::stdout());
+            generate(gen, &mut cmd, name.clone(), &mut std::io::stdout());
+            print!("{}", completion::dynamic_completion_script(gen, &name));
             std::process::exit(0);
         }
+        "complete" => {
+            let words = args
+                .get_many::<String>("words")
+                .map(|words| words.cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            match completion::completion_kind(&words) {
+                Some(kind) => {
+                    completion::print_candidates(configs, kind);
+                    std::process::exit(0);
+                }
+                None => std::process::exit(1),
+            }
+        }
         "features" => {
             print_features();
             std::process::exit(0);
         }
+        "history" => {
+            print_history(configs, args)?;
+            std::process::exit(0);
+        }
         _ => {}
     }
 
-    let socket = UdpSocket::bind("127.0.0.1:0")?;
-    try_connect_to_client(&socket, configs).context("try to connect to a client")?;
+    let mut conn = try_connect_to_client(configs).context("try to connect to a client")?;
+
+    if cmd == "watch" {
+        return handle_watch_command(conn);
+    }
 
     // construct a socket request based on the CLI command and its arguments
     let request = match cmd {
         "get" => handle_get_subcommand(args),
         "playback" => handle_playback_subcommand(args)?,
         "playlist" => handle_playlist_subcommand(args)?,
+        "queue" => handle_queue_subcommand(args),
+        "library" => handle_library_subcommand(args)?,
+        #[cfg(feature = "daemon")]
+        "alarm" => handle_alarm_subcommand(args)?,
         "connect" => Request::Connect(get_id_or_name(args)),
         "like" => Request::Like {
             unlike: args.get_flag("unlike"),
@@ -238,24 +278,211 @@ pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
         _ => unreachable!(),
     };
 
+    let output = match (cmd, args.subcommand()) {
+        ("playlist", Some(("export", args))) => {
+            ResponseOutput::Raw(args.get_one::<PathBuf>("output").cloned())
+        }
+        ("library", Some(("backup", args))) => {
+            ResponseOutput::Raw(args.get_one::<PathBuf>("file").cloned())
+        }
+        ("playlist", Some(("import-file", args))) => {
+            ResponseOutput::ImportReport(import_report_path(args))
+        }
+        _ => ResponseOutput::Text,
+    };
+
     // send the request to the client's socket
-    let request_buf = serde_json::to_vec(&request)?;
-    assert!(request_buf.len() <= MAX_REQUEST_SIZE);
-    socket.send(&request_buf)?;
+    conn.send(&request).context("send request to the client")?;
 
     // receive and handle a response from the client's socket
-    match receive_response(&socket)? {
+    match conn.receive(request.response_timeout())? {
         Response::Err(err) => {
             eprintln!("{}", String::from_utf8_lossy(&err));
             std::process::exit(1);
         }
         Response::Ok(data) => {
-            println!("{}", String::from_utf8_lossy(&data).replace("\\n", "\n"));
+            match output {
+                ResponseOutput::Text => {
+                    println!("{}", String::from_utf8_lossy(&data).replace("\\n", "\n"));
+                }
+                ResponseOutput::Raw(Some(path)) => {
+                    std::fs::write(&path, &data)
+                        .with_context(|| format!("write data to {}", path.display()))?;
+                    println!("Data written to {}", path.display());
+                }
+                ResponseOutput::Raw(None) => std::io::stdout().write_all(&data)?,
+                ResponseOutput::ImportReport(path) => {
+                    let report: ImportReport = serde_json::from_slice(&data)?;
+                    println!("{}", report.summary());
+                    if report.needs_review() {
+                        std::fs::write(&path, report.details()).with_context(|| {
+                            format!("write import report to {}", path.display())
+                        })?;
+                        println!(
+                            "Entries that weren't imported are listed in {}",
+                            path.display()
+                        );
+                    }
+                }
+            }
             std::process::exit(0);
         }
     }
 }
 
+/// How the data of a successful response is outputted
+enum ResponseOutput {
+    /// Print the data as a text
+    Text,
+    /// Write the data as-is, into a file if specified or the standard output otherwise
+    Raw(Option<PathBuf>),
+    /// Print the summary of a file import and write its report into a file if needed
+    ImportReport(PathBuf),
+}
+
+fn import_report_path(args: &ArgMatches) -> PathBuf {
+    args.get_one::<PathBuf>("report")
+        .cloned()
+        .unwrap_or_else(|| {
+            let path = args
+                .get_one::<PathBuf>("path")
+                .expect("path arg is required");
+            let mut report = path.clone().into_os_string();
+            report.push(".report.txt");
+            report.into()
+        })
+}
+
+fn handle_library_subcommand(args: &ArgMatches) -> Result<Request> {
+    let (cmd, args) = args.subcommand().expect("library subcommand is required");
+    let file = args
+        .get_one::<PathBuf>("file")
+        .expect("file arg is required");
+
+    let command = match cmd {
+        "backup" => {
+            println!("Backing up the library, this may take a while...\n");
+            LibraryCommand::Backup
+        }
+        "restore" => {
+            let data = std::fs::read(file)
+                .with_context(|| format!("read backup file {}", file.display()))?;
+            let backup: LibraryBackup =
+                serde_json::from_slice(&data).context("parse backup file")?;
+            backup.validate()?;
+
+            LibraryCommand::Restore {
+                backup: Box::new(backup),
+                dry_run: args.get_flag("dry_run"),
+            }
+        }
+        _ => unreachable!(),
+    };
+
+    Ok(Request::Library(command))
+}
+
+#[cfg(feature = "daemon")]
+fn handle_alarm_subcommand(args: &ArgMatches) -> Result<Request> {
+    let (cmd, args) = args.subcommand().expect("alarm subcommand is required");
+
+    let command = match cmd {
+        "add" => {
+            let context_type = *args
+                .get_one::<AlarmContextType>("context_type")
+                .expect("context_type is required");
+            let id_or_name = try_get_id_or_name(args);
+            match (context_type, &id_or_name) {
+                (AlarmContextType::LikedTracks, Some(_)) => {
+                    anyhow::bail!("--id and --name can't be used with liked tracks")
+                }
+                (AlarmContextType::Playlist | AlarmContextType::Album, None) => {
+                    anyhow::bail!("--id or --name is required for a playlist or album")
+                }
+                _ => {}
+            }
+
+            AlarmCommand::Add {
+                schedule: args
+                    .get_one::<String>("schedule")
+                    .expect("schedule arg is required")
+                    .parse()?,
+                context_type,
+                id_or_name,
+                device: args.get_one::<String>("device").cloned(),
+                volume: args.get_one::<u8>("volume").copied(),
+                fade_in_secs: *args
+                    .get_one::<u64>("fade_in")
+                    .expect("fade_in has a default value"),
+                shuffle: args.get_flag("shuffle"),
+            }
+        }
+        "list" => AlarmCommand::List,
+        "remove" => AlarmCommand::Remove {
+            id: *args.get_one::<u32>("id").expect("id arg is required"),
+        },
+        _ => unreachable!(),
+    };
+
+    Ok(Request::Alarm(command))
+}
+
+fn handle_queue_subcommand(args: &ArgMatches) -> Request {
+    let (cmd, args) = args.subcommand().expect("queue subcommand is required");
+    let get_item_type = |args: &ArgMatches| {
+        *args
+            .get_one::<QueueItemType>("item_type")
+            .expect("item_type is required")
+    };
+    let get_position = |args: &ArgMatches, name: &str| {
+        *args
+            .get_one::<usize>(name)
+            .expect("position arg is required")
+    };
+
+    let command = match cmd {
+        "add" => QueueCommand::Add(get_item_type(args), get_id_or_name(args)),
+        "play-next" => QueueCommand::PlayNext(get_item_type(args), get_id_or_name(args)),
+        "remove" => QueueCommand::Remove {
+            position: get_position(args, "position"),
+        },
+        "clear" => QueueCommand::Clear,
+        "list" => QueueCommand::List,
+        "move" => QueueCommand::Move {
+            from: get_position(args, "from"),
+            to: get_position(args, "to"),
+        },
+        "save" => QueueCommand::Save {
+            name: args
+                .get_one::<String>("name")
+                .expect("name arg is required")
+                .to_owned(),
+        },
+        _ => unreachable!(),
+    };
+
+    Request::Queue(command)
+}
+
+/// Subscribe to the running client's state events and print them until the connection is closed
+fn handle_watch_command(mut conn: Connection) -> Result<()> {
+    conn.send(&Request::Subscribe)
+        .context("send subscribe request to the client")?;
+
+    loop {
+        // events can be arbitrarily far apart, so wait for them without a timeout
+        match conn.receive(std::time::Duration::ZERO)? {
+            Response::Err(err) => {
+                eprintln!("{}", String::from_utf8_lossy(&err));
+                std::process::exit(1);
+            }
+            Response::Ok(data) => {
+                println!("{}", String::from_utf8_lossy(&data));
+            }
+        }
+    }
+}
+
 fn handle_playlist_subcommand(args: &ArgMatches) -> Result<Request> {
     let (cmd, args) = args.subcommand().expect("playlist subcommand is required");
     let command = match cmd {
@@ -363,12 +590,146 @@ fn handle_playlist_subcommand(args: &ArgMatches) -> Result<Request> {
                 album_id,
             }
         }
+        "dedupe" => {
+            let id = PlaylistId::from_id(
+                args.get_one::<String>("id")
+                    .expect("id arg is required")
+                    .to_owned(),
+            )?;
+            let by = *args
+                .get_one::<DedupeKey>("by")
+                .expect("by should have a default value");
+
+            PlaylistCommand::Dedupe {
+                id,
+                by,
+                dry_run: args.get_flag("dry_run"),
+            }
+        }
+        "sort" => {
+            let id = PlaylistId::from_id(
+                args.get_one::<String>("id")
+                    .expect("id arg is required")
+                    .to_owned(),
+            )?;
+
+            PlaylistCommand::Sort {
+                id,
+                by: *args
+                    .get_one::<TrackOrder>("by")
+                    .expect("by arg is required"),
+                reverse: args.get_flag("reverse"),
+            }
+        }
+        "combine" => {
+            let playlists = args
+                .get_many::<String>("ids")
+                .expect("ids arg is required")
+                .map(|id| PlaylistId::from_id(id.to_owned()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            PlaylistCommand::Combine {
+                op: *args
+                    .get_one::<SetOperation>("op")
+                    .expect("op arg is required"),
+                playlists,
+                into: args
+                    .get_one::<String>("into")
+                    .expect("into arg is required")
+                    .to_owned(),
+                preserve_order: args.get_flag("preserve_order"),
+                dedupe: args.get_flag("dedupe"),
+            }
+        }
+        "import-file" => {
+            let path = args
+                .get_one::<PathBuf>("path")
+                .expect("path arg is required");
+            let to_s = args.get_one::<String>("to").expect("to arg is required");
+            let to = PlaylistId::from_id(to_s.to_owned())?;
+            let format = args
+                .get_one::<ImportFileFormat>("format")
+                .copied()
+                .unwrap_or_else(|| ImportFileFormat::from_path(path));
+
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("read import file {}", path.display()))?;
+            let entries = import::parse_entries(&content, format);
+            if entries.is_empty() {
+                anyhow::bail!("no entries found in {}", path.display());
+            }
+
+            println!("Importing {} entries into '{to_s}'...\n", entries.len());
+            PlaylistCommand::ImportFile {
+                to,
+                entries,
+                accept_low_confidence: args.get_flag("accept_low_confidence"),
+            }
+        }
+        "export" => {
+            let source = if let Some(id) = args.get_one::<String>("id") {
+                ExportSource::Playlist(PlaylistId::from_id(id.to_owned())?)
+            } else if let Some(id) = args.get_one::<String>("album_id") {
+                ExportSource::Album(AlbumId::from_id(id.to_owned())?)
+            } else if args.get_flag("liked") {
+                ExportSource::LikedTracks
+            } else {
+                ExportSource::Queue
+            };
+
+            let format = *args
+                .get_one::<ExportFormat>("format")
+                .expect("format should have a default value");
+
+            PlaylistCommand::Export { source, format }
+        }
         _ => unreachable!(),
     };
 
     Ok(Request::Playlist(command))
 }
 
+fn print_history(configs: &config::Configs, args: &ArgMatches) -> Result<()> {
+    let mut records = crate::state::load_history(&configs.cache_folder)?;
+    if let Some(since) = args.get_one::<chrono::NaiveDate>("since") {
+        records.retain(|r| r.started_at.with_timezone(&chrono::Local).date_naive() >= *since);
+    }
+    let limit = *args
+        .get_one::<usize>("limit")
+        .expect("limit has a default value");
+    if limit > 0 {
+        records.drain(..records.len().saturating_sub(limit));
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    for record in records {
+        if args.get_flag("json") {
+            writeln!(stdout, "{}", serde_json::to_string(&record)?)?;
+            continue;
+        }
+        let listened =
+            chrono::Duration::milliseconds(i64::try_from(record.listened_ms).unwrap_or(i64::MAX));
+        writeln!(
+            stdout,
+            "{}  {} - {}  [{}/{}]{}{}",
+            record
+                .started_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+            record.artists.join(", "),
+            record.name,
+            crate::utils::format_duration(&listened),
+            crate::utils::format_duration(&chrono::Duration::milliseconds(record.duration_ms)),
+            if record.skipped { " skipped" } else { "" },
+            record
+                .device
+                .map(|d| format!(" on {d}"))
+                .unwrap_or_default(),
+        )?;
+    }
+    Ok(())
+}
+
 macro_rules! print_feature {
     ($feature:literal) => {
         #[cfg(feature = $feature)]
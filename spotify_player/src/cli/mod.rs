@@ -1,14 +1,26 @@
 mod client;
 mod commands;
+mod completion;
 mod handlers;
+mod socket;
 
-use crate::config;
-use rspotify::model::{AlbumId, ArtistId, Id, PlaylistId, TrackId};
+use crate::{
+    backup::LibraryBackup,
+    config::{self, TokenScope},
+    export::{ExportFormat, ExportSource},
+    import::ImportEntry,
+    playlist_edit::{DedupeKey, SetOperation},
+    state::{SleepMode, TimeRange, TrackOrder},
+};
+use rspotify::model::{AlbumId, ArtistId, EpisodeId, Id, PlaylistId, ShowId, TrackId};
 use serde::{Deserialize, Serialize};
 
-const MAX_REQUEST_SIZE: usize = 4096;
+#[cfg(feature = "daemon")]
+use crate::alarm::Schedule;
 
-pub use client::start_socket;
+#[cfg(feature = "daemon")]
+pub use client::{start_alarm_scheduler, start_playlist_sync_scheduler};
+pub use client::{start_remote_socket, start_socket};
 pub use handlers::handle_cli_subcommand;
 
 #[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone)]
@@ -19,7 +31,10 @@ pub enum Key {
     UserLikedTracks,
     UserSavedAlbums,
     UserFollowedArtists,
+    UserSavedShows,
+    UserSavedAudiobooks,
     UserTopTracks,
+    UserTopArtists,
     Queue,
 }
 
@@ -28,6 +43,7 @@ pub enum ContextType {
     Playlist,
     Album,
     Artist,
+    Show,
 }
 
 #[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone)]
@@ -36,6 +52,16 @@ pub enum ItemType {
     Album,
     Artist,
     Track,
+    Show,
+    Episode,
+}
+
+#[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone, Copy)]
+pub enum QueueItemType {
+    Track,
+    Episode,
+    Album,
+    Playlist,
 }
 
 /// Spotify item's ID
@@ -44,11 +70,25 @@ enum ItemId {
     Artist(ArtistId<'static>),
     Album(AlbumId<'static>),
     Track(TrackId<'static>),
+    Show(ShowId<'static>),
+    Episode(EpisodeId<'static>),
+}
+
+/// Kind of names completed dynamically by the shell completion
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Devices,
+    Playlists,
+    /// playlist IDs, described by the playlists' names
+    PlaylistIds,
+    Artists,
+    Albums,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum GetRequest {
-    Key(Key),
+    /// Get data by key, the time range is only used by the user's top items
+    Key(Key, Option<TimeRange>),
     Item(ItemType, IdOrName),
 }
 
@@ -94,6 +134,91 @@ pub enum PlaylistCommand {
         track_id: Option<TrackId<'static>>,
         album_id: Option<AlbumId<'static>>,
     },
+    Export {
+        source: ExportSource,
+        format: ExportFormat,
+    },
+    Dedupe {
+        id: PlaylistId<'static>,
+        by: DedupeKey,
+        dry_run: bool,
+    },
+    Sort {
+        id: PlaylistId<'static>,
+        by: TrackOrder,
+        reverse: bool,
+    },
+    Combine {
+        op: SetOperation,
+        playlists: Vec<PlaylistId<'static>>,
+        /// ID or name of an owned playlist, a new playlist is created if none matches
+        into: String,
+        preserve_order: bool,
+        dedupe: bool,
+    },
+    ImportFile {
+        to: PlaylistId<'static>,
+        entries: Vec<ImportEntry>,
+        /// Also import matches whose score is below the confidence threshold
+        accept_low_confidence: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum LibraryCommand {
+    Backup,
+    Restore {
+        backup: Box<LibraryBackup>,
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum QueueCommand {
+    Add(QueueItemType, IdOrName),
+    PlayNext(QueueItemType, IdOrName),
+    /// Remove the item at a (1-based) position of the listed queue
+    Remove {
+        position: usize,
+    },
+    Clear,
+    List,
+    /// Move the item at a (1-based) position of the listed queue to another position
+    Move {
+        from: usize,
+        to: usize,
+    },
+    /// Save the queue, starting with the current track, as a new playlist
+    Save {
+        name: String,
+    },
+}
+
+#[cfg(feature = "daemon")]
+#[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone, Copy)]
+pub enum AlarmContextType {
+    Playlist,
+    Album,
+    LikedTracks,
+}
+
+#[cfg(feature = "daemon")]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum AlarmCommand {
+    Add {
+        schedule: Schedule,
+        context_type: AlarmContextType,
+        /// The playlist or album to play, unused for liked tracks
+        id_or_name: Option<IdOrName>,
+        device: Option<String>,
+        volume: Option<u8>,
+        fade_in_secs: u64,
+        shuffle: bool,
+    },
+    List,
+    Remove {
+        id: u32,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -102,8 +227,11 @@ pub enum Command {
         context_type: ContextType,
         id_or_name: IdOrName,
         shuffle: bool,
+        /// The episode to start a show context from
+        episode: Option<IdOrName>,
     },
     StartTrack(IdOrName),
+    StartEpisode(IdOrName),
     StartLikedTracks {
         limit: usize,
         random: bool,
@@ -121,6 +249,12 @@ pub enum Command {
         is_offset: bool,
     },
     Seek(i64),
+    /// Start a sleep timer pausing the playback, or cancel the current timer if `mode` is `None`
+    Sleep {
+        mode: Option<SleepMode>,
+        /// Duration of the volume fade-out, the `sleep_timer_fade_secs` config if not specified
+        fade_secs: Option<u64>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -128,10 +262,25 @@ pub enum Request {
     Get(GetRequest),
     Playback(Command),
     Connect(IdOrName),
-    Like { unlike: bool },
+    Like {
+        unlike: bool,
+    },
     Playlist(PlaylistCommand),
-    Search { query: String },
-    Lyrics { id_or_name: Option<IdOrName> },
+    Queue(QueueCommand),
+    Library(LibraryCommand),
+    #[cfg(feature = "daemon")]
+    Alarm(AlarmCommand),
+    Search {
+        query: String,
+    },
+    Lyrics {
+        id_or_name: Option<IdOrName>,
+    },
+    /// Get the shell completion candidates of a kind
+    Complete(CompletionKind),
+    /// Subscribe to the application's state events.
+    /// The connection then receives a `Response` for each event until it is closed.
+    Subscribe,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -146,6 +295,48 @@ impl From<ContextType> for ItemType {
             ContextType::Playlist => Self::Playlist,
             ContextType::Album => Self::Album,
             ContextType::Artist => Self::Artist,
+            ContextType::Show => Self::Show,
+        }
+    }
+}
+
+impl Request {
+    /// Get the scope a remote client's token must have for the request to be handled
+    pub fn required_scope(&self) -> TokenScope {
+        match self {
+            Request::Get(_)
+            | Request::Search { .. }
+            | Request::Lyrics { .. }
+            | Request::Complete(_)
+            | Request::Subscribe
+            | Request::Queue(QueueCommand::List)
+            | Request::Playlist(
+                PlaylistCommand::List
+                | PlaylistCommand::Export { .. }
+                | PlaylistCommand::Dedupe { dry_run: true, .. },
+            )
+            | Request::Library(
+                LibraryCommand::Backup | LibraryCommand::Restore { dry_run: true, .. },
+            ) => TokenScope::Read,
+            #[cfg(feature = "daemon")]
+            Request::Alarm(AlarmCommand::List) => TokenScope::Read,
+            #[cfg(feature = "daemon")]
+            Request::Alarm(_) => TokenScope::Control,
+            Request::Playback(_) | Request::Connect(_) | Request::Queue(_) => TokenScope::Control,
+            Request::Like { .. } | Request::Playlist(_) | Request::Library(_) => {
+                TokenScope::Library
+            }
+        }
+    }
+
+    /// Get the timeout for receiving the request's response, zero (no timeout)
+    /// for a request whose handling time grows with its input, e.g. a large import
+    pub fn response_timeout(&self) -> std::time::Duration {
+        match self {
+            Request::Playlist(PlaylistCommand::ImportFile { .. }) | Request::Library(_) => {
+                std::time::Duration::ZERO
+            }
+            _ => socket::RESPONSE_TIMEOUT,
         }
     }
 }
@@ -157,6 +348,8 @@ impl ItemId {
             ItemId::Artist(id) => id.uri(),
             ItemId::Album(id) => id.uri(),
             ItemId::Track(id) => id.uri(),
+            ItemId::Show(id) => id.uri(),
+            ItemId::Episode(id) => id.uri(),
         }
     }
 }
@@ -175,10 +368,20 @@ pub fn init_cli() -> anyhow::Result<clap::Command> {
         .subcommand(commands::init_like_command())
         .subcommand(commands::init_authenticate_command())
         .subcommand(commands::init_playlist_subcommand())
+        .subcommand(commands::init_queue_subcommand())
+        .subcommand(commands::init_library_subcommand())
         .subcommand(commands::init_generate_command())
         .subcommand(commands::init_search_command())
         .subcommand(commands::init_print_features_command())
         .subcommand(commands::init_lyrics_command())
+        .subcommand(commands::init_watch_command())
+        .subcommand(commands::init_history_command())
+        .subcommand(commands::init_complete_command());
+
+    #[cfg(feature = "daemon")]
+    let cmd = cmd.subcommand(commands::init_alarm_subcommand());
+
+    let cmd = cmd
         .arg(
             clap::Arg::new("theme")
                 .short('t')
@@ -222,3 +425,60 @@ pub fn init_cli() -> anyhow::Result<clap::Command> {
 
     Ok(cmd)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dedupe(dry_run: bool) -> Request {
+        Request::Playlist(PlaylistCommand::Dedupe {
+            id: PlaylistId::from_id("37i9dQZF1DXcBWIGoYBM5M").unwrap(),
+            by: DedupeKey::Id,
+            dry_run,
+        })
+    }
+
+    #[test]
+    fn request_scopes() {
+        assert_eq!(Request::Subscribe.required_scope(), TokenScope::Read);
+        assert_eq!(
+            Request::Queue(QueueCommand::List).required_scope(),
+            TokenScope::Read
+        );
+        assert_eq!(
+            Request::Queue(QueueCommand::Clear).required_scope(),
+            TokenScope::Control
+        );
+        assert_eq!(
+            Request::Like { unlike: false }.required_scope(),
+            TokenScope::Library
+        );
+        assert_eq!(
+            Request::Library(LibraryCommand::Backup).required_scope(),
+            TokenScope::Read
+        );
+    }
+
+    #[test]
+    fn long_running_requests_have_no_response_timeout() {
+        let import = Request::Playlist(PlaylistCommand::ImportFile {
+            to: PlaylistId::from_id("37i9dQZF1DXcBWIGoYBM5M").unwrap(),
+            entries: vec![],
+            accept_low_confidence: false,
+        });
+        assert!(import.response_timeout().is_zero());
+        assert!(Request::Library(LibraryCommand::Backup)
+            .response_timeout()
+            .is_zero());
+        assert_eq!(
+            Request::Queue(QueueCommand::List).response_timeout(),
+            socket::RESPONSE_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn dry_runs_only_require_read_scope() {
+        assert_eq!(dedupe(true).required_scope(), TokenScope::Read);
+        assert_eq!(dedupe(false).required_scope(), TokenScope::Library);
+    }
+}
@@ -0,0 +1,467 @@
+//! Transport used by CLI commands to communicate with a running client.
+//!
+//! The client listens on a Unix domain socket inside the cache folder and falls back to
+//! a TCP socket on `127.0.0.1:{client_port}` if the Unix socket cannot be used.
+//! It can also listen on `client_listen_address` for CLI commands from other machines,
+//! whose requests are only handled if the connection's token has the required scope.
+//!
+//! Every message is JSON-encoded and sent as a frame prefixed by its length (a big-endian `u32`).
+//! A connection starts with a handshake in which both sides exchange their protocol version,
+//! the peer also sending its token if it has one.
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config;
+
+/// Version of the socket protocol.
+///
+/// It must be bumped whenever a variant or field of `Request`, `Response` or their nested
+/// types (e.g. a new CLI command) is added, removed or changed, because a peer at another
+/// version would pass the handshake and then fail to deserialize the frames.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Maximum size of a frame, used to reject garbage data from a non-`spotify_player` peer
+pub const MAX_FRAME_SIZE: usize = 256 * 1024 * 1024;
+/// Maximum size of a frame sent before a peer is authorized, e.g. the handshake
+pub const MAX_UNAUTHORIZED_FRAME_SIZE: usize = 64 * 1024;
+
+const SOCKET_FILE_NAME: &str = "spotify_player.sock";
+
+/// Timeout for connecting to a client and exchanging the handshake
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Timeout for receiving a response after sending a request
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_mins(5);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Handshake {
+    pub version: u32,
+    /// token authorizing the peer's requests to a remote listener
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Path to the client's Unix domain socket
+pub fn socket_path(cache_folder: &Path) -> PathBuf {
+    cache_folder.join(SOCKET_FILE_NAME)
+}
+
+fn check_frame_size(len: usize, max: usize) -> Result<()> {
+    if len > max {
+        anyhow::bail!("frame of {len} bytes exceeds the maximum size of {max} bytes");
+    }
+    Ok(())
+}
+
+fn encode_frame<T: Serialize>(msg: &T) -> Result<Vec<u8>> {
+    let data = serde_json::to_vec(msg)?;
+    check_frame_size(data.len(), MAX_FRAME_SIZE)?;
+    let len = u32::try_from(data.len()).context("frame is too large")?;
+
+    let mut frame = Vec::with_capacity(data.len() + 4);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&data);
+    Ok(frame)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Server side
+// ------------------------------------------------------------------------------------------------
+
+/// A bidirectional stream accepted by a `Listener`
+pub trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for T {}
+
+pub enum Listener {
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+    Tcp(tokio::net::TcpListener),
+}
+
+impl Listener {
+    /// Bind the client's listener, preferring the Unix domain socket and
+    /// falling back to the TCP socket if it cannot be used.
+    pub async fn bind(configs: &config::Configs) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            let path = socket_path(&configs.cache_folder);
+            match bind_unix(&path) {
+                Ok(listener) => {
+                    tracing::info!("Starting a client socket at {}", path.display());
+                    return Ok(Self::Unix(listener));
+                }
+                Err(err) => tracing::warn!(
+                    "Failed to bind a Unix socket at {}, falling back to TCP: {err:#}",
+                    path.display()
+                ),
+            }
+        }
+
+        let port = configs.app_config.client_port;
+        tracing::info!("Starting a client socket at 127.0.0.1:{port}");
+        Ok(Self::Tcp(
+            tokio::net::TcpListener::bind(("127.0.0.1", port)).await?,
+        ))
+    }
+
+    /// Bind a TCP listener for remote clients on `client_listen_address`
+    pub async fn bind_remote(addr: &str) -> Result<Self> {
+        tracing::info!("Starting a remote client socket at {addr}");
+        Ok(Self::Tcp(tokio::net::TcpListener::bind(addr).await?))
+    }
+
+    /// Bind a TCP listener on an OS-assigned local port
+    pub async fn bind_local() -> Result<(Self, std::net::SocketAddr)> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        Ok((Self::Tcp(listener), addr))
+    }
+
+    pub async fn accept(&self) -> std::io::Result<Box<dyn AsyncStream>> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+            Self::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> Result<tokio::net::UnixListener> {
+    if path.exists() {
+        // a socket file left behind by an instance that didn't exit cleanly can be removed,
+        // but one that still accepts connections belongs to another running instance
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("another instance is already listening on the socket");
+        }
+        std::fs::remove_file(path).context("remove stale socket file")?;
+    }
+    Ok(tokio::net::UnixListener::bind(path)?)
+}
+
+/// Read a frame's raw data of at most `max_size` bytes from a stream,
+/// returning `None` if the peer closed the connection
+pub async fn read_frame<S: AsyncRead + Unpin + ?Sized>(
+    stream: &mut S,
+    max_size: usize,
+) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    check_frame_size(len, max_size)?;
+
+    let mut data = vec![0; len];
+    stream.read_exact(&mut data).await?;
+    Ok(Some(data))
+}
+
+pub async fn write_message<S: AsyncWrite + Unpin + ?Sized, T: Serialize>(
+    stream: &mut S,
+    msg: &T,
+) -> Result<()> {
+    stream.write_all(&encode_frame(msg)?).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Handle the handshake of a newly accepted connection, returning the peer's token.
+///
+/// The server's version is always sent back so that the peer can report a mismatch.
+pub async fn accept_handshake<S: AsyncStream + ?Sized>(stream: &mut S) -> Result<Option<String>> {
+    let data = read_frame(stream, MAX_UNAUTHORIZED_FRAME_SIZE)
+        .await?
+        .context("connection closed before the handshake")?;
+    let handshake: Handshake =
+        serde_json::from_slice(&data).context("invalid handshake message")?;
+
+    write_message(
+        stream,
+        &Handshake {
+            version: PROTOCOL_VERSION,
+            token: None,
+        },
+    )
+    .await?;
+
+    if handshake.version != PROTOCOL_VERSION {
+        anyhow::bail!(
+            "protocol version mismatch: peer uses version {}, expected version {PROTOCOL_VERSION}",
+            handshake.version
+        );
+    }
+    Ok(handshake.token)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Client side
+// ------------------------------------------------------------------------------------------------
+
+/// A blocking connection to a client's socket
+pub enum Connection {
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+    Tcp(std::net::TcpStream),
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(s) => s.read(buf),
+            Self::Tcp(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(s) => s.write(buf),
+            Self::Tcp(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(s) => s.flush(),
+            Self::Tcp(s) => s.flush(),
+        }
+    }
+}
+
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+impl Connection {
+    /// Connect to a running client, returning `None` if no running client is found.
+    ///
+    /// If `remote_client_address` is configured, the remote client is connected to instead.
+    pub fn connect(configs: &config::Configs) -> Result<Option<Self>> {
+        Self::connect_with_timeout(configs, CONNECT_TIMEOUT)
+    }
+
+    /// Connect to a running client like `connect`, waiting at most `timeout`
+    /// for connecting and for each message of the handshake
+    pub fn connect_with_timeout(
+        configs: &config::Configs,
+        timeout: Duration,
+    ) -> Result<Option<Self>> {
+        if let Some(ref addr) = configs.app_config.remote_client_address {
+            let addr = std::net::ToSocketAddrs::to_socket_addrs(addr.as_str())
+                .with_context(|| format!("resolve remote client address {addr}"))?
+                .next()
+                .with_context(|| format!("no address found for remote client {addr}"))?;
+            return Self::connect_tcp(
+                addr,
+                configs.app_config.remote_client_token.as_deref(),
+                timeout,
+            )
+            .map(Some);
+        }
+
+        #[cfg(unix)]
+        {
+            let path = socket_path(&configs.cache_folder);
+            match std::os::unix::net::UnixStream::connect(&path) {
+                Ok(stream) => return Self::Unix(stream).handshake(None, timeout).map(Some),
+                // no socket file or a stale one, try the TCP socket instead
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+                    ) => {}
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("connect to socket {}", path.display()))
+                }
+            }
+        }
+
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], configs.app_config.client_port));
+        match Self::connect_tcp(addr, None, timeout) {
+            Ok(conn) => Ok(Some(conn)),
+            Err(err)
+                if err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|err| err.kind() == std::io::ErrorKind::ConnectionRefused) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn connect_tcp(
+        addr: std::net::SocketAddr,
+        token: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let stream = match std::net::TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => stream,
+            Err(err) if is_timeout(&err) => {
+                anyhow::bail!("timed out after {timeout:?} connecting to the client at {addr}")
+            }
+            Err(err) => return Err(err.into()),
+        };
+        Self::Tcp(stream).handshake(token, timeout)
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(s) => {
+                s.set_read_timeout(timeout)?;
+                s.set_write_timeout(timeout)
+            }
+            Self::Tcp(s) => {
+                s.set_read_timeout(timeout)?;
+                s.set_write_timeout(timeout)
+            }
+        }
+    }
+
+    fn handshake(mut self, token: Option<&str>, timeout: Duration) -> Result<Self> {
+        self.set_timeout(Some(timeout))?;
+        self.send(&Handshake {
+            version: PROTOCOL_VERSION,
+            token: token.map(str::to_string),
+        })?;
+        let handshake: Handshake = self
+            .receive(timeout)
+            .context("invalid handshake response, is the socket owned by `spotify_player`?")?;
+        if handshake.version != PROTOCOL_VERSION {
+            anyhow::bail!(
+                "protocol version mismatch: this CLI uses version {PROTOCOL_VERSION} but the running client uses version {}, \
+                 restart the running `spotify_player` instance after upgrading",
+                handshake.version
+            );
+        }
+        Ok(self)
+    }
+
+    pub fn send<T: Serialize>(&mut self, msg: &T) -> Result<()> {
+        self.write_all(&encode_frame(msg)?)?;
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Receive a message, waiting at most `timeout` (forever if zero)
+    pub fn receive<T: DeserializeOwned>(&mut self, timeout: Duration) -> Result<T> {
+        self.set_timeout((!timeout.is_zero()).then_some(timeout))?;
+
+        let map_err = |err: std::io::Error| match err.kind() {
+            _ if is_timeout(&err) => anyhow::anyhow!(
+                "timed out after {}s waiting for a response from the client",
+                timeout.as_secs()
+            ),
+            std::io::ErrorKind::UnexpectedEof => {
+                anyhow::anyhow!("connection closed by the client")
+            }
+            _ => err.into(),
+        };
+
+        let mut len = [0; 4];
+        self.read_exact(&mut len).map_err(map_err)?;
+        let len = u32::from_be_bytes(len) as usize;
+        check_frame_size(len, MAX_FRAME_SIZE)?;
+
+        let mut data = vec![0; len];
+        self.read_exact(&mut data).map_err(map_err)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn frame_round_trip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_message(&mut client, &vec!["a", "b"]).await.unwrap();
+
+        let data = read_frame(&mut server, MAX_FRAME_SIZE)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&data[..], b"[\"a\",\"b\"]");
+    }
+
+    #[tokio::test]
+    async fn read_frame_on_closed_connection() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        drop(client);
+
+        assert!(read_frame(&mut server, MAX_FRAME_SIZE)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn read_frame_rejects_oversized_frame() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&1_000u32.to_be_bytes()).await.unwrap();
+
+        assert!(read_frame(&mut server, 100).await.is_err());
+    }
+
+    async fn send_handshake(version: u32, token: Option<&str>) -> (Result<Option<String>>, u32) {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_message(
+            &mut client,
+            &Handshake {
+                version,
+                token: token.map(str::to_string),
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = accept_handshake(&mut server).await;
+        let data = read_frame(&mut client, MAX_FRAME_SIZE)
+            .await
+            .unwrap()
+            .unwrap();
+        let response: Handshake = serde_json::from_slice(&data).unwrap();
+        (result, response.version)
+    }
+
+    #[tokio::test]
+    async fn handshake_returns_peer_token() {
+        let (result, version) = send_handshake(PROTOCOL_VERSION, Some("secret")).await;
+        assert_eq!(result.unwrap().as_deref(), Some("secret"));
+        assert_eq!(version, PROTOCOL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn handshake_version_mismatch() {
+        // the server's version is still sent back so that the peer can report the mismatch
+        let (result, version) = send_handshake(PROTOCOL_VERSION + 1, None).await;
+        assert!(result.is_err());
+        assert_eq!(version, PROTOCOL_VERSION);
+    }
+}
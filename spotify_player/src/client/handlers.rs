@@ -6,16 +6,26 @@ use tracing::Instrument;
 
 use crate::{
     config,
-    state::{ContextId, ContextPageType, ContextPageUIState, PageState, PlayableId, SharedState},
+    scrobble::{self, Listen},
+    state::{
+        self, ContextId, ContextPageType, ContextPageUIState, PageState, PlayerState, SharedState,
+        Track,
+    },
 };
 
 use crate::utils::map_join;
 
-use super::ClientRequest;
+use super::{ClientRequest, PlayerRequest};
 
 struct PlayerEventHandlerState {
     get_context_timer: Instant,
+    queue_requested_at: Instant,
     last_playback_refresh_timer: Instant,
+    #[cfg(feature = "streaming")]
+    custom_queue_save_timer: Instant,
+    /// The custom queue last saved into the cache folder
+    #[cfg(feature = "streaming")]
+    saved_custom_queue: Option<state::SavedCustomQueue>,
 }
 
 /// starts the client's request handler
@@ -40,6 +50,22 @@ pub async fn start_client_handler(
     }
 }
 
+/// Interval between two submissions of the listens queued after failed scrobbles
+const SCROBBLE_RETRY_INTERVAL: Duration = Duration::from_mins(5);
+
+/// Periodically submit the listens queued after failed scrobbles, e.g. while offline
+pub async fn start_scrobble_retrier(client: super::AppClient) {
+    let mut interval = tokio::time::interval(SCROBBLE_RETRY_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(err) = client.submit_queued_listens().await {
+            tracing::error!("Failed to submit the queued listens: {err:#}");
+        }
+    }
+}
+
 /// Interval between background session-validity checks.
 const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
@@ -60,22 +86,19 @@ pub async fn start_session_watcher(state: SharedState, client: super::AppClient)
 fn handle_playback_change_event(
     state: &SharedState,
     client_pub: &flume::Sender<ClientRequest>,
+    handler_state: &mut PlayerEventHandlerState,
 ) -> anyhow::Result<()> {
     let player = state.player.read();
-    let (playback, id, duration) = match (
+    let (playback, uri, duration) = match (
         player.buffered_playback.as_ref(),
         player.currently_playing(),
     ) {
-        (Some(playback), Some(rspotify::model::PlayableItem::Track(track))) => (
-            playback,
-            PlayableId::Track(track.id.clone().expect("null track_id")),
-            track.duration,
-        ),
-        (Some(playback), Some(rspotify::model::PlayableItem::Episode(episode))) => (
-            playback,
-            PlayableId::Episode(episode.id.clone()),
-            episode.duration,
-        ),
+        (Some(playback), Some(item @ rspotify::model::PlayableItem::Track(track))) => {
+            (playback, state::playable_item_uri(item), track.duration)
+        }
+        (Some(playback), Some(item @ rspotify::model::PlayableItem::Episode(episode))) => {
+            (playback, state::playable_item_uri(item), episode.duration)
+        }
         _ => return Ok(()),
     };
 
@@ -86,15 +109,19 @@ fn handle_playback_change_event(
         }
     }
 
-    if let Some(queue) = player.queue.as_ref() {
-        // queue needs to be updated if its playing track is different from actual playback's playing track
-        if let Some(queue_track) = queue.currently_playing.as_ref() {
-            if queue_track.id().expect("null track_id") != id {
-                client_pub.send(ClientRequest::GetCurrentUserQueue)?;
-            }
-        }
-    } else {
+    // queue needs to be updated if its playing track is different from actual playback's playing track.
+    // To avoid making too many requests while the queue is being updated,
+    // only request if it's been a while since the last request.
+    let outdated_queue = match player.queue.as_ref() {
+        Some(queue) => queue
+            .currently_playing
+            .as_ref()
+            .is_some_and(|queue_track| state::playable_item_uri(queue_track) != uri),
+        None => true,
+    };
+    if outdated_queue && handler_state.queue_requested_at.elapsed() > Duration::from_secs(1) {
         client_pub.send(ClientRequest::GetCurrentUserQueue)?;
+        handler_state.queue_requested_at = Instant::now();
     }
 
     Ok(())
@@ -133,6 +160,7 @@ fn handle_page_change_event(
                             ContextId::Playlist(_) => ContextPageUIState::new_playlist(),
                             ContextId::Tracks(_) => ContextPageUIState::new_tracks(),
                             ContextId::Show(_) => ContextPageUIState::new_show(),
+                            ContextId::Audiobook(_) => ContextPageUIState::new_audiobook(),
                         });
                     }
                     None => {
@@ -184,6 +212,126 @@ fn handle_page_change_event(
     Ok(())
 }
 
+/// Minimum interval between two volume changes during a sleep timer's fade-out
+const SLEEP_FADE_STEP: Duration = Duration::from_secs(1);
+
+fn handle_sleep_timer_event(
+    state: &SharedState,
+    client_pub: &flume::Sender<ClientRequest>,
+) -> anyhow::Result<()> {
+    let context_id = {
+        let player = state.player.read();
+        if player.sleep_timer.is_none() {
+            return Ok(());
+        }
+        // the order of a shuffled context's items is unknown
+        let is_shuffled = player
+            .buffered_playback
+            .as_ref()
+            .is_some_and(|p| p.shuffle_state);
+        player.playing_context_id().filter(|_| !is_shuffled)
+    };
+    let context_last_uri = context_id.and_then(|id| {
+        let data = state.data.read();
+        match data.caches.context.get(&id.uri())? {
+            state::Context::Album { tracks, .. }
+            | state::Context::Playlist { tracks, .. }
+            | state::Context::Tracks { tracks, .. } => tracks.last().map(Track::uri),
+            _ => None,
+        }
+    });
+
+    let requests = state.update_player(|player| update_sleep_timer(player, context_last_uri));
+    for request in requests {
+        client_pub.send(ClientRequest::Player(request))?;
+    }
+    Ok(())
+}
+
+/// Update the sleep timer's state, returning the requests to pause or fade out the playback
+fn update_sleep_timer(
+    player: &mut PlayerState,
+    context_last_uri: Option<String>,
+) -> Vec<PlayerRequest> {
+    if let Some(timer) = player.sleep_timer.as_mut() {
+        timer.context_last_uri = context_last_uri;
+    }
+    let remaining = player.sleep_timer_remaining();
+    let (is_playing, volume) = player
+        .buffered_playback
+        .as_ref()
+        .map_or((false, None), |p| (p.is_playing, p.volume));
+    let Some(timer) = player.sleep_timer.as_mut() else {
+        return vec![];
+    };
+
+    if timer.expired {
+        // restore the volume once the playback is paused
+        if !is_playing {
+            let requests = timer
+                .volume
+                .map(PlayerRequest::Volume)
+                .into_iter()
+                .collect();
+            player.sleep_timer = None;
+            return requests;
+        }
+        return vec![];
+    }
+
+    match remaining {
+        Some(remaining) if remaining.is_zero() => {
+            tracing::info!("Sleep timer expired, pausing the playback...");
+            timer.expired = true;
+            vec![PlayerRequest::Pause]
+        }
+        Some(remaining) if remaining <= timer.fade && is_playing => {
+            if timer.volume.is_none() {
+                timer.volume = volume.and_then(|v| u8::try_from(v).ok());
+            }
+            if timer
+                .last_fade
+                .is_none_or(|t| t.elapsed() >= SLEEP_FADE_STEP)
+            {
+                if let Some(volume) = timer.fade_volume(remaining) {
+                    timer.last_fade = Some(Instant::now());
+                    return vec![PlayerRequest::Volume(volume)];
+                }
+            }
+            vec![]
+        }
+        _ => vec![],
+    }
+}
+
+fn handle_history_event(
+    state: &SharedState,
+    client_pub: &flume::Sender<ClientRequest>,
+) -> anyhow::Result<()> {
+    let update = state.history.lock().observe(&state.player.read());
+    if !scrobble::is_enabled() {
+        if let Some(record) = update.finished {
+            state.save_history_record(record)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(listen) = update.started.as_ref().and_then(Listen::from_record) {
+        client_pub.send(ClientRequest::NowPlaying(listen))?;
+    }
+    if let Some(record) = update.finished {
+        if scrobble::is_listen(&record) {
+            if let Some(listen) = Listen::from_record(&record) {
+                client_pub.send(ClientRequest::Scrobble(listen))?;
+            }
+        }
+        if config::get_config().app_config.enable_listening_history {
+            state.save_history_record(record)?;
+        }
+    }
+    Ok(())
+}
+
 fn handle_player_event(
     state: &SharedState,
     client_pub: &flume::Sender<ClientRequest>,
@@ -191,27 +339,98 @@ fn handle_player_event(
 ) -> anyhow::Result<()> {
     handle_page_change_event(state, client_pub, handler_state)
         .context("handle page change event")?;
-    handle_playback_change_event(state, client_pub).context("handle playback change event")?;
+    handle_playback_change_event(state, client_pub, handler_state)
+        .context("handle playback change event")?;
+    handle_sleep_timer_event(state, client_pub).context("handle sleep timer event")?;
+    if config::get_config().app_config.enable_listening_history || scrobble::is_enabled() {
+        handle_history_event(state, client_pub).context("handle history event")?;
+    }
+    #[cfg(feature = "streaming")]
+    if config::get_config().app_config.resume_custom_queue {
+        handle_custom_queue_save_event(state, handler_state)
+            .context("handle custom queue save event")?;
+    }
+
+    Ok(())
+}
+
+/// Interval between two checks for changes of the custom queue to save
+#[cfg(feature = "streaming")]
+const CUSTOM_QUEUE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Save the custom queue into the cache folder when it changes,
+/// removing the saved queue once there is no custom queue anymore
+#[cfg(feature = "streaming")]
+fn handle_custom_queue_save_event(
+    state: &SharedState,
+    handler_state: &mut PlayerEventHandlerState,
+) -> anyhow::Result<()> {
+    if handler_state.custom_queue_save_timer.elapsed() < CUSTOM_QUEUE_SAVE_INTERVAL {
+        return Ok(());
+    }
+    handler_state.custom_queue_save_timer = Instant::now();
 
+    let (saved, is_playing) = {
+        let player = state.player.read();
+        let is_playing = player
+            .buffered_playback
+            .as_ref()
+            .is_some_and(|p| p.is_playing);
+        (player.saved_custom_queue(), is_playing)
+    };
+    // the progress changes all the time during a playback, so it's only saved
+    // with a changed queue, when the playback is paused or when the application exits
+    let changed = match (&saved, &handler_state.saved_custom_queue) {
+        (Some(saved), Some(last_saved)) => {
+            saved.queue != last_saved.queue
+                || (!is_playing && saved.progress_ms != last_saved.progress_ms)
+        }
+        (saved, last_saved) => saved.is_some() != last_saved.is_some(),
+    };
+    if !changed {
+        return Ok(());
+    }
+
+    state::store_custom_queue(saved.as_ref())
+        .context("store custom queue into the cache folder")?;
+    handler_state.saved_custom_queue = saved;
     Ok(())
 }
 
+/// Maximum interval between playback refreshes while the listening history is recorded,
+/// so that the items played on other devices are recorded
+const HISTORY_PLAYBACK_REFRESH_DURATION: Duration = Duration::from_secs(10);
+
 /// Starts event watcher listening to events and making update requests to the client if needed
 pub fn start_player_event_watcher(state: &SharedState, client_pub: &flume::Sender<ClientRequest>) {
     let configs = config::get_config();
 
     let refresh_duration = Duration::from_millis(100);
-    let playback_refresh_duration =
-        Duration::from_millis(configs.app_config.playback_refresh_duration_in_ms);
+    let mut playback_refresh_duration = Some(Duration::from_millis(
+        configs.app_config.playback_refresh_duration_in_ms,
+    ))
+    .filter(|d| !d.is_zero());
+    if configs.app_config.enable_listening_history || scrobble::is_enabled() {
+        playback_refresh_duration = Some(
+            playback_refresh_duration.map_or(HISTORY_PLAYBACK_REFRESH_DURATION, |d| {
+                d.min(HISTORY_PLAYBACK_REFRESH_DURATION)
+            }),
+        );
+    }
     let mut handler_state = PlayerEventHandlerState {
         get_context_timer: Instant::now(),
+        queue_requested_at: Instant::now(),
         last_playback_refresh_timer: Instant::now(),
+        #[cfg(feature = "streaming")]
+        custom_queue_save_timer: Instant::now(),
+        #[cfg(feature = "streaming")]
+        saved_custom_queue: None,
     };
 
     loop {
-        // periodically refresh the playback state (if enabled in config)
-        if configs.app_config.playback_refresh_duration_in_ms > 0
-            && handler_state.last_playback_refresh_timer.elapsed() >= playback_refresh_duration
+        // periodically refresh the playback state (if enabled in config or to record the history)
+        if playback_refresh_duration
+            .is_some_and(|d| handler_state.last_playback_refresh_timer.elapsed() >= d)
         {
             client_pub
                 .send(ClientRequest::GetCurrentPlayback)
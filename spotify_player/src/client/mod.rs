@@ -2,16 +2,19 @@ use std::collections::HashSet;
 use std::ops::Deref;
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
+use crate::export::{self, ExportFormat, ExportSource};
+use crate::playlist_edit::{self, DedupeKey};
 use crate::state::Lyrics;
 use crate::{auth, config};
 use crate::{
     auth::AuthConfig,
     state::{
-        store_data_into_file_cache, Album, AlbumId, Artist, ArtistId, Category, Context, ContextId,
-        Device, FileCacheKey, Item, ItemId, MemoryCaches, Playback, PlaybackMetadata, Playlist,
-        PlaylistFolderItem, PlaylistId, SearchResults, SharedState, Show, ShowId, Track, TrackId,
-        UserId, TTL_CACHE_DURATION, USER_LIKED_TRACKS_URI, USER_RECENTLY_PLAYED_TRACKS_URI,
-        USER_TOP_TRACKS_URI,
+        store_data_into_file_cache, Album, AlbumId, Artist, ArtistId, Audiobook, AudiobookId,
+        Category, Chapter, Context, ContextId, Device, EpisodeId, FileCacheKey, Item, ItemId,
+        ListeningStats, MemoryCaches, PlayableId, Playback, PlaybackMetadata, Playlist,
+        PlaylistFolderItem, PlaylistId, PopupState, SearchResults, SharedState, Show, ShowId,
+        StateEvent, TimeRange, Track, TrackId, TrackOrder, UserId, TTL_CACHE_DURATION,
+        USER_LIKED_TRACKS_URI, USER_RECENTLY_PLAYED_TRACKS_URI, USER_TOP_TRACKS_URI,
     },
 };
 
@@ -19,6 +22,7 @@ use std::io::Write;
 
 use anyhow::Context as _;
 use anyhow::Result;
+use futures::StreamExt;
 
 use librespot_core::SpotifyUri;
 #[cfg(feature = "streaming")]
@@ -40,6 +44,18 @@ const PLAYBACK_TYPES: [&rspotify::model::AdditionalType; 2] = [
     &rspotify::model::AdditionalType::Track,
     &rspotify::model::AdditionalType::Episode,
 ];
+/// Maximum number of the custom queue's upcoming items displayed in the queue page
+const CUSTOM_QUEUE_PAGE_LIMIT: usize = 200;
+/// Maximum number of concurrent requests for the unplayed episodes of the saved shows
+const MAX_CONCURRENT_SHOW_REQUESTS: usize = 8;
+
+/// Positions of a track's occurrences in a version of a playlist
+#[derive(Debug, Clone, Copy)]
+pub struct PlaylistPositions<'a> {
+    pub positions: &'a [u32],
+    /// the snapshot id of the playlist's version the positions refer to
+    pub snapshot_id: &'a str,
+}
 
 /// The application's Spotify client
 #[derive(Clone)]
@@ -331,7 +347,7 @@ impl AppClient {
                     playback.shuffle_state = shuffle;
                 }
                 let device_id = playback.as_ref().and_then(|p| p.device_id.as_deref());
-                self.start_playback(p, device_id).await?;
+                self.start_playback(p, device_id, None).await?;
                 // For some reasons, when starting a new playback, the integrated `spotify_player`
                 // client doesn't respect the initial shuffle state, so we need to manually update the state
                 if let Some(ref playback) = playback {
@@ -339,6 +355,11 @@ impl AppClient {
                 }
                 return Ok(None);
             }
+            PlayerRequest::StartPlaybackAt(p, position) => {
+                let device_id = playback.as_ref().and_then(|p| p.device_id.as_deref());
+                self.start_playback(p, device_id, Some(position)).await?;
+                return Ok(None);
+            }
             _ => {}
         }
 
@@ -408,7 +429,7 @@ impl AppClient {
 
                 playback.mute_state = new_mute_state;
             }
-            PlayerRequest::StartPlayback(..) => {
+            PlayerRequest::StartPlayback(..) | PlayerRequest::StartPlaybackAt(..) => {
                 anyhow::bail!("`StartPlayback` should be handled earlier")
             }
             PlayerRequest::TransferPlayback(..) => {
@@ -419,6 +440,11 @@ impl AppClient {
         Ok(Some(playback))
     }
 
+    /// Submit the listens queued after failed scrobbles
+    pub async fn submit_queued_listens(&self) -> Result<()> {
+        crate::scrobble::submit_queued_listens(&self.http).await
+    }
+
     /// Handle a client request
     pub(crate) async fn handle_request(
         &self,
@@ -432,6 +458,17 @@ impl AppClient {
                 let categories = self.browse_categories().await?;
                 state.data.write().browse.categories = categories;
             }
+            ClientRequest::GetListeningStats(window) => {
+                let records = crate::state::load_history(&config::get_config().cache_folder)?;
+                let stats = ListeningStats::new(&records, window, &chrono::Local::now());
+                state.data.write().stats = Some(stats);
+            }
+            ClientRequest::NowPlaying(listen) => {
+                crate::scrobble::now_playing(&self.http, &listen).await;
+            }
+            ClientRequest::Scrobble(listen) => {
+                crate::scrobble::scrobble(&self.http, listen).await?;
+            }
             ClientRequest::GetBrowseCategoryPlaylists(category) => {
                 let playlists = self.browse_category_playlists(&category.id).await?;
                 state
@@ -457,6 +494,10 @@ impl AppClient {
             ClientRequest::RestartIntegratedClient => {
                 self.new_session(Some(state), false).await?;
             }
+            #[cfg(feature = "streaming")]
+            ClientRequest::ResumeCustomQueue(progress) => {
+                self.resume_custom_queue(state, progress).await?;
+            }
             ClientRequest::GetCurrentUser => {
                 let user = self.current_user().await?;
                 state.data.write().user_data.user = Some(user);
@@ -464,7 +505,7 @@ impl AppClient {
             ClientRequest::Player(request) => {
                 let playback = state.player.read().buffered_playback.clone();
                 let playback = self.handle_player_request(request, playback).await?;
-                state.player.write().buffered_playback = playback;
+                state.update_player(|player| player.buffered_playback = playback);
                 self.update_playback(state);
             }
             ClientRequest::GetCurrentPlayback => {
@@ -529,6 +570,14 @@ impl AppClient {
                 .context("store user's followed artists into the cache folder")?;
                 state.data.write().user_data.followed_artists = artists;
             }
+            ClientRequest::GetUserTopArtists(time_range) => {
+                let artists = self.current_user_top_artists(time_range).await?;
+                state.data.write().caches.top_artists.insert(
+                    time_range.as_str().to_string(),
+                    artists,
+                    *TTL_CACHE_DURATION,
+                );
+            }
             ClientRequest::GetUserSavedAlbums => {
                 let albums = self.current_user_saved_albums().await?;
                 store_data_into_file_cache(
@@ -540,7 +589,29 @@ impl AppClient {
                 state.data.write().user_data.saved_albums = albums;
             }
             ClientRequest::GetUserSavedShows => {
-                let shows = self.current_user_saved_shows().await?;
+                let mut shows = self.current_user_saved_shows().await?;
+                let requests = shows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, show)| {
+                        let id = show.id.clone();
+                        async move { (i, self.show_unplayed_episodes(id).await) }
+                    })
+                    .collect::<Vec<_>>();
+                let unplayed_episodes = futures::stream::iter(requests)
+                    .buffer_unordered(MAX_CONCURRENT_SHOW_REQUESTS)
+                    .collect::<Vec<_>>()
+                    .await;
+                for (i, unplayed) in unplayed_episodes {
+                    let show = &mut shows[i];
+                    match unplayed {
+                        Ok(n) => show.unplayed_episodes = Some(n),
+                        Err(err) => tracing::warn!(
+                            "Failed to get the unplayed episodes of show {}: {err:#}",
+                            show.id.uri()
+                        ),
+                    }
+                }
                 store_data_into_file_cache(
                     FileCacheKey::SavedShows,
                     &config::get_config().cache_folder,
@@ -549,6 +620,24 @@ impl AppClient {
                 .context("store user's saved shows into the cache folder")?;
                 state.data.write().user_data.saved_shows = shows;
             }
+            ClientRequest::GetUserSavedAudiobooks => {
+                // audiobooks aren't available in every market,
+                // in which case the user is considered to have no saved audiobooks
+                let audiobooks = match self.current_user_saved_audiobooks().await {
+                    Ok(audiobooks) => audiobooks,
+                    Err(err) => {
+                        tracing::warn!("Failed to get the user's saved audiobooks: {err:#}");
+                        vec![]
+                    }
+                };
+                store_data_into_file_cache(
+                    FileCacheKey::SavedAudiobooks,
+                    &config::get_config().cache_folder,
+                    &audiobooks,
+                )
+                .context("store user's saved audiobooks into the cache folder")?;
+                state.data.write().user_data.saved_audiobooks = audiobooks;
+            }
             ClientRequest::GetContext(context) => {
                 let uri = context.uri();
                 // Liked tracks must always be refreshed to keep user_data.saved_tracks in sync.
@@ -563,10 +652,14 @@ impl AppClient {
                         ContextId::Album(album_id) => self.album_context(album_id).await?,
                         ContextId::Artist(artist_id) => self.artist_context(artist_id).await?,
                         ContextId::Tracks(tracks_id) => match tracks_id.uri.as_str() {
-                            USER_TOP_TRACKS_URI => Context::Tracks {
-                                tracks: self.current_user_top_tracks().await?,
-                                desc: "User's top tracks".to_string(),
-                            },
+                            u if u.starts_with(USER_TOP_TRACKS_URI) => {
+                                let time_range =
+                                    tracks_id.top_tracks_time_range().unwrap_or_default();
+                                Context::Tracks {
+                                    tracks: self.current_user_top_tracks(time_range).await?,
+                                    desc: format!("User's top tracks ({time_range})"),
+                                }
+                            }
                             USER_RECENTLY_PLAYED_TRACKS_URI => Context::Tracks {
                                 tracks: self.current_user_recently_played_tracks().await?,
                                 desc: "User's recently played tracks".to_string(),
@@ -575,7 +668,7 @@ impl AppClient {
                                 let tracks = self.current_user_saved_tracks().await?;
                                 let tracks_hm = tracks
                                     .iter()
-                                    .map(|t| (t.id.uri(), t.clone()))
+                                    .map(|t| (t.uri(), t.clone()))
                                     .collect::<HashMap<_, _>>();
                                 store_data_into_file_cache(
                                     FileCacheKey::SavedTracks,
@@ -596,6 +689,9 @@ impl AppClient {
                             uri => anyhow::bail!("unsupported Tracks context: {uri}"),
                         },
                         ContextId::Show(show_id) => self.show_context(show_id).await?,
+                        ContextId::Audiobook(audiobook_id) => {
+                            self.audiobook_context(&audiobook_id).await?
+                        }
                     };
 
                     state
@@ -621,6 +717,9 @@ impl AppClient {
 
             ClientRequest::AddPlayableToQueue(playable_id) => {
                 self.add_item_to_queue(playable_id, None).await?;
+                if !state.subscribers.is_empty() {
+                    self.refresh_queue(state).await?;
+                }
             }
             ClientRequest::AddPlayableToPlaylist(playlist_id, playable_id) => {
                 self.add_item_to_playlist(state, playlist_id, playable_id)
@@ -630,14 +729,20 @@ impl AppClient {
                 let album_context = self.album_context(album_id).await?;
 
                 if let Context::Album { album: _, tracks } = album_context {
-                    for track in tracks {
-                        self.add_item_to_queue(PlayableId::Track(track.id), None)
-                            .await?;
+                    for id in tracks.iter().filter_map(Track::playable_id) {
+                        self.add_item_to_queue(id, None).await?;
                     }
                 }
+                if !state.subscribers.is_empty() {
+                    self.refresh_queue(state).await?;
+                }
             }
-            ClientRequest::DeleteTrackFromPlaylist(playlist_id, track_id) => {
-                self.delete_track_from_playlist(state, playlist_id, track_id)
+            ClientRequest::DeleteTrackFromPlaylist(playlist_id, playable_id) => {
+                self.delete_track_from_playlist(Some(state), playlist_id, playable_id, None)
+                    .await?;
+            }
+            ClientRequest::DedupePlaylist(playlist_id) => {
+                self.dedupe_playlist(Some(state), playlist_id, DedupeKey::default(), false)
                     .await?;
             }
             ClientRequest::AddToLibrary(item) => {
@@ -647,8 +752,23 @@ impl AppClient {
                 self.delete_from_library(state, id).await?;
             }
             ClientRequest::GetCurrentUserQueue => {
-                let queue = self.current_user_queue().await?;
-                state.player.write().queue = Some(queue);
+                self.refresh_queue(state).await?;
+            }
+            ClientRequest::EditQueue(edit) => {
+                self.edit_queue(state, edit).await?;
+            }
+            ClientRequest::SaveQueueAsPlaylist {
+                playlist_name,
+                desc,
+            } => {
+                let (_, _, skipped) = self
+                    .save_queue_as_playlist(Some(state), &playlist_name, &desc)
+                    .await?;
+                if skipped > 0 {
+                    tracing::warn!(
+                        "Skipped {skipped} items of the queue without a Spotify ID, e.g. local files"
+                    );
+                }
             }
             ClientRequest::ReorderPlaylistItems {
                 playlist_id,
@@ -658,7 +778,7 @@ impl AppClient {
                 snapshot_id,
             } => {
                 self.reorder_playlist_items(
-                    state,
+                    Some(state),
                     playlist_id,
                     insert_index,
                     range_start,
@@ -667,6 +787,16 @@ impl AppClient {
                 )
                 .await?;
             }
+            ClientRequest::CommitPlaylistOrder(playlist_id) => {
+                self.commit_playlist_order(state, playlist_id).await?;
+            }
+            ClientRequest::ExportTracks(source) => {
+                let message = match self.export_tracks_to_file(state, source).await {
+                    Ok((count, path)) => format!("Exported {count} items to {}", path.display()),
+                    Err(err) => format!("Failed to export: {err:#}"),
+                };
+                state.ui.lock().popup = Some(PopupState::Message(message));
+            }
             ClientRequest::CreatePlaylist {
                 playlist_name,
                 public,
@@ -682,7 +812,7 @@ impl AppClient {
                     .map(|u| u.id.clone())
                     .unwrap();
                 self.create_new_playlist(
-                    state,
+                    Some(state),
                     user_id,
                     playlist_name.as_str(),
                     public,
@@ -722,6 +852,190 @@ impl AppClient {
         }
     }
 
+    /// Retrieve the latest user's queue
+    pub async fn refresh_queue(&self, state: &SharedState) -> Result<()> {
+        let mut queue = self.current_user_queue().await?;
+
+        // Spotify's queue only contains the current batch of the custom queue,
+        // so the upcoming items are retrieved from the custom queue instead
+        let custom_queue_ids = state.player.read().custom_queue.as_ref().map(|q| {
+            q.remaining_tracks()
+                .iter()
+                .take(CUSTOM_QUEUE_PAGE_LIMIT)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        if let Some(ids) = custom_queue_ids {
+            // reuse the items of Spotify's queue and of the previous refresh,
+            // so that only the items newly shown in the queue are retrieved
+            let previous_items = state
+                .player
+                .read()
+                .queue
+                .as_ref()
+                .map(|q| q.queue.clone())
+                .unwrap_or_default();
+            let known_items = std::mem::take(&mut queue.queue)
+                .into_iter()
+                .chain(previous_items)
+                .filter_map(|item| Some((crate::state::playable_item_uri(&item)?, item)))
+                .collect();
+            queue.queue = self.playable_items(&ids, known_items).await?;
+        }
+
+        state.update_player(|player| player.queue = Some(queue));
+        Ok(())
+    }
+
+    /// Get the full items of a list of playable IDs, preserving their order.
+    /// Only the items missing from `items`, a map from URIs to already known items,
+    /// are retrieved. Items that can't be retrieved are returned as `PlayableItem::Unknown`.
+    async fn playable_items(
+        &self,
+        ids: &[PlayableId<'static>],
+        mut items: HashMap<String, rspotify::model::PlayableItem>,
+    ) -> Result<Vec<rspotify::model::PlayableItem>> {
+        let track_ids = ids
+            .iter()
+            .filter(|id| !items.contains_key(&id.uri()))
+            .filter_map(|id| match id {
+                PlayableId::Track(id) => Some(id.clone()),
+                PlayableId::Episode(_) => None,
+            })
+            .collect::<Vec<_>>();
+        for chunk in track_ids.chunks(50) {
+            for track in self.tracks(chunk.to_vec(), None).await? {
+                if let Some(ref id) = track.id {
+                    items.insert(id.uri(), rspotify::model::PlayableItem::Track(track));
+                }
+            }
+        }
+
+        let episode_ids = ids
+            .iter()
+            .filter(|id| !items.contains_key(&id.uri()))
+            .filter_map(|id| match id {
+                PlayableId::Episode(id) => Some(id.clone()),
+                PlayableId::Track(_) => None,
+            })
+            .collect::<Vec<_>>();
+        for chunk in episode_ids.chunks(50) {
+            for episode in self.get_several_episodes(chunk.to_vec(), None).await? {
+                items.insert(
+                    episode.id.uri(),
+                    rspotify::model::PlayableItem::Episode(episode),
+                );
+            }
+        }
+
+        Ok(ids
+            .iter()
+            .map(|id| {
+                let uri = id.uri();
+                match items.get(&uri) {
+                    Some(item) => item.clone(),
+                    None => rspotify::model::PlayableItem::Unknown(uri.into()),
+                }
+            })
+            .collect())
+    }
+
+    /// Edit the playback queue.
+    ///
+    /// The app-managed custom queue is edited directly. Spotify's queue only supports
+    /// adding items and skipping to the next item, so only jumping to an item and
+    /// playing an item next can be emulated for it.
+    async fn edit_queue(&self, state: &SharedState, edit: QueueEdit) -> Result<()> {
+        if state.player.read().custom_queue.is_some() {
+            return self.edit_custom_queue(state, edit).await;
+        }
+
+        let (device_id, item) = {
+            let player = state.player.read();
+            let index = match edit {
+                QueueEdit::PlayNext(index) | QueueEdit::JumpTo(index) => index,
+                QueueEdit::Remove(_) | QueueEdit::Move { .. } | QueueEdit::Clear => {
+                    anyhow::bail!(
+                        "Spotify's queue only supports adding items, enable `custom_queue` to edit the queue"
+                    )
+                }
+            };
+            let item = player
+                .queue
+                .as_ref()
+                .and_then(|q| q.queue.get(index))
+                .and_then(rspotify::model::PlayableItem::id)
+                .map(PlayableId::into_static)
+                .with_context(|| format!("no item at position {index} in the queue"))?;
+            let device_id = player
+                .buffered_playback
+                .as_ref()
+                .and_then(|p| p.device_id.clone());
+            (device_id, item)
+        };
+
+        match edit {
+            QueueEdit::JumpTo(index) => {
+                // skip the items before the selected item
+                for _ in 0..=index {
+                    self.next_track(device_id.as_deref()).await?;
+                }
+                self.update_playback(state);
+            }
+            QueueEdit::PlayNext(_) => {
+                // items added to Spotify's queue are played before the context's upcoming items
+                self.add_item_to_queue(item, device_id.as_deref()).await?;
+            }
+            _ => unreachable!(),
+        }
+
+        self.refresh_queue(state).await
+    }
+
+    /// Edit the app-managed custom queue, keeping the queue displayed
+    /// in the queue page in sync with the edit
+    async fn edit_custom_queue(&self, state: &SharedState, edit: QueueEdit) -> Result<()> {
+        let batch = state.update_player(|player| -> Result<_> {
+            let queue = player.custom_queue.as_mut().context("no custom queue")?;
+            let mut batch = None;
+            let edited = match edit {
+                QueueEdit::Remove(index) => queue.remove_upcoming(index).is_some(),
+                QueueEdit::Move { from, to } => queue.move_upcoming(from, to),
+                QueueEdit::PlayNext(index) => queue.move_upcoming(index, 0),
+                QueueEdit::JumpTo(index) => {
+                    batch = queue.jump_to_upcoming(index);
+                    batch.is_some()
+                }
+                QueueEdit::Clear => {
+                    queue.clear_upcoming();
+                    true
+                }
+            };
+            if !edited {
+                anyhow::bail!("invalid queue edit {edit:?}");
+            }
+
+            if let Some(q) = player.queue.as_mut() {
+                apply_queue_edit(&mut q.queue, &edit);
+            }
+            Ok(batch)
+        })?;
+
+        // jumping to an item starts a new batch from that item
+        if let Some(batch) = batch {
+            let device_id = state
+                .player
+                .read()
+                .buffered_playback
+                .as_ref()
+                .and_then(|p| p.device_id.clone());
+            self.start_playback(Playback::URIs(batch, None), device_id.as_deref(), None)
+                .await?;
+            self.update_playback(state);
+        }
+        Ok(())
+    }
+
     /// Get user available devices
     pub async fn available_devices(&self) -> Result<Vec<rspotify::model::Device>> {
         Ok(self.device().await?)
@@ -856,7 +1170,7 @@ impl AppClient {
 
         Ok(tracks
             .into_iter()
-            .filter_map(|t| Track::try_from_full_track(t.track))
+            .filter_map(Track::try_from_saved_track)
             .collect())
     }
 
@@ -878,11 +1192,14 @@ impl AppClient {
         Ok(tracks)
     }
 
-    /// Get the top tracks of the current user
-    pub async fn current_user_top_tracks(&self) -> Result<Vec<Track>> {
+    /// Get the top tracks of the current user in a time range
+    pub async fn current_user_top_tracks(&self, time_range: TimeRange) -> Result<Vec<Track>> {
         let tracks = self
             .all_paging_items::<rspotify::model::FullTrack>(
-                &format!("{SPOTIFY_API_ENDPOINT}/me/top/tracks"),
+                &format!(
+                    "{SPOTIFY_API_ENDPOINT}/me/top/tracks?time_range={}",
+                    time_range.as_str()
+                ),
                 0, // we don't know the total number of top tracks beforehand
             )
             .await?;
@@ -893,6 +1210,21 @@ impl AppClient {
             .collect())
     }
 
+    /// Get the top artists of the current user in a time range
+    pub async fn current_user_top_artists(&self, time_range: TimeRange) -> Result<Vec<Artist>> {
+        let artists = self
+            .all_paging_items::<rspotify::model::FullArtist>(
+                &format!(
+                    "{SPOTIFY_API_ENDPOINT}/me/top/artists?time_range={}",
+                    time_range.as_str()
+                ),
+                0, // we don't know the total number of top artists beforehand
+            )
+            .await?;
+
+        Ok(artists.into_iter().map(std::convert::Into::into).collect())
+    }
+
     /// Get all playlists of the current user
     pub async fn current_user_playlists(&self) -> Result<Vec<Playlist>> {
         let playlists = self
@@ -954,7 +1286,39 @@ impl AppClient {
             )
             .await?;
 
-        Ok(shows.into_iter().map(|s| s.show.into()).collect())
+        Ok(shows.into_iter().map(std::convert::Into::into).collect())
+    }
+
+    /// Get all saved audiobooks of the current user
+    pub async fn current_user_saved_audiobooks(&self) -> Result<Vec<Audiobook>> {
+        let audiobooks = self
+            .all_paging_items::<Option<AudiobookData>>(
+                &format!("{SPOTIFY_API_ENDPOINT}/me/audiobooks"),
+                0, // we don't know the total number of saved audiobooks beforehand
+            )
+            .await?;
+
+        Ok(audiobooks
+            .into_iter()
+            .flatten()
+            .map(std::convert::Into::into)
+            .collect())
+    }
+
+    /// Get the number of unplayed episodes among the latest episodes of a show
+    pub async fn show_unplayed_episodes(&self, show_id: ShowId<'_>) -> Result<usize> {
+        let episodes = self
+            .http_get::<rspotify::model::Page<rspotify::model::SimplifiedEpisode>>(
+                &format!("{SPOTIFY_API_ENDPOINT}/shows/{}/episodes", show_id.id()),
+                &Query::from([("market", "from_token"), ("limit", "50")]),
+            )
+            .await?
+            .items;
+
+        Ok(episodes
+            .iter()
+            .filter(|e| e.resume_point.as_ref().is_some_and(|r| !r.fully_played))
+            .count())
     }
 
     /// Get all albums of an artist
@@ -975,32 +1339,104 @@ impl AppClient {
         Ok(AppClient::process_artist_albums(albums))
     }
 
+    /// Start playing the current batch of the custom queue on the integrated device
+    #[cfg(feature = "streaming")]
+    async fn resume_custom_queue(
+        &self,
+        state: &SharedState,
+        progress: chrono::Duration,
+    ) -> Result<()> {
+        let (batch, current) = {
+            let player = state.player.read();
+            let queue = player.custom_queue.as_ref().context("no custom queue")?;
+            (queue.current_batch().to_vec(), queue.current_track().uri())
+        };
+        let device_id = self.spotify.session().await.device_id().to_string();
+
+        // the integrated device may take time to show up in Spotify server,
+        // so a retry logic is implemented
+        let mut result = Ok(());
+        for _ in 0..5 {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            result = self
+                .start_uris_playback(
+                    batch.clone(),
+                    Some(&device_id),
+                    Some(rspotify::model::Offset::Uri(current.clone())),
+                    Some(progress),
+                )
+                .await;
+            if result.is_ok() {
+                break;
+            }
+        }
+        result.context("start the custom queue's playback")?;
+        if config::get_config().app_config.pause_on_startup {
+            self.spotify
+                .pause_playback(Some(&device_id))
+                .await
+                .context("pause the custom queue's playback")?;
+        }
+
+        tracing::info!("Resumed the custom queue from the previous session");
+        self.update_playback(state);
+        Ok(())
+    }
+
     /// Start a playback
-    async fn start_playback(&self, playback: Playback, device_id: Option<&str>) -> Result<()> {
+    async fn start_playback(
+        &self,
+        playback: Playback,
+        device_id: Option<&str>,
+        position: Option<chrono::Duration>,
+    ) -> Result<()> {
         match playback {
             Playback::Context(id, offset) => match id {
                 ContextId::Album(id) => {
-                    self.start_context_playback(PlayContextId::from(id), device_id, offset, None)
-                        .await?;
+                    self.start_context_playback(
+                        PlayContextId::from(id),
+                        device_id,
+                        offset,
+                        position,
+                    )
+                    .await?;
                 }
                 ContextId::Artist(id) => {
-                    self.start_context_playback(PlayContextId::from(id), device_id, offset, None)
-                        .await?;
+                    self.start_context_playback(
+                        PlayContextId::from(id),
+                        device_id,
+                        offset,
+                        position,
+                    )
+                    .await?;
                 }
                 ContextId::Playlist(id) => {
-                    self.start_context_playback(PlayContextId::from(id), device_id, offset, None)
-                        .await?;
+                    self.start_context_playback(
+                        PlayContextId::from(id),
+                        device_id,
+                        offset,
+                        position,
+                    )
+                    .await?;
                 }
                 ContextId::Show(id) => {
-                    self.start_context_playback(PlayContextId::from(id), device_id, offset, None)
-                        .await?;
+                    self.start_context_playback(
+                        PlayContextId::from(id),
+                        device_id,
+                        offset,
+                        position,
+                    )
+                    .await?;
                 }
                 ContextId::Tracks(_) => {
                     anyhow::bail!("`StartPlayback` request for `tracks` context is not supported")
                 }
+                ContextId::Audiobook(_) => anyhow::bail!(
+                    "`StartPlayback` request for `audiobook` context is not supported"
+                ),
             },
             Playback::URIs(ids, offset) => {
-                self.start_uris_playback(ids, device_id, offset, None)
+                self.start_uris_playback(ids, device_id, offset, position)
                     .await?;
             }
         }
@@ -1080,6 +1516,21 @@ impl AppClient {
         Ok(tracks)
     }
 
+    /// Search for tracks matching a given query
+    pub async fn search_tracks(&self, query: &str) -> Result<Vec<Track>> {
+        match self
+            .search_specific_type(query, rspotify::model::SearchType::Track)
+            .await?
+        {
+            rspotify::model::SearchResult::Tracks(p) => Ok(p
+                .items
+                .into_iter()
+                .filter_map(Track::try_from_full_track)
+                .collect()),
+            _ => anyhow::bail!("expect a track search result"),
+        }
+    }
+
     /// Search for items (tracks, artists, albums, playlists) matching a given query
     pub async fn search(&self, query: &str) -> Result<SearchResults> {
         let (
@@ -1089,24 +1540,26 @@ impl AppClient {
             playlist_result,
             show_result,
             episode_result,
+            audiobooks,
         ) = tokio::try_join!(
-            self.search_specific_type(query, rspotify::model::SearchType::Track),
+            self.search_tracks(query),
             self.search_specific_type(query, rspotify::model::SearchType::Artist),
             self.search_specific_type(query, rspotify::model::SearchType::Album),
             self.search_specific_type(query, rspotify::model::SearchType::Playlist),
             self.search_specific_type(query, rspotify::model::SearchType::Show),
-            self.search_specific_type(query, rspotify::model::SearchType::Episode)
+            self.search_specific_type(query, rspotify::model::SearchType::Episode),
+            async {
+                // audiobooks are only available in some markets, so failing to
+                // search them shouldn't fail the whole search
+                Ok(self.search_audiobooks(query).await.unwrap_or_else(|err| {
+                    tracing::warn!("Failed to search audiobooks: {err:#}");
+                    Vec::new()
+                }))
+            }
         )?;
 
         let (tracks, artists, albums, playlists, shows, episodes) = (
-            match track_result {
-                rspotify::model::SearchResult::Tracks(p) => p
-                    .items
-                    .into_iter()
-                    .filter_map(Track::try_from_full_track)
-                    .collect(),
-                _ => anyhow::bail!("expect a track search result"),
-            },
+            track_result,
             match artist_result {
                 rspotify::model::SearchResult::Artists(p) => {
                     p.items.into_iter().map(std::convert::Into::into).collect()
@@ -1148,9 +1601,37 @@ impl AppClient {
             playlists,
             shows,
             episodes,
+            audiobooks,
         })
     }
 
+    /// Search for audiobooks matching a given query
+    pub async fn search_audiobooks(&self, query: &str) -> Result<Vec<Audiobook>> {
+        #[derive(Debug, Deserialize)]
+        struct AudiobookSearchResponse {
+            audiobooks: rspotify::model::Page<Option<AudiobookData>>,
+        }
+
+        let response = self
+            .http_get::<AudiobookSearchResponse>(
+                &format!("{SPOTIFY_API_ENDPOINT}/search"),
+                &Query::from([
+                    ("q", query),
+                    ("type", "audiobook"),
+                    ("market", "from_token"),
+                ]),
+            )
+            .await?;
+
+        Ok(response
+            .audiobooks
+            .items
+            .into_iter()
+            .flatten()
+            .map(std::convert::Into::into)
+            .collect())
+    }
+
     /// Search for items of a specific type matching a given query
     pub async fn search_specific_type(
         &self,
@@ -1187,72 +1668,293 @@ impl AppClient {
         Ok(())
     }
 
-    /// Remove a track from a playlist
-    pub async fn delete_track_from_playlist(
+    /// Append items to a playlist, in batches of the maximum size allowed by the API
+    pub async fn add_items_to_playlist(
         &self,
-        state: &SharedState,
+        state: Option<&SharedState>,
         playlist_id: PlaylistId<'_>,
-        track_id: TrackId<'_>,
+        playable_ids: &[PlayableId<'_>],
     ) -> Result<()> {
-        // remove all the occurrences of the track to ensure no duplication in the playlist
-        self.playlist_remove_all_occurrences_of_items(
-            playlist_id.as_ref(),
-            [PlayableId::Track(track_id.as_ref())],
-            None,
-        )
-        .await?;
+        for chunk in playable_ids.chunks(100) {
+            self.playlist_add_items(
+                playlist_id.as_ref(),
+                chunk.iter().map(PlayableId::as_ref),
+                None,
+            )
+            .await?;
+        }
+
+        if let Some(state) = state {
+            state.data.write().caches.context.remove(&playlist_id.uri());
+        }
+        Ok(())
+    }
+
+    /// Remove a track from a playlist.
+    ///
+    /// If `positions` is specified, only the track's occurrences at these positions are removed.
+    /// Otherwise, all the track's occurrences are removed. Returns the playlist's new snapshot id.
+    pub async fn delete_track_from_playlist(
+        &self,
+        state: Option<&SharedState>,
+        playlist_id: PlaylistId<'_>,
+        playable_id: PlayableId<'_>,
+        positions: Option<PlaylistPositions<'_>>,
+    ) -> Result<String> {
+        let uri = playable_id.uri();
+        let result = match positions {
+            // remove all the occurrences of the track to ensure no duplication in the playlist
+            None => {
+                self.playlist_remove_all_occurrences_of_items(
+                    playlist_id.as_ref(),
+                    [playable_id],
+                    None,
+                )
+                .await?
+            }
+            Some(positions) => {
+                self.playlist_remove_specific_occurrences_of_items(
+                    playlist_id.as_ref(),
+                    [rspotify::model::ItemPositions {
+                        id: playable_id,
+                        positions: positions.positions,
+                    }],
+                    Some(positions.snapshot_id),
+                )
+                .await?
+            }
+        };
 
         // After making a delete request, update the playlist in-memory data stored inside the app caches.
-        if let Some(Context::Playlist { tracks, .. }) = state
-            .data
-            .write()
-            .caches
-            .context
-            .get_mut(&playlist_id.uri())
-        {
-            tracks.retain(|t| t.id != track_id);
+        if let Some(state) = state {
+            let mut data = state.data.write();
+            let playlist_uri = playlist_id.uri();
+            if positions.is_some() {
+                // cached tracks don't keep the playlist's positions, so the cached data is invalidated instead
+                data.caches.context.remove(&playlist_uri);
+            } else if let Some(Context::Playlist { tracks, .. }) =
+                data.caches.context.get_mut(&playlist_uri)
+            {
+                tracks.retain(|t| t.uri() != uri);
+            }
         }
 
-        Ok(())
+        Ok(result.snapshot_id)
     }
 
-    /// Reorder items in a playlist
-    async fn reorder_playlist_items(
+    /// Delete the duplicate tracks of a playlist, keeping the earliest added occurrence of each track.
+    ///
+    /// Returns the playlist and the deleted duplicates with their original positions.
+    /// If `dry_run` is set, the duplicates are only found and not deleted.
+    pub async fn dedupe_playlist(
+        &self,
+        state: Option<&SharedState>,
+        playlist_id: PlaylistId<'static>,
+        by: DedupeKey,
+        dry_run: bool,
+    ) -> Result<(Playlist, Vec<(usize, Track)>)> {
+        let (playlist, items) = self.playlist_items(playlist_id.as_ref()).await?;
+        let duplicates = playlist_edit::find_duplicates(&items, by)
+            .into_iter()
+            .filter_map(|pos| Some((pos, items[pos].clone()?)))
+            .collect::<Vec<_>>();
+
+        if dry_run || duplicates.is_empty() {
+            return Ok((playlist, duplicates));
+        }
+
+        // group the duplicates' positions by item, each group is deleted in a separate request
+        let mut groups: Vec<(PlayableId<'static>, Vec<usize>)> = vec![];
+        for (pos, track) in &duplicates {
+            let Some(playable_id) = track.playable_id() else {
+                continue;
+            };
+            match groups.iter_mut().find(|(id, _)| *id == playable_id) {
+                Some((_, positions)) => positions.push(*pos),
+                None => groups.push((playable_id, vec![*pos])),
+            }
+        }
+
+        let mut snapshot_id = playlist.snapshot_id.clone();
+        let mut removed = vec![];
+        for (playable_id, positions) in groups {
+            // positions in the latest snapshot, after deleting the previous groups
+            let shifted = playlist_edit::shift_positions(&positions, &removed)
+                .into_iter()
+                .map(u32::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            snapshot_id = self
+                .delete_track_from_playlist(
+                    state,
+                    playlist_id.as_ref(),
+                    playable_id,
+                    Some(PlaylistPositions {
+                        positions: &shifted,
+                        snapshot_id: &snapshot_id,
+                    }),
+                )
+                .await?;
+            removed.extend(positions);
+        }
+
+        tracing::info!(
+            "Deleted {} duplicates from playlist {}",
+            duplicates.len(),
+            playlist_id.uri()
+        );
+        if let Some(state) = state {
+            self.refresh_playlist_context(state, playlist_id.as_ref())
+                .await?;
+        }
+
+        Ok((playlist, duplicates))
+    }
+
+    /// Sort a playlist's items, returning the number of moves made
+    pub async fn sort_playlist(
+        &self,
+        state: Option<&SharedState>,
+        playlist_id: PlaylistId<'static>,
+        by: TrackOrder,
+        reverse: bool,
+    ) -> Result<(Playlist, usize)> {
+        let (playlist, items) = self.playlist_items(playlist_id.as_ref()).await?;
+        let order = playlist_edit::sorted_order(&items, by, reverse);
+        let moves = self
+            .reorder_playlist(playlist_id.as_ref(), playlist.snapshot_id.clone(), &order)
+            .await?;
+
+        if let Some(state) = state {
+            self.refresh_playlist_context(state, playlist_id.as_ref())
+                .await?;
+        }
+
+        Ok((playlist, moves))
+    }
+
+    /// Reorder a playlist's items to match the order of its tracks displayed in the application
+    async fn commit_playlist_order(
         &self,
         state: &SharedState,
+        playlist_id: PlaylistId<'static>,
+    ) -> Result<()> {
+        let tracks = match state.data.read().caches.context.get(&playlist_id.uri()) {
+            Some(Context::Playlist { tracks, .. }) => tracks.clone(),
+            _ => return Ok(()),
+        };
+
+        let (playlist, items) = self.playlist_items(playlist_id.as_ref()).await?;
+        let Some(order) = playlist_edit::matching_order(&items, &tracks) else {
+            anyhow::bail!(
+                "playlist {} was modified since it was loaded, refresh it before committing its order",
+                playlist_id.uri()
+            );
+        };
+        let moves = self
+            .reorder_playlist(playlist_id.as_ref(), playlist.snapshot_id, &order)
+            .await?;
+        tracing::info!(
+            "Reordered playlist {} with {moves} moves",
+            playlist_id.uri()
+        );
+
+        self.refresh_playlist_context(state, playlist_id.as_ref())
+            .await
+    }
+
+    /// Reorder a playlist's items with a minimal sequence of moves.
+    /// See [`playlist_edit::sorted_order`] for the format of `order`.
+    ///
+    /// The reordering is aborted if the playlist was modified since `snapshot_id` was retrieved.
+    /// Each move is then made against the snapshot returned by the previous move.
+    /// Returns the number of moves made.
+    async fn reorder_playlist(
+        &self,
+        playlist_id: PlaylistId<'_>,
+        mut snapshot_id: String,
+        order: &[usize],
+    ) -> Result<usize> {
+        let moves = playlist_edit::reorder_moves(order);
+        if moves.is_empty() {
+            return Ok(0);
+        }
+        if self.playlist_snapshot_id(playlist_id.as_ref()).await? != snapshot_id {
+            anyhow::bail!(
+                "playlist {} was modified concurrently, aborted reordering",
+                playlist_id.uri()
+            );
+        }
+        for m in &moves {
+            snapshot_id = self
+                .reorder_playlist_items(
+                    None,
+                    playlist_id.as_ref(),
+                    m.insert_index,
+                    m.range_start,
+                    None,
+                    Some(&snapshot_id),
+                )
+                .await?;
+        }
+        Ok(moves.len())
+    }
+
+    /// Reorder items in a playlist, returning the playlist's new snapshot id
+    async fn reorder_playlist_items(
+        &self,
+        state: Option<&SharedState>,
         playlist_id: PlaylistId<'_>,
         insert_index: usize,
         range_start: usize,
         range_length: Option<usize>,
         snapshot_id: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         let insert_before = if insert_index > range_start {
             insert_index + 1
         } else {
             insert_index
         };
 
-        self.playlist_reorder_items(
-            playlist_id.clone(),
-            Some(range_start as i32),
-            Some(insert_before as i32),
-            range_length.map(|range_length| range_length as u32),
-            snapshot_id,
-        )
-        .await?;
+        let result = self
+            .playlist_reorder_items(
+                playlist_id.clone(),
+                Some(range_start as i32),
+                Some(insert_before as i32),
+                range_length.map(|range_length| range_length as u32),
+                snapshot_id,
+            )
+            .await?;
 
         // After making a reorder request, update the playlist in-memory data stored inside the app caches.
-        if let Some(Context::Playlist { tracks, .. }) = state
+        if let Some(state) = state {
+            if let Some(Context::Playlist { tracks, .. }) = state
+                .data
+                .write()
+                .caches
+                .context
+                .get_mut(&playlist_id.uri())
+            {
+                let track = tracks.remove(range_start);
+                tracks.insert(insert_index, track);
+            }
+        }
+
+        Ok(result.snapshot_id)
+    }
+
+    /// Update the cached context of a playlist after modifying it
+    async fn refresh_playlist_context(
+        &self,
+        state: &SharedState,
+        playlist_id: PlaylistId<'_>,
+    ) -> Result<()> {
+        let context = self.playlist_context(playlist_id.as_ref()).await?;
+        state
             .data
             .write()
             .caches
             .context
-            .get_mut(&playlist_id.uri())
-        {
-            let track = tracks.remove(range_start);
-            tracks.insert(insert_index, track);
-        }
-
+            .insert(playlist_id.uri(), context, *TTL_CACHE_DURATION);
         Ok(())
     }
 
@@ -1261,19 +1963,25 @@ impl AppClient {
         // Before adding new item, checks if that item already exists in the library to avoid adding a duplicated item.
         match item {
             Item::Track(track) => {
+                let Some(id) = track.id.clone() else {
+                    anyhow::bail!("'{}' is not a Spotify track", track.name);
+                };
                 let contains = self
-                    .current_user_saved_tracks_contains([track.id.as_ref()])
+                    .current_user_saved_tracks_contains([id.as_ref()])
                     .await?;
                 if !contains[0] {
-                    self.current_user_saved_tracks_add([track.id.as_ref()])
-                        .await?;
+                    self.current_user_saved_tracks_add([id.as_ref()]).await?;
+                    state.subscribers.publish(&[StateEvent::LikeToggled {
+                        uri: id.uri(),
+                        liked: true,
+                    }]);
                     // update the in-memory `user_data`
                     state
                         .data
                         .write()
                         .user_data
                         .saved_tracks
-                        .insert(track.id.uri(), track);
+                        .insert(id.uri(), track);
                 }
             }
             Item::Album(album) => {
@@ -1344,6 +2052,9 @@ impl AppClient {
                 let uri = id.uri();
                 self.current_user_saved_tracks_delete([id]).await?;
                 state.data.write().user_data.saved_tracks.remove(&uri);
+                state
+                    .subscribers
+                    .publish(&[StateEvent::LikeToggled { uri, liked: false }]);
             }
             ItemId::Album(id) => {
                 state
@@ -1399,11 +2110,13 @@ impl AppClient {
         .context("convert FullTrack into Track")
     }
 
-    /// Get a playlist context data
-    pub async fn playlist_context(&self, playlist_id: PlaylistId<'_>) -> Result<Context> {
-        let playlist_uri = playlist_id.uri();
-        tracing::info!("Get playlist context: {}", playlist_uri);
-
+    /// Get a playlist's data and its items in order, with `None` for the items that
+    /// can't be converted into a `Track` (e.g. episodes, local files or unplayable tracks),
+    /// so that an item's index is its position in the playlist
+    pub async fn playlist_items(
+        &self,
+        playlist_id: PlaylistId<'_>,
+    ) -> Result<(Playlist, Vec<Option<Track>>)> {
         let playlist = self
             .playlist(
                 playlist_id.clone(),
@@ -1412,7 +2125,7 @@ impl AppClient {
             )
             .await?;
 
-        let tracks = self
+        let items = self
             .all_paging_items(
                 &format!(
                     "{SPOTIFY_API_ENDPOINT}/playlists/{}/tracks",
@@ -1422,12 +2135,38 @@ impl AppClient {
             )
             .await?
             .into_iter()
-            .filter_map(Track::try_from_playlist_item)
+            .map(Track::try_from_playlist_item)
             .collect::<Vec<_>>();
 
+        Ok((playlist.into(), items))
+    }
+
+    /// Get the current snapshot id of a playlist
+    async fn playlist_snapshot_id(&self, playlist_id: PlaylistId<'_>) -> Result<String> {
+        #[derive(Deserialize, Debug)]
+        struct PlaylistSnapshot {
+            snapshot_id: String,
+        }
+
+        Ok(self
+            .http_get::<PlaylistSnapshot>(
+                &format!("{SPOTIFY_API_ENDPOINT}/playlists/{}", playlist_id.id()),
+                &Query::from([("fields", "snapshot_id")]),
+            )
+            .await?
+            .snapshot_id)
+    }
+
+    /// Get a playlist context data
+    pub async fn playlist_context(&self, playlist_id: PlaylistId<'_>) -> Result<Context> {
+        let playlist_uri = playlist_id.uri();
+        tracing::info!("Get playlist context: {}", playlist_uri);
+
+        let (playlist, items) = self.playlist_items(playlist_id).await?;
+
         Ok(Context::Playlist {
-            playlist: playlist.into(),
-            tracks,
+            playlist,
+            tracks: items.into_iter().flatten().collect(),
         })
     }
 
@@ -1535,6 +2274,37 @@ impl AppClient {
         Ok(Context::Show { show, episodes })
     }
 
+    /// Get an audiobook context data
+    pub async fn audiobook_context(&self, audiobook_id: &AudiobookId) -> Result<Context> {
+        tracing::info!("Get audiobook context: {}", audiobook_id.uri());
+
+        let audiobook = self
+            .http_get::<FullAudiobookData>(
+                &format!("{SPOTIFY_API_ENDPOINT}/audiobooks/{}", audiobook_id.id()),
+                &Query::from([("market", "from_token")]),
+            )
+            .await?;
+
+        // get the audiobook's chapters
+        let chapters = self
+            .all_paging_items::<ChapterData>(
+                &format!(
+                    "{SPOTIFY_API_ENDPOINT}/audiobooks/{}/chapters",
+                    audiobook_id.id()
+                ),
+                audiobook.chapters.total as usize,
+            )
+            .await?
+            .into_iter()
+            .map(std::convert::Into::into)
+            .collect::<Vec<_>>();
+
+        Ok(Context::Audiobook {
+            audiobook: audiobook.audiobook.into(),
+            chapters,
+        })
+    }
+
     /// Make a GET HTTP request to the Spotify server
     async fn http_get<T>(&self, url: &str, payload: &Query<'_>) -> Result<T>
     where
@@ -1662,11 +2432,9 @@ impl AppClient {
         state: &SharedState,
         reset_buffered_playback: bool,
     ) -> Result<()> {
-        let new_playback = {
-            // update the playback state
-            let playback = self.current_playback2().await?;
-            let mut player = state.player.write();
-
+        // update the playback state
+        let playback = self.current_playback2().await?;
+        let new_playback = state.update_player(|player| {
             let prev_item = player.currently_playing();
 
             let prev_name = match prev_item {
@@ -1711,7 +2479,7 @@ impl AppClient {
             }
 
             new_playback
-        };
+        });
 
         if !new_playback {
             return Ok(());
@@ -1846,16 +2614,111 @@ impl AppClient {
         Ok(())
     }
 
-    /// Create a new playlist
-    async fn create_new_playlist(
+    /// Get the name and the tracks of an export source
+    pub async fn export_source_tracks(
+        &self,
+        source: ExportSource,
+        state: Option<&SharedState>,
+    ) -> Result<(String, Vec<Track>)> {
+        let (name, mut tracks) = match source {
+            ExportSource::Playlist(id) => match self.playlist_context(id).await? {
+                Context::Playlist { playlist, tracks } => (playlist.name, tracks),
+                _ => unreachable!(),
+            },
+            ExportSource::Album(id) => match self.album_context(id).await? {
+                Context::Album { album, tracks } => (album.name, tracks),
+                _ => unreachable!(),
+            },
+            ExportSource::LikedTracks => (
+                "Liked Tracks".to_string(),
+                self.current_user_saved_tracks().await?,
+            ),
+            ExportSource::Queue => {
+                let (ids, skipped) = self.current_queue_items(state).await?;
+                if skipped > 0 {
+                    tracing::warn!(
+                        "Skipped {skipped} items of the queue without a Spotify ID, e.g. local files"
+                    );
+                }
+                let tracks = self
+                    .playable_items(&ids, HashMap::new())
+                    .await?
+                    .into_iter()
+                    .filter_map(Track::try_from_playable_item)
+                    .collect();
+                ("Queue".to_string(), tracks)
+            }
+        };
+
+        // simplified tracks (e.g. album tracks) don't have ISRCs,
+        // so retrieve them from the corresponding full tracks
+        let missing_ids = tracks
+            .iter()
+            .filter(|t| t.isrc.is_none())
+            .filter_map(|t| t.id.clone())
+            .collect::<Vec<_>>();
+        if !missing_ids.is_empty() {
+            let isrcs = self
+                .full_tracks(missing_ids)
+                .await?
+                .into_iter()
+                .filter_map(|t| Some((t.id?, t.isrc?)))
+                .collect::<HashMap<_, _>>();
+            for t in tracks.iter_mut().filter(|t| t.isrc.is_none()) {
+                t.isrc = t.id.as_ref().and_then(|id| isrcs.get(id)).cloned();
+            }
+        }
+
+        Ok((name, tracks))
+    }
+
+    /// Export the tracks of a source into the `exports` folder inside the cache folder,
+    /// returning the number of exported items and the path of the export file
+    async fn export_tracks_to_file(
         &self,
         state: &SharedState,
+        source: ExportSource,
+    ) -> Result<(usize, std::path::PathBuf)> {
+        let format = ExportFormat::Csv;
+        let (name, tracks) = self.export_source_tracks(source, Some(state)).await?;
+        let data = export::export_tracks(&name, &tracks, format)?;
+
+        let dir = config::get_config().cache_folder.join("exports");
+        std::fs::create_dir_all(&dir)?;
+        let path = export::export_file_path(&dir, &name, format);
+        std::fs::write(&path, data)?;
+
+        tracing::info!("Exported {} tracks to {}", tracks.len(), path.display());
+        Ok((tracks.len(), path))
+    }
+
+    /// Get the full data of tracks, retrieved in batches of the maximum size allowed by the API
+    pub async fn full_tracks(&self, ids: Vec<TrackId<'static>>) -> Result<Vec<Track>> {
+        let mut tracks = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(50) {
+            tracks.extend(
+                self.tracks(
+                    chunk.iter().cloned(),
+                    Some(rspotify::model::Market::FromToken),
+                )
+                .await?
+                .into_iter()
+                .filter_map(Track::try_from_full_track),
+            );
+        }
+        Ok(tracks)
+    }
+
+    /// Create a new playlist
+    pub async fn create_new_playlist(
+        &self,
+        state: Option<&SharedState>,
         user_id: UserId<'static>,
         playlist_name: &str,
         public: bool,
         collab: bool,
         desc: &str,
-    ) -> Result<()> {
+    ) -> Result<Playlist> {
         let playlist: Playlist = self
             .user_playlist_create(
                 user_id,
@@ -1871,13 +2734,67 @@ impl AppClient {
             playlist.name,
             playlist.id
         );
-        state
-            .data
-            .write()
-            .user_data
-            .playlists
-            .insert(0, PlaylistFolderItem::Playlist(playlist));
-        Ok(())
+        if let Some(state) = state {
+            state
+                .data
+                .write()
+                .user_data
+                .playlists
+                .insert(0, PlaylistFolderItem::Playlist(playlist.clone()));
+        }
+        Ok(playlist)
+    }
+
+    /// Get the items of the current queue, starting with the currently playing item,
+    /// and the number of skipped items without an ID, e.g. local files.
+    ///
+    /// The items are taken from the custom queue when it's active, or from Spotify's queue otherwise.
+    pub async fn current_queue_items(
+        &self,
+        state: Option<&SharedState>,
+    ) -> Result<(Vec<PlayableId<'static>>, usize)> {
+        let queue = match state {
+            Some(state) => {
+                let player = state.player.read();
+                if let Some(ref queue) = player.custom_queue {
+                    let ids = std::iter::once(queue.current_track())
+                        .chain(queue.remaining_tracks())
+                        .cloned()
+                        .collect();
+                    return Ok((ids, 0));
+                }
+                player.queue.clone()
+            }
+            None => None,
+        };
+        let queue = match queue {
+            Some(queue) => queue,
+            None => self.current_user_queue().await?,
+        };
+
+        Ok(queue_item_ids(&queue))
+    }
+
+    /// Create a new playlist from the items of the current queue.
+    /// Returns the new playlist, its number of items and the number of skipped queue items.
+    pub async fn save_queue_as_playlist(
+        &self,
+        state: Option<&SharedState>,
+        playlist_name: &str,
+        desc: &str,
+    ) -> Result<(Playlist, usize, usize)> {
+        let (ids, skipped) = self.current_queue_items(state).await?;
+        if ids.is_empty() {
+            anyhow::bail!("the queue has no items that can be added to a playlist");
+        }
+
+        let user_id = self.current_user().await?.id;
+        let playlist = self
+            .create_new_playlist(state, user_id, playlist_name, false, false, desc)
+            .await?;
+        self.add_items_to_playlist(state, playlist.id.as_ref(), &ids)
+            .await?;
+        Ok((playlist, ids.len(), skipped))
     }
 
     #[cfg(feature = "notify")]
@@ -2031,26 +2948,143 @@ impl AppClient {
     }
 }
 
+#[derive(Debug, Deserialize)]
+/// A named entity (author, narrator) of an audiobook returned by Spotify APIs
+struct NameData {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+/// An audiobook returned by Spotify APIs
+struct AudiobookData {
+    id: String,
+    name: String,
+    authors: Vec<NameData>,
+    narrators: Vec<NameData>,
+    publisher: String,
+    total_chapters: usize,
+}
+
+#[derive(Debug, Deserialize)]
+/// An audiobook with its first page of chapters returned by Spotify APIs
+struct FullAudiobookData {
+    #[serde(flatten)]
+    audiobook: AudiobookData,
+    chapters: rspotify::model::Page<ChapterData>,
+}
+
+#[derive(Debug, Deserialize)]
+/// An audiobook chapter returned by Spotify APIs
+struct ChapterData {
+    id: EpisodeId<'static>,
+    name: String,
+    chapter_number: usize,
+    duration_ms: u64,
+    resume_point: Option<rspotify::model::ResumePoint>,
+}
+
+impl From<AudiobookData> for Audiobook {
+    fn from(audiobook: AudiobookData) -> Self {
+        Self {
+            id: AudiobookId::from_id(audiobook.id),
+            name: audiobook.name,
+            authors: audiobook.authors.into_iter().map(|a| a.name).collect(),
+            narrators: audiobook.narrators.into_iter().map(|n| n.name).collect(),
+            publisher: audiobook.publisher,
+            total_chapters: audiobook.total_chapters,
+        }
+    }
+}
+
+impl From<ChapterData> for Chapter {
+    fn from(chapter: ChapterData) -> Self {
+        let (resume_position, fully_played) = match chapter.resume_point {
+            Some(p) => (
+                // a zero resume position means the chapter hasn't been started
+                (!p.resume_position.is_zero())
+                    .then(|| p.resume_position.to_std().unwrap_or_default()),
+                p.fully_played,
+            ),
+            None => (None, false),
+        };
+        Self {
+            id: chapter.id,
+            name: chapter.name,
+            number: chapter.chapter_number,
+            duration: std::time::Duration::from_millis(chapter.duration_ms),
+            resume_position,
+            fully_played,
+        }
+    }
+}
+
 fn move_seed_track_to_front(tracks: &mut Vec<Track>, seed_track: Track) {
     tracks.retain(|track| track.id != seed_track.id);
     tracks.insert(0, seed_track);
 }
 
+/// Get the IDs of a Spotify queue's items, starting with the currently playing item,
+/// and the number of skipped items without an ID, e.g. local files
+fn queue_item_ids(queue: &rspotify::model::CurrentUserQueue) -> (Vec<PlayableId<'static>>, usize) {
+    let mut skipped = 0;
+    let ids = queue
+        .currently_playing
+        .iter()
+        .chain(queue.queue.iter())
+        .filter_map(|item| {
+            let id = item.id().map(PlayableId::into_static);
+            if id.is_none() {
+                skipped += 1;
+            }
+            id
+        })
+        .collect();
+    (ids, skipped)
+}
+
+/// Apply a queue edit to the displayed upcoming items, so that the queue page
+/// is updated without waiting for the next queue refresh
+fn apply_queue_edit<T>(items: &mut Vec<T>, edit: &QueueEdit) {
+    match *edit {
+        QueueEdit::Remove(index) if index < items.len() => {
+            items.remove(index);
+        }
+        QueueEdit::Move { from, to } if from < items.len() && to < items.len() => {
+            let item = items.remove(from);
+            items.insert(to, item);
+        }
+        QueueEdit::PlayNext(index) if index < items.len() => {
+            let item = items.remove(index);
+            items.insert(0, item);
+        }
+        QueueEdit::JumpTo(index) => {
+            items.drain(..(index + 1).min(items.len()));
+        }
+        QueueEdit::Clear => items.clear(),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::move_seed_track_to_front;
-    use crate::state::Track;
+    use super::{
+        apply_queue_edit, move_seed_track_to_front, queue_item_ids, ChapterData, QueueEdit,
+    };
+    use crate::state::{Chapter, Track, TrackKind};
     use rspotify::model::TrackId;
+    use rspotify::prelude::Id;
 
     fn sample_track(id: &'static str, name: &str) -> Track {
         Track {
-            id: TrackId::from_id(id).unwrap().into_static(),
+            id: Some(TrackId::from_id(id).unwrap().into_static()),
             name: name.to_string(),
             artists: vec![],
             album: None,
             duration: std::time::Duration::default(),
             explicit: false,
             added_at: 0,
+            isrc: None,
+            kind: TrackKind::Track,
         }
     }
 
@@ -2080,4 +3114,100 @@ mod tests {
         assert_eq!(tracks[0].id, seed.id);
         assert_eq!(tracks[1].id, second.id);
     }
+
+    #[test]
+    fn chapter_resume_points() {
+        let chapter = |resume_point: &str| -> Chapter {
+            serde_json::from_str::<ChapterData>(&format!(
+                r#"{{"id": "0D5wENdkdwbqlrHoaJ9g29", "name": "Chapter 1", "chapter_number": 1,
+                    "duration_ms": 600000, "resume_point": {resume_point}}}"#
+            ))
+            .unwrap()
+            .into()
+        };
+
+        let c = chapter("null");
+        assert_eq!(c.duration, std::time::Duration::from_mins(10));
+        assert!(c.resume_position.is_none() && !c.fully_played);
+
+        let c = chapter(r#"{"fully_played": false, "resume_position_ms": 0}"#);
+        assert!(c.resume_position.is_none() && !c.fully_played);
+
+        let c = chapter(r#"{"fully_played": false, "resume_position_ms": 90000}"#);
+        assert_eq!(c.resume_position, Some(std::time::Duration::from_secs(90)));
+
+        let c = chapter(r#"{"fully_played": true, "resume_position_ms": 600000}"#);
+        assert!(c.fully_played);
+    }
+
+    #[test]
+    fn apply_queue_edits() {
+        let mut items = vec![0, 1, 2, 3, 4, 5];
+
+        apply_queue_edit(&mut items, &QueueEdit::Remove(1));
+        assert_eq!(items, [0, 2, 3, 4, 5]);
+        apply_queue_edit(&mut items, &QueueEdit::Move { from: 0, to: 2 });
+        assert_eq!(items, [2, 3, 0, 4, 5]);
+        apply_queue_edit(&mut items, &QueueEdit::PlayNext(3));
+        assert_eq!(items, [4, 2, 3, 0, 5]);
+        apply_queue_edit(&mut items, &QueueEdit::JumpTo(1));
+        assert_eq!(items, [3, 0, 5]);
+        apply_queue_edit(&mut items, &QueueEdit::Clear);
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn apply_out_of_range_queue_edits() {
+        let mut items = vec![0, 1, 2];
+
+        apply_queue_edit(&mut items, &QueueEdit::Remove(3));
+        apply_queue_edit(&mut items, &QueueEdit::Move { from: 1, to: 3 });
+        apply_queue_edit(&mut items, &QueueEdit::PlayNext(3));
+        assert_eq!(items, [0, 1, 2]);
+        // the items displayed in the queue page may be fewer than the queue's items
+        apply_queue_edit(&mut items, &QueueEdit::JumpTo(5));
+        assert!(items.is_empty());
+    }
+
+    fn queue_track(id: Option<&str>) -> rspotify::model::PlayableItem {
+        let track = serde_json::json!({
+            "album": {
+                "album_type": null, "artists": [], "external_urls": {}, "href": null,
+                "id": null, "images": [], "name": "album",
+            },
+            "artists": [], "disc_number": 1, "duration_ms": 60000, "explicit": false,
+            "external_ids": {}, "external_urls": {}, "href": null, "id": id,
+            "is_local": id.is_none(), "name": "track", "popularity": 0,
+            "preview_url": null, "track_number": 1, "type": "track",
+        });
+        rspotify::model::PlayableItem::Track(serde_json::from_value(track).unwrap())
+    }
+
+    #[test]
+    fn queue_item_ids_skip_items_without_ids() {
+        let queue = rspotify::model::CurrentUserQueue {
+            currently_playing: Some(queue_track(Some("3n3Ppam7vgaVa1iaRUc9Lp"))),
+            queue: vec![
+                queue_track(None),
+                queue_track(Some("4uLU6hMCjMI75M1A2tKUQC")),
+                rspotify::model::PlayableItem::Unknown(serde_json::Value::Null),
+            ],
+        };
+
+        let (ids, skipped) = queue_item_ids(&queue);
+        assert_eq!(
+            ids.iter().map(|id| id.id().to_owned()).collect::<Vec<_>>(),
+            ["3n3Ppam7vgaVa1iaRUc9Lp", "4uLU6hMCjMI75M1A2tKUQC"]
+        );
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn queue_item_ids_of_empty_queue() {
+        let queue = rspotify::model::CurrentUserQueue {
+            currently_playing: None,
+            queue: vec![],
+        };
+        assert_eq!(queue_item_ids(&queue), (vec![], 0));
+    }
 }
@@ -1,5 +1,10 @@
-use crate::state::{
-    AlbumId, Category, ContextId, Item, ItemId, PlayableId, Playback, PlaylistId, TrackId,
+use crate::{
+    export::ExportSource,
+    scrobble::Listen,
+    state::{
+        AlbumId, Category, ContextId, Item, ItemId, PlayableId, Playback, PlaylistId, StatsWindow,
+        TimeRange, TrackId,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -17,6 +22,18 @@ pub enum PlayerRequest {
     ToggleMute,
     TransferPlayback(String, bool),
     StartPlayback(Playback, Option<bool>),
+    /// Start a playback from a position in its first played item
+    StartPlaybackAt(Playback, chrono::Duration),
+}
+
+#[derive(Clone, Debug)]
+/// An edit of the playback queue, addressing the upcoming items by their index
+pub enum QueueEdit {
+    Remove(usize),
+    Move { from: usize, to: usize },
+    PlayNext(usize),
+    JumpTo(usize),
+    Clear,
 }
 
 #[derive(Clone, Debug)]
@@ -26,17 +43,23 @@ pub enum ClientRequest {
     GetDevices,
     GetBrowseCategories,
     GetBrowseCategoryPlaylists(Category),
+    /// Compute the listening statistics of a window from the listening history
+    GetListeningStats(StatsWindow),
     GetUserPlaylists,
     GetUserSavedAlbums,
     GetUserSavedShows,
+    GetUserSavedAudiobooks,
     GetUserFollowedArtists,
+    GetUserTopArtists(TimeRange),
     GetContext(ContextId),
     GetCurrentPlayback,
     Search(String),
     AddPlayableToQueue(PlayableId<'static>),
     AddAlbumToQueue(AlbumId<'static>),
     AddPlayableToPlaylist(PlaylistId<'static>, PlayableId<'static>),
-    DeleteTrackFromPlaylist(PlaylistId<'static>, TrackId<'static>),
+    DeleteTrackFromPlaylist(PlaylistId<'static>, PlayableId<'static>),
+    /// Delete the duplicate tracks of a playlist
+    DedupePlaylist(PlaylistId<'static>),
     ReorderPlaylistItems {
         playlist_id: PlaylistId<'static>,
         insert_index: usize,
@@ -44,19 +67,36 @@ pub enum ClientRequest {
         range_length: Option<usize>,
         snapshot_id: Option<String>,
     },
+    /// Reorder a playlist's items to match the order of its displayed tracks
+    CommitPlaylistOrder(PlaylistId<'static>),
     AddToLibrary(Item),
     DeleteFromLibrary(ItemId),
     Player(PlayerRequest),
     GetCurrentUserQueue,
+    EditQueue(QueueEdit),
     GetLyrics {
         track_id: TrackId<'static>,
     },
     #[cfg(feature = "streaming")]
     RestartIntegratedClient,
+    /// Resume the custom queue restored from the previous session at the given progress
+    #[cfg(feature = "streaming")]
+    ResumeCustomQueue(chrono::Duration),
     CreatePlaylist {
         playlist_name: String,
         public: bool,
         collab: bool,
         desc: String,
     },
+    /// Create a new playlist from the items of the current queue
+    SaveQueueAsPlaylist {
+        playlist_name: String,
+        desc: String,
+    },
+    /// Export the tracks of a source into a file inside the cache folder
+    ExportTracks(ExportSource),
+    /// Report a track as playing to the scrobbling services
+    NowPlaying(Listen),
+    /// Scrobble a listen to the scrobbling services
+    Scrobble(Listen),
 }
@@ -1,6 +1,6 @@
 use crate::state::{
     Album, Artist, DataReadGuard, Episode, Playlist, PlaylistFolder, PlaylistFolderItem, Show,
-    Track,
+    Track, TrackKind,
 };
 use serde::Deserialize;
 
@@ -26,6 +26,7 @@ pub enum Command {
     SeekBackward {
         duration: Option<u16>,
     },
+    SleepTimer,
 
     Quit,
     OpenCommandHelp,
@@ -66,6 +67,8 @@ pub enum Command {
 
     CurrentlyPlayingContextPage,
     TopTrackPage,
+    TopArtistPage,
+    CycleTimeRange,
     RecentlyPlayedTrackPage,
     LikedTrackPage,
     LyricsPage,
@@ -88,8 +91,16 @@ pub enum Command {
     MovePlaylistItemUp,
     MovePlaylistItemDown,
 
+    RemoveSelectedItemFromQueue,
+    PlaySelectedItemNext,
+    ClearQueue,
+    SaveQueueAsPlaylist,
+
     CreatePlaylist,
     OpenLogs,
+
+    StatsPage,
+    CycleStatsWindow,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
@@ -112,6 +123,9 @@ pub enum Action {
     CopyLink,
     Follow,
     Unfollow,
+    Export,
+    Dedupe,
+    CommitOrder,
 }
 
 #[derive(Debug)]
@@ -201,6 +215,12 @@ impl ActionContext {
 
 /// constructs a list of actions on a track
 pub fn construct_track_actions(track: &Track, data: &DataReadGuard) -> Vec<Action> {
+    match track.kind {
+        TrackKind::Track => {}
+        TrackKind::Episode { .. } => return vec![Action::AddToQueue],
+        TrackKind::Local { .. } => return vec![],
+    }
+
     let mut actions = vec![
         Action::GoToArtist,
         Action::GoToAlbum,
@@ -229,6 +249,7 @@ pub fn construct_album_actions(album: &Album, data: &DataReadGuard) -> Vec<Actio
         Action::ShowActionsOnArtist,
         Action::CopyLink,
         Action::AddToQueue,
+        Action::Export,
     ];
     if data.user_data.saved_albums.iter().any(|a| a.id == album.id) {
         actions.push(Action::DeleteFromLibrary);
@@ -257,7 +278,7 @@ pub fn construct_artist_actions(artist: &Artist, data: &DataReadGuard) -> Vec<Ac
 
 /// constructs a list of actions on an playlist
 pub fn construct_playlist_actions(playlist: &Playlist, data: &DataReadGuard) -> Vec<Action> {
-    let mut actions = vec![Action::GoToRadio, Action::CopyLink];
+    let mut actions = vec![Action::GoToRadio, Action::CopyLink, Action::Export];
 
     if data
         .user_data
@@ -269,6 +290,16 @@ pub fn construct_playlist_actions(playlist: &Playlist, data: &DataReadGuard) ->
     } else {
         actions.push(Action::AddToLibrary);
     }
+    if playlist.collaborative
+        || data
+            .user_data
+            .user
+            .as_ref()
+            .is_some_and(|u| u.id == playlist.owner.1)
+    {
+        actions.push(Action::Dedupe);
+        actions.push(Action::CommitOrder);
+    }
     actions
 }
 
@@ -309,6 +340,7 @@ impl Command {
             Self::Shuffle => "toggle the shuffle mode",
             Self::Mute => "toggle playback volume between 0% and previous level",
             Self::SeekStart => "seek to track start",
+            Self::SleepTimer => "cycle the sleep timer (15, 30, 45, 60 minutes, end of track, end of context, off)",
             Self::SeekForward { duration } => { return format!("seek forward by {}s", duration.unwrap_or(5)) },
             Self::SeekBackward { duration } => { return format!("seek backward by {}s", duration.unwrap_or(5)) },
             Self::Quit => "quit the application",
@@ -349,6 +381,8 @@ impl Command {
             Self::BrowseUserSavedAlbums => "open a popup for browsing user's saved albums",
             Self::CurrentlyPlayingContextPage => "go to the currently playing context page",
             Self::TopTrackPage => "go to the user top track page",
+            Self::TopArtistPage => "go to the user top artist page",
+            Self::CycleTimeRange => "cycle the time range of the top track or top artist page (last 4 weeks, last 6 months, last year)",
             Self::RecentlyPlayedTrackPage => "go to the user recently played track page",
             Self::LikedTrackPage => "go to the user liked track page",
             Self::LyricsPage => "go to the lyrics page of the current track",
@@ -367,13 +401,19 @@ impl Command {
             Self::ReverseTrackOrder => "reverse the order of the track table (if any)",
             Self::SortLibraryAlphabetically => "sort the library alphabetically",
             Self::SortLibraryByRecent => {
-                "sort the library (playlists and albums) by recently added items"
+                "sort the library (playlists, albums and shows) by recently added items"
             }
-            Self::MovePlaylistItemUp => "move playlist item up one position",
-            Self::MovePlaylistItemDown => "move playlist item down one position",
+            Self::MovePlaylistItemUp => "move playlist or queue item up one position",
+            Self::MovePlaylistItemDown => "move playlist or queue item down one position",
+            Self::RemoveSelectedItemFromQueue => "remove the selected item from the queue",
+            Self::PlaySelectedItemNext => "move the selected queue item to play next",
+            Self::ClearQueue => "clear the queue after the current track",
+            Self::SaveQueueAsPlaylist => "save the current queue as a new playlist",
             Self::CreatePlaylist => "create a new playlist",
             Self::VolumeChange { offset: _ } => unreachable!(),
             Self::OpenLogs => "go to the application logs page",
+            Self::StatsPage => "go to the listening statistics page",
+            Self::CycleStatsWindow => "cycle the time window of the listening statistics page (today, week, month, year, all time)",
         }
         .to_string()
     }
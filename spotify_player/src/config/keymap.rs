@@ -75,6 +75,10 @@ impl Default for KeymapConfig {
                     key_sequence: "^".into(),
                     command: Command::SeekStart,
                 },
+                Keymap {
+                    key_sequence: "C-t".into(),
+                    command: Command::SleepTimer,
+                },
                 Keymap {
                     key_sequence: ">".into(),
                     command: Command::SeekForward { duration: None },
@@ -168,6 +172,14 @@ impl Default for KeymapConfig {
                     key_sequence: "g t".into(),
                     command: Command::TopTrackPage,
                 },
+                Keymap {
+                    key_sequence: "g T".into(),
+                    command: Command::TopArtistPage,
+                },
+                Keymap {
+                    key_sequence: "W".into(),
+                    command: Command::CycleTimeRange,
+                },
                 Keymap {
                     key_sequence: "g r".into(),
                     command: Command::RecentlyPlayedTrackPage,
@@ -320,6 +332,22 @@ impl Default for KeymapConfig {
                     key_sequence: "C-j".into(),
                     command: Command::MovePlaylistItemDown,
                 },
+                Keymap {
+                    key_sequence: "x".into(),
+                    command: Command::RemoveSelectedItemFromQueue,
+                },
+                Keymap {
+                    key_sequence: "P".into(),
+                    command: Command::PlaySelectedItemNext,
+                },
+                Keymap {
+                    key_sequence: "X".into(),
+                    command: Command::ClearQueue,
+                },
+                Keymap {
+                    key_sequence: "S".into(),
+                    command: Command::SaveQueueAsPlaylist,
+                },
                 Keymap {
                     key_sequence: "N".into(),
                     command: Command::CreatePlaylist,
@@ -332,6 +360,14 @@ impl Default for KeymapConfig {
                     key_sequence: "g o".into(),
                     command: Command::OpenLogs,
                 },
+                Keymap {
+                    key_sequence: "g S".into(),
+                    command: Command::StatsPage,
+                },
+                Keymap {
+                    key_sequence: "w".into(),
+                    command: Command::CycleStatsWindow,
+                },
             ],
         }
     }
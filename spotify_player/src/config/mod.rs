@@ -55,6 +55,15 @@ pub struct AppConfig {
     pub client_id_command: Option<Command>,
 
     pub client_port: u16,
+    /// Address of an additional TCP socket handling CLI commands from other machines,
+    /// whose requests must be authorized by one of `client_tokens`.
+    /// The socket isn't encrypted, tokens are sent in plaintext.
+    pub client_listen_address: Option<String>,
+    pub client_tokens: Vec<ClientToken>,
+    /// Address and token of a remote application's socket to send CLI commands to,
+    /// instead of the local application
+    pub remote_client_address: Option<String>,
+    pub remote_client_token: Option<String>,
 
     pub login_redirect_uri: String,
 
@@ -119,6 +128,9 @@ pub struct AppConfig {
 
     pub enable_cover_image_cache: bool,
 
+    /// Record the played items into the listening history stored in the cache folder
+    pub enable_listening_history: bool,
+
     pub default_device: String,
 
     pub device: DeviceConfig,
@@ -128,6 +140,9 @@ pub struct AppConfig {
 
     pub seek_duration_secs: u16,
 
+    /// Duration of the volume fade-out before a sleep timer pauses the playback, `0` disables the fade-out
+    pub sleep_timer_fade_secs: u64,
+
     pub sort_artist_albums_by_type: bool,
 
     pub volume_scroll_step: u8,
@@ -138,6 +153,12 @@ pub struct AppConfig {
     /// management.
     pub custom_queue: bool,
 
+    /// Save the custom queue, including its play order, shuffle mode, repeat state
+    /// and playback progress, into the cache folder and resume it on startup,
+    /// paused if `pause_on_startup` is enabled. Requires streaming and `custom_queue`.
+    #[cfg(feature = "streaming")]
+    pub resume_custom_queue: bool,
+
     pub enable_relative_line_number: bool,
 
     /// Start the application with playback paused instead of resuming the
@@ -146,6 +167,35 @@ pub struct AppConfig {
     /// playing track; enabling this pauses that auto-started playback once.
     #[cfg(feature = "streaming")]
     pub pause_on_startup: bool,
+
+    /// Scheduled sync of playlist imports, only run when the application is running as a daemon
+    #[cfg(feature = "daemon")]
+    pub playlist_sync: PlaylistSyncConfig,
+
+    /// Alarms starting a playback at scheduled times, only run when the application is running as a daemon
+    #[cfg(feature = "daemon")]
+    pub alarm: AlarmConfig,
+
+    /// Scrobbling of the played tracks, enabled for the services whose credentials are set
+    pub scrobble: ScrobbleConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// A token authorizing the requests of remote CLI commands
+pub struct ClientToken {
+    pub token: String,
+    pub scope: TokenScope,
+}
+
+/// Requests allowed by a client token, each scope including the previous ones
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TokenScope {
+    /// get data and subscribe to the application's events
+    Read,
+    /// control the playback and the queue
+    Control,
+    /// modify the library: likes, playlists, backup restores, etc
+    Library,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -225,6 +275,59 @@ pub struct NotifyFormat {
     pub body: String,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize, ConfigParse, Clone)]
+#[cfg(feature = "daemon")]
+/// Scheduled playlist sync configurations
+pub struct PlaylistSyncConfig {
+    /// Interval between two syncs, `0` disables the scheduled sync
+    pub interval_in_mins: u64,
+    /// IDs of the playlists whose imports are synced, all imports are synced if empty
+    pub playlists: Vec<String>,
+    /// Delete the previously imported tracks that are no longer in the source playlists
+    pub delete: bool,
+    #[cfg(feature = "notify")]
+    /// Send a notification summarizing the result of each sync
+    pub notify: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ConfigParse, Clone)]
+#[cfg(feature = "daemon")]
+/// Alarm configurations
+pub struct AlarmConfig {
+    /// Alarms defined in the config, in addition to the ones added with the `alarm add` CLI command
+    pub alarms: Vec<crate::alarm::Alarm>,
+    /// What to do with the alarms missed while the application was unable to run them, e.g. during a suspend
+    pub missed_policy: MissedAlarmPolicy,
+    /// With the `Run` policy, alarms missed by more than this duration are skipped anyway, `0` for no limit
+    pub missed_grace_in_mins: u64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "daemon")]
+pub enum MissedAlarmPolicy {
+    #[default]
+    Skip,
+    /// Run the latest missed occurrence of each alarm once the application is able to
+    Run,
+}
+#[cfg(feature = "daemon")]
+config_parser_impl!(MissedAlarmPolicy);
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+/// Scrobbling configurations
+pub struct ScrobbleConfig {
+    /// User token of `ListenBrainz`
+    pub listenbrainz_token: Option<String>,
+    /// Base URL of the `ListenBrainz` API
+    pub listenbrainz_api_url: String,
+    pub lastfm_api_key: Option<String>,
+    pub lastfm_api_secret: Option<String>,
+    /// Session key of the Last.fm user authorizing the API account
+    pub lastfm_session_key: Option<String>,
+    /// URL of the Last.fm API
+    pub lastfm_api_url: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
 // Application layout configurations
 pub struct LayoutConfig {
@@ -305,6 +408,10 @@ impl Default for AppConfig {
             client_id_command: None,
 
             client_port: 8080,
+            client_listen_address: None,
+            client_tokens: Vec::new(),
+            remote_client_address: None,
+            remote_client_token: None,
 
             login_redirect_uri: "http://127.0.0.1:8989/login".to_string(),
 
@@ -383,6 +490,8 @@ impl Default for AppConfig {
 
             enable_cover_image_cache: true,
 
+            enable_listening_history: true,
+
             default_device: "spotify-player".to_string(),
 
             device: DeviceConfig::default(),
@@ -392,6 +501,8 @@ impl Default for AppConfig {
 
             seek_duration_secs: 5,
 
+            sleep_timer_fade_secs: 0,
+
             sort_artist_albums_by_type: false,
 
             volume_scroll_step: 5,
@@ -399,10 +510,21 @@ impl Default for AppConfig {
 
             custom_queue: true,
 
+            #[cfg(feature = "streaming")]
+            resume_custom_queue: false,
+
             enable_relative_line_number: false,
 
             #[cfg(feature = "streaming")]
             pause_on_startup: false,
+
+            #[cfg(feature = "daemon")]
+            playlist_sync: PlaylistSyncConfig::default(),
+
+            #[cfg(feature = "daemon")]
+            alarm: AlarmConfig::default(),
+
+            scrobble: ScrobbleConfig::default(),
         }
     }
 }
@@ -421,6 +543,19 @@ impl Default for DeviceConfig {
     }
 }
 
+impl Default for ScrobbleConfig {
+    fn default() -> Self {
+        Self {
+            listenbrainz_token: None,
+            listenbrainz_api_url: "https://api.listenbrainz.org".to_string(),
+            lastfm_api_key: None,
+            lastfm_api_secret: None,
+            lastfm_session_key: None,
+            lastfm_api_url: "https://ws.audioscrobbler.com/2.0/".to_string(),
+        }
+    }
+}
+
 impl Default for LayoutConfig {
     fn default() -> Self {
         Self {
@@ -503,6 +638,14 @@ impl AppConfig {
             None => Ok(self.client_id.clone()),
         }
     }
+
+    /// Get the scope of a remote client's token, `None` if the token is invalid
+    pub fn client_token_scope(&self, token: &str) -> Option<TokenScope> {
+        self.client_tokens
+            .iter()
+            .find(|t| constant_time_eq(t.token.as_bytes(), token.as_bytes()))
+            .map(|t| t.scope)
+    }
 }
 
 /// gets the application's configuration folder path
@@ -521,6 +664,11 @@ pub fn get_cache_folder_path() -> Result<PathBuf> {
     }
 }
 
+/// Compare two byte strings in a time independent of their content
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 pub fn get_config() -> &'static Configs {
     CONFIGS.get().expect("configs is already initialized")
 }
@@ -562,3 +710,43 @@ pub fn apply_config_override(config: &mut AppConfig, key: &str, value: &str) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"secret", b""));
+    }
+
+    #[test]
+    fn token_scope() {
+        let config = AppConfig {
+            client_tokens: vec![
+                ClientToken {
+                    token: "read".to_string(),
+                    scope: TokenScope::Read,
+                },
+                ClientToken {
+                    token: "library".to_string(),
+                    scope: TokenScope::Library,
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(config.client_token_scope("read"), Some(TokenScope::Read));
+        assert_eq!(
+            config.client_token_scope("library"),
+            Some(TokenScope::Library)
+        );
+        assert_eq!(config.client_token_scope("control"), None);
+        assert!(
+            TokenScope::Read < TokenScope::Control && TokenScope::Control < TokenScope::Library
+        );
+    }
+}
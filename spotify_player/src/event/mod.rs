@@ -1,19 +1,21 @@
 use crate::{
-    client::{ClientRequest, PlayerRequest},
+    client::{ClientRequest, PlayerRequest, QueueEdit},
     command::{
         self, construct_artist_actions, Action, ActionContext, ActionTarget, Command,
         CommandOrAction,
     },
     config,
+    export::ExportSource,
     key::{Key, KeySequence},
     state::{
-        ActionListItem, Album, AlbumId, Artist, ArtistFocusState, ArtistId, ArtistPopupAction,
-        BrowsePageUIState, ConfirmableAction, Context, ContextId, ContextPageType,
-        ContextPageUIState, DataReadGuard, Focusable, Id, Item, ItemId, LibraryFocusState,
-        LibraryPageUIState, PageState, PageType, PlayableId, Playback, PlaylistCreateCurrentField,
-        PlaylistFolderItem, PlaylistId, PlaylistPopupAction, PopupState, SearchFocusState,
-        SearchPageUIState, SharedState, ShowId, Track, TrackId, TrackOrder, TracksId, UIStateGuard,
-        USER_LIKED_TRACKS_ID, USER_RECENTLY_PLAYED_TRACKS_ID, USER_TOP_TRACKS_ID,
+        playable_item_uri, ActionListItem, Album, AlbumId, Artist, ArtistFocusState, ArtistId,
+        ArtistPopupAction, BrowsePageUIState, ConfirmableAction, Context, ContextId,
+        ContextPageType, ContextPageUIState, DataReadGuard, Focusable, Id, Item, ItemId,
+        LibraryFocusState, LibraryPageUIState, PageState, PageType, Playback,
+        PlaylistCreateCurrentField, PlaylistFolderItem, PlaylistId, PlaylistPopupAction,
+        PopupState, SearchFocusState, SearchPageUIState, SharedState, ShowId, SleepMode,
+        StatsFocusState, StatsPageUIState, StatsWindow, TimeRange, Track, TrackId, TrackKind,
+        TrackOrder, TracksId, UIStateGuard, USER_LIKED_TRACKS_ID, USER_RECENTLY_PLAYED_TRACKS_ID,
     },
     ui::{single_line_input::LineInput, Orientation},
     utils::parse_uri,
@@ -24,7 +26,7 @@ use anyhow::{Context as _, Result};
 use crossterm::event::KeyCode;
 
 use clipboard::{execute_copy_command, get_clipboard_content};
-use ratatui::widgets::ListState;
+use ratatui::widgets::{ListState, TableState};
 
 mod clipboard;
 mod page;
@@ -200,6 +202,13 @@ pub fn handle_action_in_context(
     ui: &mut UIStateGuard,
 ) -> Result<bool> {
     match context {
+        // episodes and local files inside a playlist only support a few actions
+        ActionContext::Track(track)
+            if track.kind != TrackKind::Track
+                && !matches!(action, Action::AddToQueue | Action::DeleteFromPlaylist) =>
+        {
+            Ok(false)
+        }
         ActionContext::Track(track) => match action {
             Action::GoToAlbum => {
                 if let Some(album) = track.album {
@@ -220,22 +229,31 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::AddToQueue => {
-                client_pub.send(ClientRequest::AddPlayableToQueue(track.id.into()))?;
+                let Some(playable_id) = track.playable_id() else {
+                    return Ok(false);
+                };
+                client_pub.send(ClientRequest::AddPlayableToQueue(playable_id))?;
                 ui.popup = None;
                 Ok(true)
             }
             Action::CopyLink => {
-                let track_url = format!("https://open.spotify.com/track/{}", track.id.id());
+                let Some(id) = track.id else {
+                    return Ok(false);
+                };
+                let track_url = format!("https://open.spotify.com/track/{}", id.id());
                 execute_copy_command(track_url)?;
                 ui.popup = None;
                 Ok(true)
             }
             Action::AddToPlaylist => {
+                let Some(track_id) = track.id else {
+                    return Ok(false);
+                };
                 client_pub.send(ClientRequest::GetUserPlaylists)?;
                 ui.popup = Some(PopupState::UserPlaylistList(
                     PlaylistPopupAction::AddTrack {
                         folder_id: 0,
-                        track_id: track.id,
+                        track_id,
                         search_query: String::new(),
                     },
                     ListState::default(),
@@ -244,7 +262,10 @@ pub fn handle_action_in_context(
             }
             Action::ToggleLiked => {
                 if data.user_data.is_liked_track(&track) {
-                    client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Track(track.id)))?;
+                    let Some(id) = track.id else {
+                        return Ok(false);
+                    };
+                    client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Track(id)))?;
                 } else {
                     client_pub.send(ClientRequest::AddToLibrary(Item::Track(track)))?;
                 }
@@ -257,12 +278,15 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::DeleteFromLiked => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Track(track.id)))?;
+                let Some(id) = track.id else {
+                    return Ok(false);
+                };
+                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Track(id)))?;
                 ui.popup = None;
                 Ok(true)
             }
             Action::GoToRadio => {
-                handle_go_to_radio(&track.id.uri(), &track.name, ui, client_pub)?;
+                handle_go_to_radio(&track.uri(), &track.name, ui, client_pub)?;
                 Ok(true)
             }
             Action::ShowActionsOnArtist => {
@@ -289,11 +313,15 @@ pub fn handle_action_in_context(
                     ..
                 } = ui.current_page()
                 {
+                    // local files can't be deleted through Spotify APIs
+                    let Some(playable_id) = track.playable_id() else {
+                        return Ok(false);
+                    };
                     ui.popup = Some(PopupState::ConfirmAction {
                         message: format!("Delete {} from this playlist?", track.name),
                         action: ConfirmableAction::DeleteTrackFromPlaylist {
                             playlist_id: playlist_id.clone_static(),
-                            track_id: track.id,
+                            playable_id,
                         },
                     });
                 }
@@ -337,6 +365,11 @@ pub fn handle_action_in_context(
                 ui.popup = None;
                 Ok(true)
             }
+            Action::Export => {
+                client_pub.send(ClientRequest::ExportTracks(ExportSource::Album(album.id)))?;
+                ui.popup = None;
+                Ok(true)
+            }
             _ => Ok(false),
         },
         ActionContext::Artist(artist) => match action {
@@ -379,6 +412,27 @@ pub fn handle_action_in_context(
                 ui.popup = None;
                 Ok(true)
             }
+            Action::Export => {
+                client_pub.send(ClientRequest::ExportTracks(ExportSource::Playlist(
+                    playlist.id,
+                )))?;
+                ui.popup = None;
+                Ok(true)
+            }
+            Action::Dedupe => {
+                ui.popup = Some(PopupState::ConfirmAction {
+                    message: format!("Delete duplicate tracks from {}?", playlist.name),
+                    action: ConfirmableAction::DedupePlaylist(playlist.id),
+                });
+                Ok(true)
+            }
+            Action::CommitOrder => {
+                ui.popup = Some(PopupState::ConfirmAction {
+                    message: format!("Save the displayed track order of {}?", playlist.name),
+                    action: ConfirmableAction::CommitPlaylistOrder(playlist.id),
+                });
+                Ok(true)
+            }
             Action::DeleteFromLibrary => {
                 ui.popup = Some(PopupState::ConfirmAction {
                     message: format!("Delete {} from your library?", playlist.name),
@@ -603,6 +657,18 @@ fn handle_global_command(
         Command::Mute => {
             client_pub.send(ClientRequest::Player(PlayerRequest::ToggleMute))?;
         }
+        Command::SleepTimer => {
+            let fade = std::time::Duration::from_secs(
+                config::get_config().app_config.sleep_timer_fade_secs,
+            );
+            let volume = state.update_player(|player| {
+                let mode = SleepMode::next_preset(player.sleep_timer.as_ref().map(|t| t.mode));
+                player.set_sleep_timer(mode, fade)
+            });
+            if let Some(volume) = volume {
+                client_pub.send(ClientRequest::Player(PlayerRequest::Volume(volume)))?;
+            }
+        }
         Command::SeekStart => {
             client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
                 chrono::TimeDelta::try_seconds(0).unwrap(),
@@ -635,6 +701,14 @@ fn handle_global_command(
         Command::OpenLogs => {
             ui.new_page(PageState::Logs { scroll_offset: 0 });
         }
+        Command::StatsPage => {
+            let window = StatsWindow::default();
+            ui.new_page(PageState::Stats {
+                window,
+                state: StatsPageUIState::new(),
+            });
+            client_pub.send(ClientRequest::GetListeningStats(window))?;
+        }
         Command::RefreshPlayback => {
             client_pub.send(ClientRequest::GetCurrentPlayback)?;
         }
@@ -690,16 +764,21 @@ fn handle_global_command(
             ui.popup = Some(PopupState::UserSavedAlbumList(ListState::default()));
         }
         Command::TopTrackPage => {
+            let tracks_id = TracksId::top_tracks(TimeRange::default());
             ui.new_page(PageState::Context {
                 id: None,
-                context_page_type: ContextPageType::Browsing(ContextId::Tracks(
-                    USER_TOP_TRACKS_ID.to_owned(),
-                )),
+                context_page_type: ContextPageType::Browsing(ContextId::Tracks(tracks_id.clone())),
                 state: None,
             });
-            client_pub.send(ClientRequest::GetContext(ContextId::Tracks(
-                USER_TOP_TRACKS_ID.to_owned(),
-            )))?;
+            client_pub.send(ClientRequest::GetContext(ContextId::Tracks(tracks_id)))?;
+        }
+        Command::TopArtistPage => {
+            let time_range = TimeRange::default();
+            ui.new_page(PageState::TopArtists {
+                time_range,
+                artist_list: ListState::default(),
+            });
+            client_pub.send(ClientRequest::GetUserTopArtists(time_range))?;
         }
         Command::RecentlyPlayedTrackPage => {
             ui.new_page(PageState::Context {
@@ -851,7 +930,9 @@ fn handle_global_command(
             }
         }
         Command::Queue => {
-            ui.new_page(PageState::Queue { scroll_offset: 0 });
+            ui.new_page(PageState::Queue {
+                queue_table: TableState::default(),
+            });
             client_pub.send(ClientRequest::GetCurrentUserQueue)?;
         }
         Command::CreatePlaylist => {
@@ -859,17 +940,25 @@ fn handle_global_command(
                 name: LineInput::default(),
                 desc: LineInput::default(),
                 current_field: PlaylistCreateCurrentField::Name,
+                from_queue: false,
+            });
+        }
+        Command::SaveQueueAsPlaylist => {
+            ui.popup = Some(PopupState::PlaylistCreate {
+                name: LineInput::default(),
+                desc: LineInput::default(),
+                current_field: PlaylistCreateCurrentField::Name,
+                from_queue: true,
             });
         }
         Command::JumpToCurrentTrackInContext => {
-            let track_id = match state.player.read().currently_playing() {
-                Some(rspotify::model::PlayableItem::Track(track)) => {
-                    PlayableId::Track(track.id.clone().expect("all non-local tracks have ids"))
-                }
-                Some(rspotify::model::PlayableItem::Episode(episode)) => {
-                    PlayableId::Episode(episode.id.clone())
-                }
-                Some(rspotify::model::PlayableItem::Unknown(_)) | None => return Ok(false),
+            let Some(uri) = state
+                .player
+                .read()
+                .currently_playing()
+                .and_then(playable_item_uri)
+            else {
+                return Ok(false);
             };
 
             if let PageState::Context {
@@ -881,7 +970,7 @@ fn handle_global_command(
                     .data
                     .read()
                     .context_tracks(context_id)
-                    .and_then(|tracks| tracks.iter().position(|t| t.id.uri() == track_id.uri()));
+                    .and_then(|tracks| tracks.iter().position(|t| t.uri() == uri));
 
                 if let Some(p) = context_track_pos {
                     ui.current_page_mut().select(p);
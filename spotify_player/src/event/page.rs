@@ -29,9 +29,13 @@ pub fn handle_key_sequence_for_page(
             PageType::Browse => handle_command_for_browse_page(command, client_pub, ui, state),
             // lyrics page doesn't support any commands
             PageType::Lyrics => Ok(false),
-            PageType::Queue => Ok(handle_command_for_queue_page(command, ui)),
+            PageType::Queue => handle_command_for_queue_page(command, client_pub, ui, state),
             PageType::CommandHelp => Ok(handle_command_for_command_help_page(command, ui)),
             PageType::Logs => Ok(handle_command_for_logs_page(command, ui)),
+            PageType::Stats => handle_command_for_stats_page(command, client_pub, ui, state),
+            PageType::TopArtists => {
+                handle_command_for_top_artists_page(command, client_pub, ui, state)
+            }
         },
         Some(CommandOrAction::Action(action, ActionTarget::SelectedItem)) => match page_type {
             PageType::Search => anyhow::bail!("page search type should already be handled!"),
@@ -40,6 +44,10 @@ pub fn handle_key_sequence_for_page(
                 window::handle_action_for_focused_context_page(action, client_pub, ui, state)
             }
             PageType::Browse => handle_action_for_browse_page(action, client_pub, ui, state),
+            PageType::Stats => handle_action_for_stats_page(action, client_pub, ui, state),
+            PageType::TopArtists => {
+                handle_action_for_top_artists_page(action, client_pub, ui, state)
+            }
             _ => Ok(false),
         },
         _ => Ok(false),
@@ -82,6 +90,15 @@ fn handle_action_for_library_page(
             ui,
             client_pub,
         ),
+        LibraryFocusState::SavedShows => window::handle_action_for_selected_item(
+            action,
+            &ui.search_filtered_items(&data.user_data.saved_shows),
+            &data,
+            ui,
+            client_pub,
+        ),
+        // audiobooks don't have any actions
+        LibraryFocusState::SavedAudiobooks => Ok(false),
     }
 }
 
@@ -127,6 +144,16 @@ fn handle_command_for_library_page(
         data.user_data
             .followed_artists
             .sort_by_key(|x| x.name.to_lowercase());
+
+        // Sort shows alphabetically
+        data.user_data
+            .saved_shows
+            .sort_by_key(|x| x.name.to_lowercase());
+
+        // Sort audiobooks alphabetically
+        data.user_data
+            .saved_audiobooks
+            .sort_by_key(|x| x.name.to_lowercase());
     }
 
     if command == Command::SortLibraryByRecent {
@@ -156,6 +183,11 @@ fn handle_command_for_library_page(
         data.user_data
             .saved_albums
             .sort_by_key(|a| std::cmp::Reverse(a.added_at));
+
+        // Sort shows by recent addition
+        data.user_data
+            .saved_shows
+            .sort_by_key(|s| std::cmp::Reverse(s.added_at));
     }
 
     match focus_state {
@@ -192,6 +224,23 @@ fn handle_command_for_library_page(
                 ui,
             ))
         }
+        LibraryFocusState::SavedShows => {
+            let data = state.data.read();
+            Ok(window::handle_command_for_show_list_window(
+                command,
+                &ui.search_filtered_items(&data.user_data.saved_shows),
+                &data,
+                ui,
+            ))
+        }
+        LibraryFocusState::SavedAudiobooks => {
+            let data = state.data.read();
+            Ok(window::handle_command_for_audiobook_list_window(
+                command,
+                &ui.search_filtered_items(&data.user_data.saved_audiobooks),
+                ui,
+            ))
+        }
     }
 }
 
@@ -353,6 +402,19 @@ fn handle_key_sequence_for_search_page(
                 CommandOrAction::Action(..) => Ok(false),
             }
         }
+        SearchFocusState::Audiobooks => {
+            let audiobooks = search_results
+                .map(|s| s.audiobooks.iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            match found_keymap {
+                CommandOrAction::Command(command) => Ok(
+                    window::handle_command_for_audiobook_list_window(command, &audiobooks, ui),
+                ),
+                // audiobooks don't have any actions
+                CommandOrAction::Action(..) => Ok(false),
+            }
+        }
     }
 }
 
@@ -411,11 +473,29 @@ fn handle_command_for_context_page(
                         ));
                         Ok(true)
                     }
-                    Context::Tracks { tracks: _, desc: _ } => Ok(false),
+                    Context::Tracks { tracks: _, desc: _ } | Context::Audiobook { .. } => Ok(false),
                 },
                 None => Ok(false),
             }
         }
+        Command::CycleTimeRange => {
+            let PageState::Context {
+                context_page_type: ContextPageType::Browsing(ContextId::Tracks(tracks_id)),
+                ..
+            } = ui.current_page_mut()
+            else {
+                return Ok(false);
+            };
+            let Some(time_range) = tracks_id.top_tracks_time_range() else {
+                return Ok(false);
+            };
+            // the page's UI state is reset by the player event watcher once its context changes
+            *tracks_id = TracksId::top_tracks(time_range.next());
+            client_pub.send(ClientRequest::GetContext(ContextId::Tracks(
+                tracks_id.clone(),
+            )))?;
+            Ok(true)
+        }
         _ => window::handle_command_for_focused_context_window(command, client_pub, ui, state),
     }
 }
@@ -533,13 +613,67 @@ fn handle_command_for_browse_page(
     Ok(true)
 }
 
-fn handle_command_for_queue_page(command: Command, ui: &mut UIStateGuard) -> bool {
-    let scroll_offset = match ui.current_page() {
-        PageState::Queue { scroll_offset } => *scroll_offset,
-        _ => return false,
+fn handle_command_for_queue_page(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let (len, is_custom_queue) = {
+        let player = state.player.read();
+        (
+            player.queue.as_ref().map_or(0, |q| q.queue.len()),
+            player.custom_queue.is_some(),
+        )
     };
-    let count = ui.count_prefix;
-    handle_navigation_command(command, ui.current_page_mut(), scroll_offset, 10000, count)
+    if len == 0 {
+        return Ok(false);
+    }
+    let id = ui.current_page_mut().selected().unwrap_or_default();
+
+    let edit = match command {
+        Command::ChooseSelected => QueueEdit::JumpTo(id),
+        Command::RemoveSelectedItemFromQueue => QueueEdit::Remove(id),
+        Command::PlaySelectedItemNext => QueueEdit::PlayNext(id),
+        Command::ClearQueue => QueueEdit::Clear,
+        Command::MovePlaylistItemUp => {
+            if id == 0 {
+                return Ok(true);
+            }
+            // Spotify's queue can't be reordered, so only follow the moved item in the custom queue
+            if is_custom_queue {
+                ui.current_page_mut().select(id - 1);
+            }
+            QueueEdit::Move {
+                from: id,
+                to: id - 1,
+            }
+        }
+        Command::MovePlaylistItemDown => {
+            if id + 1 >= len {
+                return Ok(true);
+            }
+            if is_custom_queue {
+                ui.current_page_mut().select(id + 1);
+            }
+            QueueEdit::Move {
+                from: id,
+                to: id + 1,
+            }
+        }
+        _ => {
+            let count = ui.count_prefix;
+            return Ok(handle_navigation_command(
+                command,
+                ui.current_page_mut(),
+                id,
+                len,
+                count,
+            ));
+        }
+    };
+    client_pub.send(ClientRequest::EditQueue(edit))?;
+    Ok(true)
 }
 
 fn handle_command_for_command_help_page(command: Command, ui: &mut UIStateGuard) -> bool {
@@ -564,6 +698,163 @@ fn handle_command_for_logs_page(command: Command, ui: &mut UIStateGuard) -> bool
     handle_navigation_command(command, ui.current_page_mut(), scroll_offset, 10000, count)
 }
 
+fn handle_action_for_stats_page(
+    action: Action,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let (window, focus_state) = match ui.current_page() {
+        PageState::Stats { window, state } => (*window, state.focus),
+        _ => anyhow::bail!("expect a stats page state"),
+    };
+    let data = state.data.read();
+    let Some(stats) = data.stats.as_ref().filter(|s| s.window == window) else {
+        return Ok(false);
+    };
+
+    match focus_state {
+        StatsFocusState::Tracks => window::handle_action_for_selected_item(
+            action,
+            &stats.top_tracks.iter().map(|e| &e.item).collect::<Vec<_>>(),
+            &data,
+            ui,
+            client_pub,
+        ),
+        StatsFocusState::Artists => window::handle_action_for_selected_item(
+            action,
+            &stats
+                .top_artists
+                .iter()
+                .map(|e| &e.item)
+                .collect::<Vec<_>>(),
+            &data,
+            ui,
+            client_pub,
+        ),
+        StatsFocusState::Albums => window::handle_action_for_selected_item(
+            action,
+            &stats.top_albums.iter().map(|e| &e.item).collect::<Vec<_>>(),
+            &data,
+            ui,
+            client_pub,
+        ),
+    }
+}
+
+fn handle_command_for_stats_page(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let PageState::Stats {
+        window,
+        state: page_state,
+    } = ui.current_page_mut()
+    else {
+        anyhow::bail!("expect a stats page state");
+    };
+
+    if command == Command::CycleStatsWindow {
+        *window = window.next();
+        *page_state = StatsPageUIState::new();
+        client_pub.send(ClientRequest::GetListeningStats(*window))?;
+        return Ok(true);
+    }
+
+    let (window, focus_state) = (*window, page_state.focus);
+    let data = state.data.read();
+    let Some(stats) = data.stats.as_ref().filter(|s| s.window == window) else {
+        return Ok(false);
+    };
+
+    match focus_state {
+        StatsFocusState::Tracks => window::handle_command_for_track_list_window(
+            command,
+            client_pub,
+            &stats.top_tracks.iter().map(|e| &e.item).collect::<Vec<_>>(),
+            &data,
+            ui,
+            state,
+        ),
+        StatsFocusState::Artists => Ok(window::handle_command_for_artist_list_window(
+            command,
+            &stats
+                .top_artists
+                .iter()
+                .map(|e| &e.item)
+                .collect::<Vec<_>>(),
+            &data,
+            ui,
+        )),
+        StatsFocusState::Albums => window::handle_command_for_album_list_window(
+            command,
+            &stats.top_albums.iter().map(|e| &e.item).collect::<Vec<_>>(),
+            &data,
+            ui,
+            client_pub,
+        ),
+    }
+}
+
+fn handle_action_for_top_artists_page(
+    action: Action,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let PageState::TopArtists { time_range, .. } = ui.current_page() else {
+        anyhow::bail!("expect a top artists page state");
+    };
+    let data = state.data.read();
+    let Some(artists) = data.caches.top_artists.get(time_range.as_str()) else {
+        return Ok(false);
+    };
+
+    window::handle_action_for_selected_item(
+        action,
+        &artists.iter().collect::<Vec<_>>(),
+        &data,
+        ui,
+        client_pub,
+    )
+}
+
+fn handle_command_for_top_artists_page(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let PageState::TopArtists {
+        time_range,
+        artist_list,
+    } = ui.current_page_mut()
+    else {
+        anyhow::bail!("expect a top artists page state");
+    };
+
+    if command == Command::CycleTimeRange {
+        *time_range = time_range.next();
+        *artist_list = ListState::default();
+        client_pub.send(ClientRequest::GetUserTopArtists(*time_range))?;
+        return Ok(true);
+    }
+
+    let time_range = *time_range;
+    let data = state.data.read();
+    let Some(artists) = data.caches.top_artists.get(time_range.as_str()) else {
+        return Ok(false);
+    };
+    Ok(window::handle_command_for_artist_list_window(
+        command,
+        &artists.iter().collect::<Vec<_>>(),
+        &data,
+        ui,
+    ))
+}
+
 pub fn handle_navigation_command(
     command: Command,
     page: &mut PageState,
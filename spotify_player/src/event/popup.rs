@@ -42,6 +42,10 @@ pub fn handle_key_sequence_for_popup(
                 action.clone(),
             );
         }
+        PopupState::Message(_) => {
+            ui.popup = None;
+            return Ok(true);
+        }
         _ => {}
     }
 
@@ -56,6 +60,7 @@ pub fn handle_key_sequence_for_popup(
         PopupState::ConfirmAction { .. } => {
             anyhow::bail!("confirm action should be handled before")
         }
+        PopupState::Message(_) => anyhow::bail!("message popup should be handled before"),
         PopupState::Search { .. } => anyhow::bail!("search popup should be handled before"),
         PopupState::PlaylistCreate { .. } => {
             anyhow::bail!("create playlist popup should be handled before")
@@ -326,6 +331,7 @@ fn handle_key_sequence_for_create_playlist_popup(
         name,
         desc,
         current_field,
+        from_queue,
     }) = &mut ui.popup
     else {
         return Ok(false);
@@ -333,11 +339,18 @@ fn handle_key_sequence_for_create_playlist_popup(
     if key_sequence.keys.len() == 1 {
         match &key_sequence.keys[0] {
             Key::None(crossterm::event::KeyCode::Enter) => {
-                client_pub.send(ClientRequest::CreatePlaylist {
-                    playlist_name: name.get_text(),
-                    public: false,
-                    collab: false,
-                    desc: desc.get_text(),
+                client_pub.send(if *from_queue {
+                    ClientRequest::SaveQueueAsPlaylist {
+                        playlist_name: name.get_text(),
+                        desc: desc.get_text(),
+                    }
+                } else {
+                    ClientRequest::CreatePlaylist {
+                        playlist_name: name.get_text(),
+                        public: false,
+                        collab: false,
+                        desc: desc.get_text(),
+                    }
                 })?;
                 ui.popup = None;
                 return Ok(true);
@@ -632,16 +645,22 @@ fn handle_key_sequence_for_confirm_popup(
         match action {
             ConfirmableAction::DeleteTrackFromPlaylist {
                 playlist_id,
-                track_id,
+                playable_id,
             } => {
                 client_pub.send(ClientRequest::DeleteTrackFromPlaylist(
                     playlist_id,
-                    track_id,
+                    playable_id,
                 ))?;
             }
             ConfirmableAction::DeleteFromLibrary(item_id) => {
                 client_pub.send(ClientRequest::DeleteFromLibrary(item_id))?;
             }
+            ConfirmableAction::DedupePlaylist(playlist_id) => {
+                client_pub.send(ClientRequest::DedupePlaylist(playlist_id))?;
+            }
+            ConfirmableAction::CommitPlaylistOrder(playlist_id) => {
+                client_pub.send(ClientRequest::CommitPlaylistOrder(playlist_id))?;
+            }
         }
     }
     ui.popup = None;
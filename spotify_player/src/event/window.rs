@@ -5,7 +5,7 @@ use crate::{
         construct_album_actions, construct_artist_actions, construct_playlist_actions,
         construct_show_actions,
     },
-    state::{Episode, MutableWindowState, Show, UIStateGuard},
+    state::{Audiobook, Chapter, Episode, MutableWindowState, Show, UIStateGuard},
 };
 use command::Action;
 use rand::RngExt;
@@ -83,7 +83,8 @@ pub fn handle_action_for_focused_context_page(
             ui,
             client_pub,
         ),
-        None => Ok(false),
+        // chapters don't have any actions
+        Some(Context::Audiobook { .. }) | None => Ok(false),
     }
 }
 
@@ -212,6 +213,13 @@ pub fn handle_command_for_focused_context_window(
                 ui,
                 state,
             ),
+            Context::Audiobook { chapters, .. } => handle_command_for_chapter_table_window(
+                command,
+                client_pub,
+                &ui.search_filtered_items(chapters),
+                ui,
+                state,
+            ),
         },
         None => Ok(false),
     }
@@ -256,7 +264,10 @@ fn handle_playlist_modify_command(
         }
         Command::ShowActionsOnSelectedItem => {
             let mut actions = command::construct_track_actions(tracks[id], data);
-            actions.push(Action::DeleteFromPlaylist);
+            // local files can't be deleted through Spotify APIs
+            if tracks[id].playable_id().is_some() {
+                actions.push(Action::DeleteFromPlaylist);
+            }
             ui.popup = Some(PopupState::ActionList(
                 Box::new(ActionListItem::Track(tracks[id].clone(), actions)),
                 ListState::default(),
@@ -318,9 +329,9 @@ fn handle_command_for_track_table_window(
     match command {
         Command::PlayRandom | Command::ChooseSelected => {
             let uri = if command == Command::PlayRandom {
-                tracks[rand::rng().random_range(0..tracks.len())].id.uri()
+                tracks[rand::rng().random_range(0..tracks.len())].uri()
             } else {
-                filtered_tracks[id].id.uri()
+                filtered_tracks[id].uri()
             };
 
             // Update currently_playing_tracks_id based on the context
@@ -335,7 +346,7 @@ fn handle_command_for_track_table_window(
 
             let base_playback = match context_id {
                 None | Some(ContextId::Tracks(_)) => {
-                    Playback::URIs(tracks.iter().map(|t| t.id.clone().into()).collect(), None)
+                    Playback::URIs(tracks.iter().filter_map(Track::playable_id).collect(), None)
                 }
                 Some(ContextId::Show(_)) => unreachable!(
                     "show context should be handled by handle_command_for_episode_table_window"
@@ -357,9 +368,10 @@ fn handle_command_for_track_table_window(
             ));
         }
         Command::AddSelectedItemToQueue => {
-            client_pub.send(ClientRequest::AddPlayableToQueue(
-                filtered_tracks[id].id.clone().into(),
-            ))?;
+            let Some(playable_id) = filtered_tracks[id].playable_id() else {
+                return Ok(false);
+            };
+            client_pub.send(ClientRequest::AddPlayableToQueue(playable_id))?;
         }
         Command::JumpToHighlightTrackInContext => {
             ui.popup = None;
@@ -367,7 +379,7 @@ fn handle_command_for_track_table_window(
             let location = tracks
                 .iter()
                 .enumerate()
-                .find(|(_, track)| track.id == selected_track.id)
+                .find(|(_, track)| track.uri() == selected_track.uri())
                 .unwrap();
 
             // Move selection and change the offset so selection is at the top
@@ -411,7 +423,7 @@ pub fn handle_command_for_track_list_window(
             state.player.write().currently_playing_tracks_id = None;
 
             client_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
-                Playback::URIs(vec![tracks[id].id.clone().into()], None),
+                Playback::URIs(tracks[id].playable_id().into_iter().collect(), None),
                 None,
             )))?;
         }
@@ -423,9 +435,9 @@ pub fn handle_command_for_track_list_window(
             ));
         }
         Command::AddSelectedItemToQueue => {
-            client_pub.send(ClientRequest::AddPlayableToQueue(
-                tracks[id].id.clone().into(),
-            ))?;
+            if let Some(playable_id) = tracks[id].playable_id() {
+                client_pub.send(ClientRequest::AddPlayableToQueue(playable_id))?;
+            }
         }
         _ => return Ok(false),
     }
@@ -598,6 +610,34 @@ pub fn handle_command_for_show_list_window(
     true
 }
 
+pub fn handle_command_for_audiobook_list_window(
+    command: Command,
+    audiobooks: &[&Audiobook],
+    ui: &mut UIStateGuard,
+) -> bool {
+    let id = ui.current_page_mut().selected().unwrap_or_default();
+    if id >= audiobooks.len() {
+        return false;
+    }
+
+    let count = ui.count_prefix;
+    if handle_navigation_command(command, ui.current_page_mut(), id, audiobooks.len(), count) {
+        return true;
+    }
+    match command {
+        Command::ChooseSelected => {
+            let context_id = ContextId::Audiobook(audiobooks[id].id.clone());
+            ui.new_page(PageState::Context {
+                id: None,
+                context_page_type: ContextPageType::Browsing(context_id),
+                state: None,
+            });
+        }
+        _ => return false,
+    }
+    true
+}
+
 pub fn handle_command_for_episode_list_window(
     command: Command,
     client_pub: &flume::Sender<ClientRequest>,
@@ -691,3 +731,51 @@ fn handle_command_for_episode_table_window(
     }
     Ok(true)
 }
+
+fn handle_command_for_chapter_table_window(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    chapters: &[&Chapter],
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> Result<bool> {
+    let id = ui.current_page_mut().selected().unwrap_or_default();
+    if id >= chapters.len() {
+        return Ok(false);
+    }
+
+    let count = ui.count_prefix;
+    if handle_navigation_command(command, ui.current_page_mut(), id, chapters.len(), count) {
+        return Ok(true);
+    }
+    match command {
+        Command::ChooseSelected => {
+            // Audiobook context doesn't have a Tracks context, so clear it
+            state.player.write().currently_playing_tracks_id = None;
+
+            // Spotify doesn't support playing an audiobook as a context,
+            // so play its chapters starting from the selected one instead
+            let playback = Playback::URIs(
+                chapters.iter().map(|c| c.id.clone().into()).collect(),
+                Some(rspotify::model::Offset::Uri(chapters[id].id.uri())),
+            );
+            // resume a started chapter from where it was left off
+            let request = match chapters[id]
+                .resume_position
+                .filter(|_| !chapters[id].fully_played)
+                .and_then(|p| chrono::Duration::from_std(p).ok())
+            {
+                Some(position) => PlayerRequest::StartPlaybackAt(playback, position),
+                None => PlayerRequest::StartPlayback(playback, None),
+            };
+            client_pub.send(ClientRequest::Player(request))?;
+        }
+        Command::AddSelectedItemToQueue => {
+            client_pub.send(ClientRequest::AddPlayableToQueue(
+                chapters[id].id.clone().into(),
+            ))?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
@@ -0,0 +1,253 @@
+//! Export a list of tracks into a file format that can be shared outside of Spotify
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use rspotify::model::{AlbumId, PlaylistId};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Track, TrackKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ExportFormat {
+    M3u,
+    Csv,
+    Json,
+    Txt,
+}
+
+/// The source of tracks to export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ExportSource {
+    Playlist(PlaylistId<'static>),
+    Album(AlbumId<'static>),
+    LikedTracks,
+    Queue,
+}
+
+/// A track's data written to an export file
+#[derive(Debug, Serialize)]
+struct ExportedTrack {
+    uri: String,
+    title: String,
+    artists: Vec<String>,
+    album: Option<String>,
+    duration_ms: u64,
+    /// the date the track was added to the source, in RFC 3339 format
+    added_at: Option<String>,
+    isrc: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedTrackList<'a> {
+    name: &'a str,
+    tracks: Vec<ExportedTrack>,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::M3u => "m3u",
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Txt => "txt",
+        }
+    }
+}
+
+impl From<&Track> for ExportedTrack {
+    fn from(track: &Track) -> Self {
+        Self {
+            uri: track.uri(),
+            title: track.name.clone(),
+            artists: match track.kind {
+                TrackKind::Track => track.artists.iter().map(|a| a.name.clone()).collect(),
+                _ => vec![track.artists_info()],
+            },
+            album: Some(track.album_info()).filter(|a| !a.is_empty()),
+            duration_ms: u64::try_from(track.duration.as_millis()).unwrap_or(u64::MAX),
+            added_at: (track.added_at > 0)
+                .then(|| {
+                    chrono::DateTime::from_timestamp(i64::try_from(track.added_at).ok()?, 0)
+                        .map(|t| t.to_rfc3339())
+                })
+                .flatten(),
+            isrc: track.isrc.clone(),
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a special character
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Get a path inside `dir` to export a named list of tracks into,
+/// adding a numbered suffix to the file name to not overwrite an existing export
+pub fn export_file_path(dir: &Path, name: &str, format: ExportFormat) -> PathBuf {
+    let file_name = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    let mut path = dir.join(format!("{file_name}.{}", format.extension()));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{file_name} ({n}).{}", format.extension()));
+        n += 1;
+    }
+    path
+}
+
+/// Export a named list of tracks into the given format
+pub fn export_tracks(name: &str, tracks: &[Track], format: ExportFormat) -> Result<String> {
+    let tracks = tracks.iter().map(ExportedTrack::from).collect::<Vec<_>>();
+
+    let mut out = String::new();
+    match format {
+        ExportFormat::M3u => {
+            writeln!(out, "#EXTM3U")?;
+            writeln!(out, "#PLAYLIST:{name}")?;
+            for t in tracks {
+                writeln!(
+                    out,
+                    "#EXTINF:{},{} - {}",
+                    t.duration_ms / 1000,
+                    t.artists.join(", "),
+                    t.title
+                )?;
+                writeln!(out, "{}", t.uri)?;
+            }
+        }
+        ExportFormat::Csv => {
+            writeln!(out, "uri,title,artists,album,duration_ms,added_at,isrc")?;
+            for t in tracks {
+                let fields = [
+                    t.uri,
+                    t.title,
+                    t.artists.join(", "),
+                    t.album.unwrap_or_default(),
+                    t.duration_ms.to_string(),
+                    t.added_at.unwrap_or_default(),
+                    t.isrc.unwrap_or_default(),
+                ];
+                writeln!(
+                    out,
+                    "{}",
+                    fields
+                        .iter()
+                        .map(|f| csv_field(f))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+            }
+        }
+        ExportFormat::Json => {
+            out = serde_json::to_string_pretty(&ExportedTrackList { name, tracks })?;
+            out.push('\n');
+        }
+        ExportFormat::Txt => {
+            for t in tracks {
+                writeln!(
+                    out,
+                    "{} - {} ({})",
+                    t.artists.join(", "),
+                    t.title,
+                    crate::utils::format_duration(&chrono::Duration::milliseconds(
+                        i64::try_from(t.duration_ms).unwrap_or(i64::MAX)
+                    ))
+                )?;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspotify::model::{EpisodeId, TrackId};
+
+    fn track(name: &str, kind: TrackKind) -> Track {
+        Track {
+            id: (kind == TrackKind::Track)
+                .then(|| TrackId::from_id("4uLU6hMCjMI75M1A2tKUQC").unwrap()),
+            name: name.to_string(),
+            artists: vec![],
+            album: None,
+            duration: std::time::Duration::from_secs(90),
+            explicit: false,
+            added_at: 0,
+            isrc: None,
+            kind,
+        }
+    }
+
+    #[test]
+    fn csv_export() {
+        let tracks = [
+            track("Hello, \"World\"", TrackKind::Track),
+            track(
+                "Episode",
+                TrackKind::Episode {
+                    id: EpisodeId::from_id("4uLU6hMCjMI75M1A2tKUQC").unwrap(),
+                    show: "Show".to_string(),
+                },
+            ),
+        ];
+
+        let out = export_tracks("list", &tracks, ExportFormat::Csv).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "uri,title,artists,album,duration_ms,added_at,isrc"
+        );
+        assert_eq!(
+            lines[1],
+            "spotify:track:4uLU6hMCjMI75M1A2tKUQC,\"Hello, \"\"World\"\"\",,,90000,,"
+        );
+        assert_eq!(
+            lines[2],
+            "spotify:episode:4uLU6hMCjMI75M1A2tKUQC,Episode,Show,,90000,,"
+        );
+    }
+
+    #[test]
+    fn m3u_export() {
+        let out =
+            export_tracks("list", &[track("a", TrackKind::Track)], ExportFormat::M3u).unwrap();
+        assert_eq!(
+            out,
+            "#EXTM3U\n#PLAYLIST:list\n#EXTINF:90, - a\nspotify:track:4uLU6hMCjMI75M1A2tKUQC\n"
+        );
+    }
+
+    #[test]
+    fn export_path_does_not_overwrite() {
+        let dir =
+            std::env::temp_dir().join(format!("spotify_player_export_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = export_file_path(&dir, "My/List", ExportFormat::Csv);
+        assert_eq!(path, dir.join("My_List.csv"));
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(
+            export_file_path(&dir, "My/List", ExportFormat::Csv),
+            dir.join("My_List (1).csv")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,517 @@
+//! Import a list of tracks from a text, CSV or M3U file by matching its entries against Spotify's tracks
+
+use std::fmt::Write as _;
+
+use rspotify::model::TrackId;
+use serde::{Deserialize, Serialize};
+
+use crate::{state::Track, utils::parse_uri};
+
+/// Minimum score of a match to be imported without a review
+pub const CONFIDENT_SCORE: f64 = 0.8;
+/// Minimum score of a match to be considered at all
+pub const MIN_SCORE: f64 = 0.5;
+
+const TITLE_WEIGHT: f64 = 0.6;
+const ARTIST_WEIGHT: f64 = 0.3;
+const DURATION_WEIGHT: f64 = 0.1;
+
+/// Maximum difference (in seconds) between two durations to be considered equal
+const DURATION_TOLERANCE_SECS: f64 = 2.0;
+/// Minimum difference (in seconds) between two durations to be considered unrelated
+const DURATION_MAX_DIFF_SECS: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ImportFileFormat {
+    /// One "Artist - Title" entry per line
+    Txt,
+    Csv,
+    M3u,
+}
+
+/// An entry of an import file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportEntry {
+    /// the entry's original line in the file
+    pub line: String,
+    /// the entry's Spotify track, if the file specifies one
+    pub id: Option<TrackId<'static>>,
+    pub title: String,
+    pub artists: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// An import file entry matched to a Spotify track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedEntry {
+    pub line: String,
+    pub track: String,
+    pub uri: String,
+    pub score: f64,
+}
+
+/// The result of importing a file into a playlist
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub playlist_name: String,
+    pub added: Vec<MatchedEntry>,
+    /// entries matched to a track that is already in the playlist
+    pub duplicates: Vec<MatchedEntry>,
+    /// entries whose best match is not good enough to be imported
+    pub low_confidence: Vec<MatchedEntry>,
+    pub missing: Vec<String>,
+}
+
+impl ImportFileFormat {
+    /// Get the format of a file based on its extension, defaulting to the text format
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("csv") => Self::Csv,
+            Some("m3u" | "m3u8") => Self::M3u,
+            _ => Self::Txt,
+        }
+    }
+}
+
+impl ImportEntry {
+    /// The query to search for the entry's track
+    pub fn query(&self) -> String {
+        match self.artists {
+            Some(ref artists) => format!("{} {}", normalize(&self.title), normalize(artists)),
+            None => normalize(&self.title),
+        }
+    }
+
+    /// Score how well a track matches the entry, from 0 (unrelated) to 1 (exact match)
+    pub fn score(&self, track: &Track) -> f64 {
+        let mut total = TITLE_WEIGHT * similarity(&normalize(&self.title), &normalize(&track.name));
+        let mut weights = TITLE_WEIGHT;
+
+        if let Some(ref artists) = self.artists {
+            let joined = normalize(&track.artists_info());
+            let artist_score = split_artists(artists)
+                .map(|a| {
+                    track
+                        .artists
+                        .iter()
+                        .map(|b| similarity(&a, &normalize(&b.name)))
+                        .fold(similarity(&a, &joined), f64::max)
+                })
+                .fold(similarity(&normalize(artists), &joined), f64::max);
+            total += ARTIST_WEIGHT * artist_score;
+            weights += ARTIST_WEIGHT;
+        }
+
+        if let Some(duration_ms) = self.duration_ms {
+            let diff = (duration_ms as f64 / 1000.0 - track.duration.as_secs_f64()).abs();
+            let duration_score = 1.0
+                - ((diff - DURATION_TOLERANCE_SECS)
+                    / (DURATION_MAX_DIFF_SECS - DURATION_TOLERANCE_SECS))
+                    .clamp(0.0, 1.0);
+            total += DURATION_WEIGHT * duration_score;
+            weights += DURATION_WEIGHT;
+        }
+
+        total / weights
+    }
+
+    /// Find the best matching track among candidates, preferring the earlier ones on ties
+    pub fn best_match(&self, candidates: Vec<Track>) -> Option<(f64, Track)> {
+        candidates.into_iter().map(|t| (self.score(&t), t)).fold(
+            None,
+            |best, (score, t)| match best {
+                Some((best_score, _)) if best_score >= score => best,
+                _ => Some((score, t)),
+            },
+        )
+    }
+
+    pub fn matched(&self, score: f64, track: &Track) -> MatchedEntry {
+        MatchedEntry {
+            line: self.line.clone(),
+            track: format!("{} - {}", track.artists_info(), track.name),
+            uri: track.uri(),
+            score,
+        }
+    }
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Imported {} tracks into '{}': {} already in the playlist, {} low-confidence matches, {} not found",
+            self.added.len(),
+            self.playlist_name,
+            self.duplicates.len(),
+            self.low_confidence.len(),
+            self.missing.len()
+        )
+    }
+
+    /// Whether some entries couldn't be imported and need a review
+    pub fn needs_review(&self) -> bool {
+        !self.low_confidence.is_empty() || !self.missing.is_empty()
+    }
+
+    /// A text report of the entries that couldn't be imported
+    pub fn details(&self) -> String {
+        let mut out = String::new();
+        if !self.low_confidence.is_empty() {
+            writeln!(
+                out,
+                "# Low-confidence matches (not imported, score below {CONFIDENT_SCORE})"
+            )
+            .unwrap();
+            for m in &self.low_confidence {
+                writeln!(
+                    out,
+                    "{}\n    best match: {} ({}, score: {:.2})",
+                    m.line, m.track, m.uri, m.score
+                )
+                .unwrap();
+            }
+            out.push('\n');
+        }
+        if !self.missing.is_empty() {
+            writeln!(out, "# Not found").unwrap();
+            for line in &self.missing {
+                writeln!(out, "{line}").unwrap();
+            }
+        }
+        out
+    }
+}
+
+/// Parse the entries of an import file
+pub fn parse_entries(content: &str, format: ImportFileFormat) -> Vec<ImportEntry> {
+    let entries = match format {
+        ImportFileFormat::Txt => content.lines().map(parse_text_line).collect(),
+        ImportFileFormat::Csv => parse_csv(content),
+        ImportFileFormat::M3u => parse_m3u(content),
+    };
+    entries
+        .into_iter()
+        .filter(|e| e.id.is_some() || !e.title.is_empty())
+        .collect()
+}
+
+/// Parse a Spotify track URI or URL
+fn parse_track_id(s: &str) -> Option<TrackId<'static>> {
+    let s = s.trim();
+    if let Some(path) = s.strip_prefix("https://open.spotify.com/track/") {
+        let id = path.split(['?', '/']).next()?;
+        return TrackId::from_id(id).ok().map(TrackId::into_static);
+    }
+    TrackId::from_uri(&parse_uri(s))
+        .ok()
+        .map(TrackId::into_static)
+}
+
+/// Parse a "{minutes}:{seconds}" (optionally prefixed by hours) or a milliseconds duration
+fn parse_duration_ms(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if !s.contains(':') {
+        return s.parse().ok();
+    }
+    s.split(':')
+        .try_fold(0, |acc: u64, part| {
+            Some(acc * 60 + part.parse::<u64>().ok()?)
+        })
+        .map(|secs| secs * 1000)
+}
+
+/// Parse an "Artist - Title" line, optionally followed by a "({minutes}:{seconds})" duration
+fn parse_text_line(line: &str) -> ImportEntry {
+    let mut entry = ImportEntry {
+        line: line.trim().to_string(),
+        id: parse_track_id(line),
+        title: String::new(),
+        artists: None,
+        duration_ms: None,
+    };
+    if entry.id.is_some() {
+        return entry;
+    }
+
+    let mut text = line.trim();
+    if let Some((rest, duration)) = text
+        .strip_suffix(')')
+        .and_then(|t| t.rsplit_once(" ("))
+        .and_then(|(rest, d)| Some((rest, parse_duration_ms(d).filter(|_| d.contains(':'))?)))
+    {
+        text = rest;
+        entry.duration_ms = Some(duration);
+    }
+
+    match text.split_once(" - ") {
+        Some((artists, title)) => {
+            entry.artists = Some(artists.trim().to_string());
+            entry.title = title.trim().to_string();
+        }
+        None => entry.title = text.to_string(),
+    }
+    entry
+}
+
+fn parse_m3u(content: &str) -> Vec<ImportEntry> {
+    let mut entries = vec![];
+    // the duration and description from the last `#EXTINF` directive
+    let mut info: Option<(Option<u64>, String)> = None;
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (secs, desc) = extinf.split_once(',').unwrap_or(("", extinf));
+            let duration_ms = secs
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .and_then(|s| u64::try_from(s).ok())
+                .filter(|s| *s > 0)
+                .map(|s| s * 1000);
+            info = Some((duration_ms, desc.trim().to_string()));
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (duration_ms, desc) = info.take().unwrap_or_else(|| {
+            let stem = std::path::Path::new(line)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(line);
+            (None, stem.to_string())
+        });
+        let mut entry = parse_text_line(&desc);
+        entry.line = line.to_string();
+        entry.id = parse_track_id(line);
+        entry.duration_ms = duration_ms.or(entry.duration_ms);
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// Split a CSV row into fields, unquoting the quoted ones
+fn parse_csv_row(row: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parse a CSV file with a header row, falling back to the text format if no known column is found
+fn parse_csv(content: &str) -> Vec<ImportEntry> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return vec![];
+    };
+    let header = parse_csv_row(header)
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let uri_col = column(&[
+        "uri",
+        "spotify uri",
+        "track uri",
+        "spotify_uri",
+        "track_uri",
+    ]);
+    let title_col = column(&["title", "name", "track", "track name", "track_name"]);
+    let artists_col = column(&[
+        "artists",
+        "artist",
+        "artist name(s)",
+        "artist_name",
+        "artist name",
+    ]);
+    let duration_col = column(&["duration_ms", "duration (ms)", "duration"]);
+
+    if uri_col.is_none() && title_col.is_none() {
+        return content.lines().map(parse_text_line).collect();
+    }
+
+    lines
+        .map(|line| {
+            let fields = parse_csv_row(line);
+            let get = |col: Option<usize>| {
+                col.and_then(|i| fields.get(i))
+                    .map(|f| f.trim())
+                    .filter(|f| !f.is_empty())
+            };
+            ImportEntry {
+                line: line.trim().to_string(),
+                id: get(uri_col).and_then(parse_track_id),
+                title: get(title_col).unwrap_or_default().to_string(),
+                artists: get(artists_col).map(str::to_string),
+                duration_ms: get(duration_col).and_then(parse_duration_ms),
+            }
+        })
+        .collect()
+}
+
+/// Normalize a title or an artist name for comparison, dropping
+/// bracketed parts, featured artists and version suffixes (e.g. "- Remastered 2011")
+fn normalize(s: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0_usize;
+    for c in s.to_lowercase().chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            c if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+
+    for sep in [" - ", " feat. ", " ft. ", " featuring "] {
+        if let Some((head, _)) = out.split_once(sep) {
+            if !head.trim().is_empty() {
+                out = head.to_string();
+            }
+        }
+    }
+
+    out.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn split_artists(artists: &str) -> impl Iterator<Item = String> + '_ {
+    artists
+        .split([',', '&', ';', '/'])
+        .map(normalize)
+        .filter(|a| !a.is_empty())
+}
+
+/// Similarity of two strings based on their Levenshtein distance, from 0 (different) to 1 (equal)
+fn similarity(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    let distance = prev[b.len()] as f64 / max_len as f64;
+    1.0 - distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Artist, TrackKind};
+    use rspotify::model::{ArtistId, Id};
+
+    fn make_track(name: &str, artists: &[&str], duration_secs: u64) -> Track {
+        Track {
+            id: Some(
+                TrackId::from_id("3n3Ppam7vgaVa1iaRUc9Lp")
+                    .unwrap()
+                    .into_static(),
+            ),
+            name: name.to_string(),
+            artists: artists
+                .iter()
+                .map(|a| Artist {
+                    id: ArtistId::from_id("0OdUWJ0sBjDrqHygGUXeCF")
+                        .unwrap()
+                        .into_static(),
+                    name: (*a).to_string(),
+                })
+                .collect(),
+            album: None,
+            duration: std::time::Duration::from_secs(duration_secs),
+            explicit: false,
+            added_at: 0,
+            isrc: None,
+            kind: TrackKind::Track,
+        }
+    }
+
+    #[test]
+    fn parse_text_line_with_duration() {
+        let entry = parse_text_line("Daft Punk - One More Time (5:20)");
+        assert_eq!(entry.artists.as_deref(), Some("Daft Punk"));
+        assert_eq!(entry.title, "One More Time");
+        assert_eq!(entry.duration_ms, Some(320_000));
+    }
+
+    #[test]
+    fn parse_text_line_with_uri() {
+        let entry = parse_text_line("https://open.spotify.com/track/3n3Ppam7vgaVa1iaRUc9Lp?si=abc");
+        assert_eq!(entry.id.unwrap().id(), "3n3Ppam7vgaVa1iaRUc9Lp");
+    }
+
+    #[test]
+    fn parse_csv_with_header_and_quotes() {
+        let entries = parse_entries(
+            "title,artists,duration_ms\n\"Hello, World\",\"A, B\",1000\n",
+            ImportFileFormat::Csv,
+        );
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "Hello, World");
+        assert_eq!(entries[0].artists.as_deref(), Some("A, B"));
+        assert_eq!(entries[0].duration_ms, Some(1000));
+    }
+
+    #[test]
+    fn parse_m3u_with_extinf() {
+        let entries = parse_entries(
+            "#EXTM3U\n#EXTINF:200,Artist - Title\nmusic/track.mp3\nother/Foo - Bar.mp3\n",
+            ImportFileFormat::M3u,
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Title");
+        assert_eq!(entries[0].duration_ms, Some(200_000));
+        assert_eq!(entries[1].artists.as_deref(), Some("Foo"));
+        assert_eq!(entries[1].title, "Bar");
+    }
+
+    #[test]
+    fn score_ignores_version_suffixes() {
+        let entry = parse_text_line("The Beatles - Let It Be (4:03)");
+        let remaster = make_track("Let It Be - Remastered 2009", &["The Beatles"], 243);
+        let cover = make_track("Let It Be", &["Some Cover Band"], 280);
+
+        assert!(entry.score(&remaster) >= CONFIDENT_SCORE);
+        assert!(entry.score(&cover) < entry.score(&remaster));
+
+        let (_, best) = entry.best_match(vec![cover, remaster]).unwrap();
+        assert_eq!(best.name, "Let It Be - Remastered 2009");
+    }
+}
@@ -1,14 +1,21 @@
+#[cfg(feature = "daemon")]
+mod alarm;
 mod auth;
+mod backup;
 mod cli;
 mod client;
 mod command;
 mod config;
 mod event;
+mod export;
+mod import;
 mod key;
 mod log_layer;
 #[cfg(feature = "media-control")]
 mod media_control;
+mod playlist_edit;
 mod playlist_folders;
+mod scrobble;
 mod state;
 #[cfg(feature = "streaming")]
 mod streaming;
@@ -125,16 +132,59 @@ async fn start_app(state: &state::SharedState) -> Result<()> {
         state::USER_LIKED_TRACKS_ID.to_owned(),
     )))?;
     client_pub.send(client::ClientRequest::GetUserSavedShows)?;
+    client_pub.send(client::ClientRequest::GetUserSavedAudiobooks)?;
+
+    // restore the custom queue saved by the previous session
+    #[cfg(feature = "streaming")]
+    if config::get_config().app_config.resume_custom_queue && state.should_use_custom_queue() {
+        if let Some(saved) = state::load_data_from_file_cache::<state::SavedCustomQueue>(
+            state::FileCacheKey::CustomQueue,
+            &config::get_config().cache_folder,
+        ) {
+            state.update_player(|player| player.custom_queue = Some(saved.queue));
+            client_pub.send(client::ClientRequest::ResumeCustomQueue(
+                chrono::Duration::milliseconds(saved.progress_ms),
+            ))?;
+        }
+    }
 
     // client socket task (for handling CLI commands)
     tokio::task::spawn({
         let client = client.clone();
         let state = state.clone();
         async move {
-            cli::start_socket(&client, Some(&state), None).await;
+            cli::start_socket(client, Some(state), None).await;
+        }
+    });
+
+    // remote client socket task (for handling CLI commands from other machines)
+    tokio::task::spawn({
+        let client = client.clone();
+        let state = state.clone();
+        async move {
+            cli::start_remote_socket(client, Some(state)).await;
         }
     });
 
+    // scheduled playlist sync task
+    #[cfg(feature = "daemon")]
+    if state.is_daemon {
+        tokio::task::spawn({
+            let client = client.clone();
+            async move {
+                cli::start_playlist_sync_scheduler(client).await;
+            }
+        });
+
+        // alarm scheduler task
+        tokio::task::spawn({
+            let client = client.clone();
+            async move {
+                cli::start_alarm_scheduler(client).await;
+            }
+        });
+    }
+
     // client event handler task
     tokio::task::spawn({
         let state = state.clone();
@@ -154,6 +204,16 @@ async fn start_app(state: &state::SharedState) -> Result<()> {
         }
     });
 
+    // task retrying the failed scrobbles
+    if scrobble::is_enabled() {
+        tokio::task::spawn({
+            let client = client.clone();
+            async move {
+                client::start_scrobble_retrier(client).await;
+            }
+        });
+    }
+
     // player event watcher task
     std::thread::Builder::new()
         .name("player-event-watcher".to_string())
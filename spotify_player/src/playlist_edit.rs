@@ -0,0 +1,404 @@
+//! Computations of edits on a playlist's items
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Track, TrackKind, TrackOrder};
+
+/// The key identifying duplicate tracks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum DedupeKey {
+    /// the same Spotify track
+    #[default]
+    Id,
+    /// the same recording, possibly released in different albums
+    Isrc,
+    /// the same title and artists
+    TitleArtist,
+}
+
+impl DedupeKey {
+    fn key(self, track: &Track) -> String {
+        match self {
+            Self::Id => track.uri(),
+            // fall back to the track's URI for tracks without an ISRC
+            Self::Isrc => track
+                .isrc
+                .as_ref()
+                .map_or_else(|| track.uri(), |isrc| isrc.to_uppercase()),
+            Self::TitleArtist => format!(
+                "{}\u{0}{}",
+                track.name.to_lowercase(),
+                track.artists_info().to_lowercase()
+            ),
+        }
+    }
+}
+
+/// A set operation combining the tracks of several playlists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum SetOperation {
+    /// tracks in any of the playlists
+    Union,
+    /// tracks of the first playlist that are in all the other playlists
+    Intersect,
+    /// tracks of the first playlist that are in none of the other playlists
+    Difference,
+}
+
+/// Combine the tracks of playlists with a set operation, tracks being compared by their URIs.
+///
+/// If `preserve_order` is set, the result follows the order of the playlists and of their tracks,
+/// otherwise it is sorted by the date tracks were added to their playlist (oldest first).
+/// If `dedupe` is set, each track appears at most once in the result.
+pub fn combine(
+    op: SetOperation,
+    playlists: &[Vec<Track>],
+    preserve_order: bool,
+    dedupe: bool,
+) -> Vec<Track> {
+    let Some((first, others)) = playlists.split_first() else {
+        return vec![];
+    };
+    let others = others
+        .iter()
+        .map(|tracks| tracks.iter().map(Track::uri).collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+
+    let mut tracks = match op {
+        SetOperation::Union => playlists.iter().flatten().cloned().collect::<Vec<_>>(),
+        SetOperation::Intersect => first
+            .iter()
+            .filter(|t| others.iter().all(|uris| uris.contains(&t.uri())))
+            .cloned()
+            .collect(),
+        SetOperation::Difference => first
+            .iter()
+            .filter(|t| !others.iter().any(|uris| uris.contains(&t.uri())))
+            .cloned()
+            .collect(),
+    };
+
+    if !preserve_order {
+        // stable sort, tracks added at the same date keep the playlists' order
+        tracks.sort_by_key(|t| t.added_at);
+    }
+    if dedupe {
+        let mut seen = HashSet::new();
+        tracks.retain(|t| seen.insert(t.uri()));
+    }
+    tracks
+}
+
+/// Find the positions of duplicate tracks in a playlist's items,
+/// keeping the earliest added occurrence of each track.
+///
+/// `items` are indexed by their position in the playlist, `None` items are ignored.
+/// The returned positions are sorted in ascending order.
+pub fn find_duplicates(items: &[Option<Track>], by: DedupeKey) -> Vec<usize> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (pos, track) in items.iter().enumerate() {
+        match track {
+            // local files can't be removed through Spotify APIs, so they are never duplicates
+            Some(track) if !matches!(track.kind, TrackKind::Local { .. }) => {
+                groups.entry(by.key(track)).or_default().push(pos);
+            }
+            _ => {}
+        }
+    }
+
+    let mut duplicates = groups
+        .into_values()
+        .filter(|positions| positions.len() > 1)
+        .flat_map(|positions| {
+            let keep = positions
+                .iter()
+                .copied()
+                .min_by_key(|&pos| (items[pos].as_ref().map(|t| t.added_at), pos));
+            positions.into_iter().filter(move |&pos| Some(pos) != keep)
+        })
+        .collect::<Vec<_>>();
+    duplicates.sort_unstable();
+    duplicates
+}
+
+/// Shift positions of a playlist's items to account for the items removed
+/// at `removed` positions, all positions referring to the original playlist
+pub fn shift_positions(positions: &[usize], removed: &[usize]) -> Vec<usize> {
+    positions
+        .iter()
+        .map(|&pos| pos - removed.iter().filter(|&&r| r < pos).count())
+        .collect()
+}
+
+/// A move of a playlist's item, as sent in a `ClientRequest::ReorderPlaylistItems` request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemMove {
+    /// the item's position before the move
+    pub range_start: usize,
+    /// the item's position after the move
+    pub insert_index: usize,
+}
+
+/// Compute the order of a playlist's items sorted by `by`, placing `None` items last.
+///
+/// The returned order is a permutation of the items' positions,
+/// the `i`-th element being the current position of the item to place at position `i`.
+pub fn sorted_order(items: &[Option<Track>], by: TrackOrder, reverse: bool) -> Vec<usize> {
+    let mut order = (0..items.len()).collect::<Vec<_>>();
+    // stable sort, equal items keep their current order to avoid unnecessary moves
+    order.sort_by(|&x, &y| match (&items[x], &items[y]) {
+        (Some(x), Some(y)) if reverse => by.compare(y, x),
+        (Some(x), Some(y)) => by.compare(x, y),
+        (x, y) => y.is_some().cmp(&x.is_some()),
+    });
+    order
+}
+
+/// Compute the order of a playlist's items matching a reordered list of its tracks,
+/// placing `None` items last. See [`sorted_order`] for the format of the returned order.
+///
+/// Returns `None` if `tracks` are not the tracks of the playlist's items,
+/// e.g. if the playlist was modified since the tracks were fetched.
+pub fn matching_order(items: &[Option<Track>], tracks: &[Track]) -> Option<Vec<usize>> {
+    let mut positions: HashMap<String, VecDeque<usize>> = HashMap::new();
+    for (pos, track) in items.iter().enumerate() {
+        if let Some(track) = track {
+            positions.entry(track.uri()).or_default().push_back(pos);
+        }
+    }
+
+    // the n-th occurrence of a track is matched to its n-th occurrence in the playlist
+    let mut order = tracks
+        .iter()
+        .map(|t| positions.get_mut(&t.uri())?.pop_front())
+        .collect::<Option<Vec<_>>>()?;
+    if positions.values().any(|p| !p.is_empty()) {
+        return None;
+    }
+    order.extend((0..items.len()).filter(|&pos| items[pos].is_none()));
+    Some(order)
+}
+
+/// Compute a minimal sequence of single item moves reordering a playlist into `order`.
+/// See [`sorted_order`] for the format of `order`.
+pub fn reorder_moves(order: &[usize]) -> Vec<ItemMove> {
+    // items of a longest increasing subsequence of `order` are already in the right relative order,
+    // moving each of the other items once is the minimal number of moves
+    let fixed = longest_increasing_subsequence(order);
+
+    let mut current = (0..order.len()).collect::<Vec<_>>();
+    let position = |current: &[usize], item: usize| {
+        current
+            .iter()
+            .position(|&x| x == item)
+            .expect("order should be a permutation of the items' positions")
+    };
+
+    let mut moves = vec![];
+    for (i, &item) in order.iter().enumerate() {
+        if fixed[item] {
+            continue;
+        }
+        // place the item right after its predecessor in the new order
+        let from = position(&current, item);
+        let to = match i.checked_sub(1) {
+            None => 0,
+            Some(j) => {
+                let prev = position(&current, order[j]);
+                if prev < from {
+                    prev + 1
+                } else {
+                    prev
+                }
+            }
+        };
+        if from != to {
+            current.remove(from);
+            current.insert(to, item);
+            moves.push(ItemMove {
+                range_start: from,
+                insert_index: to,
+            });
+        }
+    }
+    moves
+}
+
+/// Find a longest increasing subsequence of a permutation of `0..values.len()`,
+/// returning whether each value is in the subsequence
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<bool> {
+    // `tails[k]` is the index of the smallest last value of increasing subsequences of length `k + 1`
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; values.len()];
+    for (i, &v) in values.iter().enumerate() {
+        let k = tails.partition_point(|&j| values[j] < v);
+        if k > 0 {
+            prev[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut in_subsequence = vec![false; values.len()];
+    let mut i = tails.last().copied();
+    while let Some(j) = i {
+        in_subsequence[values[j]] = true;
+        i = prev[j];
+    }
+    in_subsequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::TrackKind;
+    use rspotify::model::TrackId;
+
+    fn make_track(n: u32, added_at: u64) -> Track {
+        Track {
+            id: Some(
+                TrackId::from_id(format!("track{n:017}"))
+                    .unwrap()
+                    .into_static(),
+            ),
+            name: format!("track {n}"),
+            artists: vec![],
+            album: None,
+            duration: std::time::Duration::default(),
+            explicit: false,
+            added_at,
+            isrc: None,
+            kind: TrackKind::Track,
+        }
+    }
+
+    #[test]
+    fn find_duplicates_keeps_earliest_added() {
+        let items = vec![
+            Some(make_track(1, 30)),
+            Some(make_track(2, 10)),
+            None,
+            Some(make_track(1, 20)),
+            Some(make_track(1, 40)),
+        ];
+        assert_eq!(find_duplicates(&items, DedupeKey::Id), vec![0, 4]);
+    }
+
+    #[test]
+    fn find_duplicates_by_isrc() {
+        let mut items = (1..=3).map(|n| Some(make_track(n, 0))).collect::<Vec<_>>();
+        for item in items.iter_mut().skip(1) {
+            item.as_mut().unwrap().isrc = Some("usrc17607839".to_string());
+        }
+        items[2].as_mut().unwrap().isrc = Some("USRC17607839".to_string());
+
+        assert!(find_duplicates(&items, DedupeKey::Id).is_empty());
+        assert_eq!(find_duplicates(&items, DedupeKey::Isrc), vec![2]);
+    }
+
+    #[test]
+    fn find_duplicates_skips_local_files() {
+        let local = |added_at| {
+            let mut track = make_track(1, added_at);
+            track.id = None;
+            track.kind = TrackKind::Local {
+                uri: "spotify:local:artist:album:track+1:180".to_string(),
+                artists: "artist".to_string(),
+                album: "album".to_string(),
+            };
+            Some(track)
+        };
+        let items = vec![local(10), Some(make_track(2, 10)), local(20)];
+        assert!(find_duplicates(&items, DedupeKey::Id).is_empty());
+    }
+
+    #[test]
+    fn combine_playlists() {
+        let a = vec![make_track(1, 30), make_track(2, 10), make_track(3, 20)];
+        let b = vec![make_track(3, 5), make_track(4, 15)];
+        let names = |tracks: Vec<Track>| {
+            tracks
+                .iter()
+                .map(|t| t.name.clone())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let playlists = [a, b];
+
+        assert_eq!(
+            names(combine(SetOperation::Union, &playlists, true, false)),
+            "track 1,track 2,track 3,track 3,track 4"
+        );
+        assert_eq!(
+            names(combine(SetOperation::Union, &playlists, false, true)),
+            "track 3,track 2,track 4,track 1"
+        );
+        assert_eq!(
+            names(combine(SetOperation::Intersect, &playlists, true, true)),
+            "track 3"
+        );
+        assert_eq!(
+            names(combine(SetOperation::Difference, &playlists, false, false)),
+            "track 2,track 1"
+        );
+    }
+
+    #[test]
+    fn sorted_and_matching_orders() {
+        let items = vec![
+            Some(make_track(2, 0)),
+            None,
+            Some(make_track(3, 0)),
+            Some(make_track(1, 0)),
+            Some(make_track(2, 0)),
+        ];
+        assert_eq!(
+            sorted_order(&items, TrackOrder::TrackName, false),
+            vec![3, 0, 4, 2, 1]
+        );
+        assert_eq!(
+            sorted_order(&items, TrackOrder::TrackName, true),
+            vec![2, 0, 4, 3, 1]
+        );
+
+        let tracks = [2, 1, 3, 2].map(|n| make_track(n, 0));
+        assert_eq!(matching_order(&items, &tracks), Some(vec![0, 3, 2, 4, 1]));
+        assert_eq!(matching_order(&items, &tracks[..3]), None);
+    }
+
+    #[test]
+    fn reorder_moves_are_minimal() {
+        const N: usize = 5;
+        // all permutations of `0..N`
+        let orders = (0..N.pow(N as u32))
+            .map(|code| {
+                (0..N)
+                    .map(|i| code / N.pow(i as u32) % N)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|order| order.iter().collect::<HashSet<_>>().len() == N);
+
+        for order in orders {
+            let moves = reorder_moves(&order);
+            let fixed = longest_increasing_subsequence(&order);
+            assert_eq!(moves.len(), fixed.iter().filter(|&&f| !f).count());
+
+            let mut items = (0..N).collect::<Vec<_>>();
+            for m in moves {
+                let item = items.remove(m.range_start);
+                items.insert(m.insert_index, item);
+            }
+            assert_eq!(items, order);
+        }
+    }
+
+    #[test]
+    fn shift_positions_after_removals() {
+        assert_eq!(shift_positions(&[2, 5, 9], &[0, 4, 7]), vec![1, 3, 6]);
+    }
+}
@@ -0,0 +1,490 @@
+//! Scrobbling of the played tracks to `ListenBrainz` and Last.fm
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{config, state::HistoryRecord};
+
+const QUEUE_FILE_NAME: &str = "scrobble_queue.json";
+
+/// Minimum duration of a track to be scrobbled
+const MIN_TRACK_DURATION_MS: i64 = 30_000;
+/// Listened duration after which a track counts as a listen, even if it's longer than twice this duration
+const MAX_REQUIRED_LISTEN_MS: u64 = 240_000;
+/// Maximum number of listens submitted in one request
+const MAX_BATCH_SIZE: usize = 50;
+
+/// Last.fm error codes of temporary failures: service offline, temporarily unavailable and rate limit exceeded
+const LASTFM_TEMPORARY_ERRORS: [u64; 3] = [11, 16, 29];
+
+/// Queued listens are only accessed by one task at a time
+static QUEUE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// A scrobbling service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Service {
+    ListenBrainz,
+    LastFm,
+}
+
+/// A listened track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Listen {
+    pub artists: Vec<String>,
+    pub track: String,
+    pub album: Option<String>,
+    pub duration_ms: i64,
+    pub uri: String,
+    /// when the track started playing
+    pub started_at: DateTime<Utc>,
+}
+
+/// A listen whose submission failed, retried later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QueuedListen {
+    service: Service,
+    listen: Listen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Submission {
+    NowPlaying,
+    Listens,
+}
+
+#[derive(Debug)]
+enum SubmitError {
+    /// the service is unreachable or temporarily unavailable, the submission can be retried later
+    Unavailable(anyhow::Error),
+    /// the service rejected the submission, e.g. because of invalid credentials
+    Rejected(String),
+}
+
+impl From<reqwest::Error> for SubmitError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Unavailable(err.into())
+    }
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable(err) => write!(f, "service unavailable: {err:#}"),
+            Self::Rejected(msg) => write!(f, "submission rejected: {msg}"),
+        }
+    }
+}
+
+impl Listen {
+    /// Get the listen of a record, `None` if the record's item isn't a track
+    pub fn from_record(record: &HistoryRecord) -> Option<Self> {
+        let track = record.track.as_ref()?;
+        Some(Self {
+            artists: record.artists.clone(),
+            track: record.name.clone(),
+            album: track.album.as_ref().map(|a| a.name.clone()),
+            duration_ms: record.duration_ms,
+            uri: record.uri.clone(),
+            started_at: record.started_at,
+        })
+    }
+}
+
+/// Whether a finished record counts as a listen: a track longer than 30 seconds,
+/// played for half of its duration or for four minutes
+pub fn is_listen(record: &HistoryRecord) -> bool {
+    let required_ms = u64::try_from(record.duration_ms / 2)
+        .unwrap_or_default()
+        .min(MAX_REQUIRED_LISTEN_MS);
+    record.duration_ms > MIN_TRACK_DURATION_MS && record.listened_ms >= required_ms
+}
+
+/// Get the services whose credentials are configured
+pub fn enabled_services() -> Vec<Service> {
+    let configs = &config::get_config().app_config.scrobble;
+    let mut services = vec![];
+    if configs.listenbrainz_token.is_some() {
+        services.push(Service::ListenBrainz);
+    }
+    if configs.lastfm_api_key.is_some()
+        && configs.lastfm_api_secret.is_some()
+        && configs.lastfm_session_key.is_some()
+    {
+        services.push(Service::LastFm);
+    }
+    services
+}
+
+/// Whether scrobbling is enabled, i.e. some service has its credentials configured
+pub fn is_enabled() -> bool {
+    !enabled_services().is_empty()
+}
+
+/// Report a track as playing to the enabled services
+pub async fn now_playing(http: &reqwest::Client, listen: &Listen) {
+    for service in enabled_services() {
+        if let Err(err) = submit(http, service, Submission::NowPlaying, &[listen]).await {
+            tracing::warn!("Failed to report the playing track to {service:?}: {err}");
+        }
+    }
+}
+
+/// Scrobble a listen to the enabled services, queueing it to be retried later
+/// if a service is unavailable
+pub async fn scrobble(http: &reqwest::Client, listen: Listen) -> anyhow::Result<()> {
+    let mut failed = vec![];
+    for service in enabled_services() {
+        match submit(http, service, Submission::Listens, &[&listen]).await {
+            Ok(()) => tracing::info!("Scrobbled {} to {service:?}", listen.uri),
+            Err(SubmitError::Unavailable(err)) => {
+                tracing::warn!("Failed to scrobble to {service:?}, queueing the listen: {err:#}");
+                failed.push(QueuedListen {
+                    service,
+                    listen: listen.clone(),
+                });
+            }
+            Err(err) => tracing::error!("Failed to scrobble to {service:?}: {err}"),
+        }
+    }
+
+    if !failed.is_empty() {
+        let cache_folder = &config::get_config().cache_folder;
+        let _lock = QUEUE_LOCK.lock().expect("scrobble queue lock");
+        let mut queue = load_queue(cache_folder)?;
+        queue.extend(failed);
+        save_queue(cache_folder, &queue)?;
+    }
+    Ok(())
+}
+
+/// Submit the queued listens, removing the ones which are accepted or rejected from the queue
+pub async fn submit_queued_listens(http: &reqwest::Client) -> anyhow::Result<()> {
+    let cache_folder = &config::get_config().cache_folder;
+    let queue = {
+        let _lock = QUEUE_LOCK.lock().expect("scrobble queue lock");
+        load_queue(cache_folder)?
+    };
+    if queue.is_empty() {
+        return Ok(());
+    }
+
+    let mut done = vec![];
+    for service in enabled_services() {
+        let entries = queue
+            .iter()
+            .filter(|e| e.service == service)
+            .collect::<Vec<_>>();
+        for batch in entries.chunks(MAX_BATCH_SIZE) {
+            let listens = batch.iter().map(|e| &e.listen).collect::<Vec<_>>();
+            match submit(http, service, Submission::Listens, &listens).await {
+                Ok(()) => {
+                    tracing::info!("Scrobbled {} queued listens to {service:?}", listens.len());
+                }
+                Err(SubmitError::Unavailable(err)) => {
+                    tracing::warn!("Failed to scrobble the queued listens to {service:?}: {err:#}");
+                    break;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        "Failed to scrobble the queued listens to {service:?}, dropping them: {err}"
+                    );
+                }
+            }
+            done.extend(batch.iter().copied().cloned());
+        }
+    }
+
+    // listens may have been queued during the submission
+    let _lock = QUEUE_LOCK.lock().expect("scrobble queue lock");
+    let mut queue = load_queue(cache_folder)?;
+    queue.retain(|e| !done.contains(e));
+    save_queue(cache_folder, &queue)
+}
+
+fn load_queue(cache_folder: &Path) -> anyhow::Result<Vec<QueuedListen>> {
+    let path = cache_folder.join(QUEUE_FILE_NAME);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let data = std::fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+fn save_queue(cache_folder: &Path, queue: &[QueuedListen]) -> anyhow::Result<()> {
+    let path = cache_folder.join(QUEUE_FILE_NAME);
+    if queue.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    std::fs::write(&path, serde_json::to_vec(queue)?)
+        .with_context(|| format!("write {}", path.display()))
+}
+
+async fn submit(
+    http: &reqwest::Client,
+    service: Service,
+    submission: Submission,
+    listens: &[&Listen],
+) -> Result<(), SubmitError> {
+    match service {
+        Service::ListenBrainz => submit_listenbrainz(http, submission, listens).await,
+        Service::LastFm => submit_lastfm(http, submission, listens).await,
+    }
+}
+
+async fn submit_listenbrainz(
+    http: &reqwest::Client,
+    submission: Submission,
+    listens: &[&Listen],
+) -> Result<(), SubmitError> {
+    let configs = &config::get_config().app_config.scrobble;
+    let token = configs.listenbrainz_token.as_deref().unwrap_or_default();
+
+    let response = http
+        .post(format!(
+            "{}/1/submit-listens",
+            configs.listenbrainz_api_url.trim_end_matches('/')
+        ))
+        .header(reqwest::header::AUTHORIZATION, format!("Token {token}"))
+        .json(&listenbrainz_payload(submission, listens))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        Err(SubmitError::Unavailable(anyhow::anyhow!(
+            "{status}: {body}"
+        )))
+    } else {
+        Err(SubmitError::Rejected(format!("{status}: {body}")))
+    }
+}
+
+fn listenbrainz_payload(submission: Submission, listens: &[&Listen]) -> serde_json::Value {
+    let payload = listens
+        .iter()
+        .map(|listen| {
+            let mut item = serde_json::json!({
+                "track_metadata": {
+                    "artist_name": listen.artists.join(", "),
+                    "track_name": listen.track,
+                    "release_name": listen.album,
+                    "additional_info": {
+                        "duration_ms": listen.duration_ms,
+                        "spotify_id": listen
+                            .uri
+                            .replacen("spotify:track:", "https://open.spotify.com/track/", 1),
+                        "media_player": "spotify_player",
+                        "submission_client": "spotify_player",
+                        "submission_client_version": env!("CARGO_PKG_VERSION"),
+                        "music_service": "spotify.com",
+                    },
+                },
+            });
+            if submission == Submission::Listens {
+                item["listened_at"] = listen.started_at.timestamp().into();
+            }
+            item
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "listen_type": match submission {
+            Submission::NowPlaying => "playing_now",
+            Submission::Listens if listens.len() == 1 => "single",
+            Submission::Listens => "import",
+        },
+        "payload": payload,
+    })
+}
+
+async fn submit_lastfm(
+    http: &reqwest::Client,
+    submission: Submission,
+    listens: &[&Listen],
+) -> Result<(), SubmitError> {
+    let configs = &config::get_config().app_config.scrobble;
+    let params = lastfm_params(
+        submission,
+        listens,
+        configs.lastfm_api_key.as_deref().unwrap_or_default(),
+        configs.lastfm_session_key.as_deref().unwrap_or_default(),
+        configs.lastfm_api_secret.as_deref().unwrap_or_default(),
+    );
+
+    let response = http
+        .post(&configs.lastfm_api_url)
+        .form(&params)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let error = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v["error"].as_u64());
+    match error {
+        None if status.is_success() => Ok(()),
+        Some(code) if LASTFM_TEMPORARY_ERRORS.contains(&code) => Err(SubmitError::Unavailable(
+            anyhow::anyhow!("{status}: {body}"),
+        )),
+        None if status.is_server_error() => Err(SubmitError::Unavailable(anyhow::anyhow!(
+            "{status}: {body}"
+        ))),
+        _ => Err(SubmitError::Rejected(format!("{status}: {body}"))),
+    }
+}
+
+/// Get the parameters of a signed Last.fm API request
+fn lastfm_params(
+    submission: Submission,
+    listens: &[&Listen],
+    api_key: &str,
+    session_key: &str,
+    api_secret: &str,
+) -> BTreeMap<String, String> {
+    let mut params = BTreeMap::new();
+    params.insert("api_key".to_string(), api_key.to_string());
+    params.insert("sk".to_string(), session_key.to_string());
+
+    match submission {
+        Submission::NowPlaying => {
+            params.insert("method".to_string(), "track.updateNowPlaying".to_string());
+            if let Some(listen) = listens.first() {
+                for (key, value) in lastfm_track_params(listen) {
+                    params.insert(key.to_string(), value);
+                }
+            }
+        }
+        Submission::Listens => {
+            params.insert("method".to_string(), "track.scrobble".to_string());
+            for (i, listen) in listens.iter().enumerate() {
+                for (key, value) in lastfm_track_params(listen) {
+                    params.insert(format!("{key}[{i}]"), value);
+                }
+                params.insert(
+                    format!("timestamp[{i}]"),
+                    listen.started_at.timestamp().to_string(),
+                );
+            }
+        }
+    }
+
+    // the signature is the MD5 hash of the parameters sorted by name and concatenated, followed by the secret
+    let mut signed = String::new();
+    for (key, value) in &params {
+        signed.push_str(key);
+        signed.push_str(value);
+    }
+    signed.push_str(api_secret);
+    params.insert(
+        "api_sig".to_string(),
+        format!("{:x}", md5::compute(signed.as_bytes())),
+    );
+    params.insert("format".to_string(), "json".to_string());
+    params
+}
+
+fn lastfm_track_params(listen: &Listen) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        (
+            "artist",
+            listen.artists.first().cloned().unwrap_or_default(),
+        ),
+        ("track", listen.track.clone()),
+        ("duration", (listen.duration_ms / 1000).to_string()),
+    ];
+    if let Some(album) = &listen.album {
+        params.push(("album", album.clone()));
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listen() -> Listen {
+        Listen {
+            artists: vec!["Daft Punk".to_string(), "Pharrell Williams".to_string()],
+            track: "Get Lucky".to_string(),
+            album: Some("Random Access Memories".to_string()),
+            duration_ms: 369_000,
+            uri: "spotify:track:69kOkLUCkxIZYexIgSG8rq".to_string(),
+            started_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn listen_rules() {
+        let record = |duration_ms: i64, listened_ms: u64| HistoryRecord {
+            uri: "spotify:track:69kOkLUCkxIZYexIgSG8rq".to_string(),
+            name: "Get Lucky".to_string(),
+            artists: vec![],
+            duration_ms,
+            context: None,
+            device: None,
+            started_at: Utc::now(),
+            listened_ms,
+            skipped: false,
+            track: None,
+            genres: vec![],
+        };
+        assert!(is_listen(&record(200_000, 100_000)));
+        assert!(!is_listen(&record(200_000, 99_000)));
+        // four minutes are enough for long tracks
+        assert!(is_listen(&record(600_000, 240_000)));
+        // tracks of 30 seconds or less are never scrobbled
+        assert!(!is_listen(&record(30_000, 30_000)));
+    }
+
+    #[test]
+    fn listenbrainz_payloads() {
+        let listen = listen();
+        let payload = listenbrainz_payload(Submission::Listens, &[&listen]);
+        assert_eq!(payload["listen_type"], "single");
+        assert_eq!(payload["payload"][0]["listened_at"], 1_700_000_000);
+        assert_eq!(
+            payload["payload"][0]["track_metadata"]["artist_name"],
+            "Daft Punk, Pharrell Williams"
+        );
+        assert_eq!(
+            payload["payload"][0]["track_metadata"]["additional_info"]["spotify_id"],
+            "https://open.spotify.com/track/69kOkLUCkxIZYexIgSG8rq"
+        );
+
+        let payload = listenbrainz_payload(Submission::NowPlaying, &[&listen]);
+        assert_eq!(payload["listen_type"], "playing_now");
+        assert!(payload["payload"][0].get("listened_at").is_none());
+
+        let payload = listenbrainz_payload(Submission::Listens, &[&listen, &listen]);
+        assert_eq!(payload["listen_type"], "import");
+    }
+
+    #[test]
+    fn lastfm_signed_params() {
+        let listen = listen();
+        let params = lastfm_params(Submission::Listens, &[&listen], "key", "session", "secret");
+        assert_eq!(params["method"], "track.scrobble");
+        assert_eq!(params["artist[0]"], "Daft Punk");
+        assert_eq!(params["timestamp[0]"], "1700000000");
+        assert_eq!(params["duration[0]"], "369");
+        assert_eq!(params["format"], "json");
+
+        let signed = "album[0]Random Access Memoriesapi_keykeyartist[0]Daft Punkduration[0]369\
+methodtrack.scrobblesksessiontimestamp[0]1700000000track[0]Get Luckysecret";
+        assert_eq!(
+            params["api_sig"],
+            format!("{:x}", md5::compute(signed.as_bytes()))
+        );
+    }
+}
@@ -5,10 +5,10 @@ use serde::{de::DeserializeOwned, Serialize};
 use std::sync::LazyLock;
 
 use super::model::{
-    Album, Artist, Category, Context, ContextId, Id, Playlist, PlaylistFolderItem,
+    Album, Artist, Audiobook, Category, Context, ContextId, Playlist, PlaylistFolderItem,
     PlaylistFolderNode, SearchResults, Show, Track,
 };
-use super::Lyrics;
+use super::{ListeningStats, Lyrics};
 
 pub type DataReadGuard<'a> = parking_lot::RwLockReadGuard<'a, AppData>;
 
@@ -18,8 +18,11 @@ pub enum FileCacheKey {
     PlaylistFolders,
     FollowedArtists,
     SavedShows,
+    SavedAudiobooks,
     SavedAlbums,
     SavedTracks,
+    #[cfg(feature = "streaming")]
+    CustomQueue,
 }
 
 /// default time-to-live cache duration
@@ -31,6 +34,8 @@ pub struct AppData {
     pub user_data: UserData,
     pub caches: MemoryCaches,
     pub browse: BrowseData,
+    /// listening statistics of the stats page, computed from the listening history
+    pub stats: Option<ListeningStats>,
 }
 
 #[derive(Debug)]
@@ -41,6 +46,7 @@ pub struct UserData {
     pub playlist_folder_node: Option<PlaylistFolderNode>,
     pub followed_artists: Vec<Artist>,
     pub saved_shows: Vec<Show>,
+    pub saved_audiobooks: Vec<Audiobook>,
     pub saved_albums: Vec<Album>,
     pub saved_tracks: HashMap<String, Track>,
 }
@@ -51,6 +57,8 @@ pub struct MemoryCaches {
     pub search: ttl_cache::TtlCache<String, SearchResults>,
     pub lyrics: ttl_cache::TtlCache<String, Option<Lyrics>>,
     pub genres: ttl_cache::TtlCache<String, Vec<String>>,
+    /// the user's top artists, keyed by their time range
+    pub top_artists: ttl_cache::TtlCache<String, Vec<Artist>>,
     #[cfg(feature = "image")]
     pub images: ttl_cache::TtlCache<String, image::DynamicImage>,
 }
@@ -69,6 +77,7 @@ impl MemoryCaches {
             search: ttl_cache::TtlCache::new(64),
             lyrics: ttl_cache::TtlCache::new(64),
             genres: ttl_cache::TtlCache::new(64),
+            top_artists: ttl_cache::TtlCache::new(3),
             #[cfg(feature = "image")]
             images: ttl_cache::TtlCache::new(64),
         }
@@ -81,6 +90,7 @@ impl AppData {
             user_data: UserData::new_from_file_caches(cache_folder),
             caches: MemoryCaches::new(),
             browse: BrowseData::default(),
+            stats: None,
         }
     }
 
@@ -95,7 +105,7 @@ impl AppData {
             | Context::Artist {
                 top_tracks: tracks, ..
             } => tracks,
-            Context::Show { .. } => {
+            Context::Show { .. } | Context::Audiobook { .. } => {
                 return None;
             }
         })
@@ -110,7 +120,7 @@ impl AppData {
             | Context::Artist {
                 top_tracks: tracks, ..
             } => tracks,
-            Context::Show { .. } => {
+            Context::Show { .. } | Context::Audiobook { .. } => {
                 return None;
             }
         })
@@ -135,6 +145,11 @@ impl UserData {
             .unwrap_or_default(),
             saved_shows: load_data_from_file_cache(FileCacheKey::SavedShows, cache_folder)
                 .unwrap_or_default(),
+            saved_audiobooks: load_data_from_file_cache(
+                FileCacheKey::SavedAudiobooks,
+                cache_folder,
+            )
+            .unwrap_or_default(),
             saved_albums: load_data_from_file_cache(FileCacheKey::SavedAlbums, cache_folder)
                 .unwrap_or_default(),
             saved_tracks: load_data_from_file_cache(FileCacheKey::SavedTracks, cache_folder)
@@ -185,7 +200,7 @@ impl UserData {
 
     /// Check if a track is a liked track
     pub fn is_liked_track(&self, track: &Track) -> bool {
-        self.saved_tracks.contains_key(&track.id.uri())
+        self.saved_tracks.contains_key(&track.uri())
     }
 
     /// Get the user's liked tracks by the given artist, sorted by liked date (newest first)
@@ -220,6 +235,15 @@ pub fn store_data_into_file_cache<T: Serialize>(
     Ok(())
 }
 
+#[cfg(feature = "streaming")]
+pub fn remove_data_from_file_cache(key: FileCacheKey, cache_folder: &Path) -> std::io::Result<()> {
+    let path = cache_folder.join(format!("{key:?}_cache.json"));
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
 pub fn load_data_from_file_cache<T>(key: FileCacheKey, cache_folder: &Path) -> Option<T>
 where
     T: DeserializeOwned,
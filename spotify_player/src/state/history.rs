@@ -0,0 +1,353 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use rspotify::model::PlayableItem;
+
+use super::{PlayerState, PlayingItem, State, Track};
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// Maximum remaining time of an item replaced by another one for the item not to be considered skipped
+const SKIP_THRESHOLD_MS: i64 = 10_000;
+/// Minimum listened duration of an item to be recorded
+const MIN_RECORDED_DURATION: Duration = Duration::from_secs(1);
+/// Maximum difference between an item's progress change and the time elapsed since the previous
+/// known progress for the change to be counted as listened, larger changes being seeks
+const SEEK_TOLERANCE_MS: i64 = 2000;
+
+/// A record of the listening history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// URI of the played track or episode
+    pub uri: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub duration_ms: i64,
+    /// URI of the context the item was played from
+    pub context: Option<String>,
+    /// name of the device the item was played on
+    pub device: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// time spent playing the item, excluding pauses and seeks
+    pub listened_ms: u64,
+    /// whether the item was replaced by another one before its end
+    pub skipped: bool,
+    /// the played track, used to navigate to the track's artists and album
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<Track>,
+    /// genres of the item's first artist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+}
+
+/// Records the items played by any device into the listening history
+#[derive(Debug, Default)]
+pub struct HistoryRecorder {
+    current: Option<ListeningSession>,
+}
+
+/// Changes of the listened item found by an observation of the player
+#[derive(Debug, Default)]
+pub struct HistoryUpdate {
+    /// record of the item which finished playing
+    pub finished: Option<HistoryRecord>,
+    /// record of the item which started playing, whose listening is in progress
+    pub started: Option<HistoryRecord>,
+}
+
+/// The listening of the currently playing item
+#[derive(Debug)]
+struct ListeningSession {
+    record: HistoryRecord,
+    is_playing: bool,
+    /// the listened time, counted from the item's progress
+    listened: Duration,
+    /// the last known progress of the item
+    progress_ms: Option<i64>,
+    /// when `progress_ms` was last updated
+    progress_updated: Instant,
+    /// whether the item is known to have played until its end
+    ended: bool,
+    /// whether the item has been reported as started playing
+    announced: bool,
+}
+
+impl HistoryRecorder {
+    /// Update the current listening session from the player's state
+    pub fn observe(&mut self, player: &PlayerState) -> HistoryUpdate {
+        let item = player
+            .currently_playing()
+            .and_then(PlayingItem::from_playable);
+        let playback = player.buffered_playback.as_ref();
+        let is_playing = playback.is_some_and(|p| p.is_playing);
+
+        if let (Some(session), Some(item)) = (self.current.as_mut(), item.as_ref()) {
+            if session.record.uri == item.uri && !session.ended {
+                session.is_playing = is_playing;
+                session.update_progress(player.playback_progress().map(|p| p.num_milliseconds()));
+                return HistoryUpdate {
+                    finished: None,
+                    started: session.announce(),
+                };
+            }
+        }
+
+        let finished = self.finish();
+        self.current = item.map(|item| ListeningSession {
+            record: HistoryRecord {
+                uri: item.uri,
+                name: item.name,
+                artists: item.artists,
+                duration_ms: item.duration_ms,
+                context: player.playing_context_id().map(|id| id.uri()),
+                device: playback.map(|p| p.device_name.clone()),
+                started_at: Utc::now(),
+                listened_ms: 0,
+                skipped: false,
+                track: match player.currently_playing() {
+                    Some(PlayableItem::Track(track)) => Track::try_from_full_track(track.clone()),
+                    _ => None,
+                },
+                genres: vec![],
+            },
+            is_playing,
+            listened: Duration::ZERO,
+            progress_ms: player.playback_progress().map(|p| p.num_milliseconds()),
+            progress_updated: Instant::now(),
+            ended: false,
+            announced: false,
+        });
+        HistoryUpdate {
+            finished,
+            started: self.current.as_mut().and_then(ListeningSession::announce),
+        }
+    }
+
+    /// Update whether the item with the given URI is playing, e.g. on an integrated player's event
+    #[cfg(feature = "streaming")]
+    pub fn set_playing(&mut self, uri: &str, is_playing: bool) {
+        if let Some(session) = self.current.as_mut().filter(|s| s.record.uri == uri) {
+            session.is_playing = is_playing;
+        }
+    }
+
+    /// Mark the item with the given URI as played until its end.
+    ///
+    /// The item's listening is finished by the next observation, which starts
+    /// a new listening even if the same item is played again.
+    #[cfg(feature = "streaming")]
+    pub fn end_of_track(&mut self, uri: &str) {
+        if let Some(session) = self.current.as_mut().filter(|s| s.record.uri == uri) {
+            session.update_progress(Some(session.record.duration_ms));
+            session.is_playing = false;
+            session.ended = true;
+        }
+    }
+
+    /// Finish the current listening session, returning its record if any
+    pub fn finish(&mut self) -> Option<HistoryRecord> {
+        self.current.take().and_then(ListeningSession::finish)
+    }
+}
+
+impl ListeningSession {
+    /// Update the item's progress, counting its change since the previous progress as listened
+    /// unless the change is caused by a seek
+    fn update_progress(&mut self, progress_ms: Option<i64>) {
+        let Some(progress_ms) = progress_ms.map(|p| p.min(self.record.duration_ms)) else {
+            return;
+        };
+        if let Some(prev) = self.progress_ms {
+            let delta = progress_ms - prev;
+            let elapsed_ms =
+                i64::try_from(self.progress_updated.elapsed().as_millis()).unwrap_or(i64::MAX);
+            if delta > 0 && delta <= elapsed_ms.saturating_add(SEEK_TOLERANCE_MS) {
+                self.listened += Duration::from_millis(delta.unsigned_abs());
+            }
+        }
+        self.progress_ms = Some(progress_ms);
+        self.progress_updated = Instant::now();
+    }
+
+    /// Get the record of the item the first time it's playing
+    fn announce(&mut self) -> Option<HistoryRecord> {
+        if self.announced || !self.is_playing {
+            return None;
+        }
+        self.announced = true;
+        Some(self.record.clone())
+    }
+
+    /// Get the record of the session, `None` if the item was barely listened to
+    fn finish(mut self) -> Option<HistoryRecord> {
+        if self.listened < MIN_RECORDED_DURATION {
+            return None;
+        }
+
+        let near_end = self
+            .progress_ms
+            .is_some_and(|p| p + SKIP_THRESHOLD_MS >= self.record.duration_ms);
+        self.record.listened_ms = u64::try_from(self.listened.as_millis()).unwrap_or(u64::MAX);
+        self.record.skipped = !self.ended && !near_end;
+        Some(self.record)
+    }
+}
+
+impl State {
+    /// Save a finished record into the history file in the cache folder,
+    /// completing it with the cached genres of its first artist
+    pub fn save_history_record(&self, mut record: HistoryRecord) -> anyhow::Result<()> {
+        if let Some(artist) = record.artists.first() {
+            if let Some(genres) = self.data.read().caches.genres.get(artist) {
+                record.genres.clone_from(genres);
+            }
+        }
+        append_history_record(&crate::config::get_config().cache_folder, &record)
+    }
+
+    /// Save the record of the item being listened to, e.g. when the application exits
+    pub fn save_current_history_record(&self) -> anyhow::Result<()> {
+        if !crate::config::get_config()
+            .app_config
+            .enable_listening_history
+        {
+            return Ok(());
+        }
+        let record = self.history.lock().finish();
+        match record {
+            Some(record) => self.save_history_record(record),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Append a record to the history file in the cache folder
+fn append_history_record(cache_folder: &Path, record: &HistoryRecord) -> anyhow::Result<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(cache_folder.join(HISTORY_FILE_NAME))?;
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    f.write_all(&line)?;
+    Ok(())
+}
+
+/// Load the records of the history file in the cache folder, sorted by start time
+pub fn load_history(cache_folder: &Path) -> anyhow::Result<Vec<HistoryRecord>> {
+    let path = cache_folder.join(HISTORY_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(std::fs::File::open(path)?)
+        .lines()
+        .enumerate()
+    {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<HistoryRecord>(&line) {
+            Ok(record) => records.push(record),
+            // a line can be truncated if the application was killed while writing it
+            Err(err) => tracing::warn!("Skipped invalid history record at line {}: {err:#}", i + 1),
+        }
+    }
+    records.sort_by_key(|r| r.started_at);
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(listened_secs: u64, progress_ms: Option<i64>, ended: bool) -> ListeningSession {
+        ListeningSession {
+            record: HistoryRecord {
+                uri: "spotify:track:4uLU6hMCjMI75M1A2tKUQC".to_string(),
+                name: "Never Gonna Give You Up".to_string(),
+                artists: vec!["Rick Astley".to_string()],
+                duration_ms: 213_000,
+                context: None,
+                device: None,
+                started_at: Utc::now(),
+                listened_ms: 0,
+                skipped: false,
+                track: None,
+                genres: vec![],
+            },
+            is_playing: false,
+            listened: Duration::from_secs(listened_secs),
+            progress_ms,
+            progress_updated: Instant::now(),
+            ended,
+            announced: false,
+        }
+    }
+
+    #[test]
+    fn finish_listening_sessions() {
+        let record = session(30, Some(30_000), false).finish().unwrap();
+        assert_eq!(record.listened_ms, 30_000);
+        assert!(record.skipped);
+
+        // an item replaced close to its end or known to have ended isn't skipped
+        assert!(!session(200, Some(205_000), false).finish().unwrap().skipped);
+        assert!(!session(200, None, true).finish().unwrap().skipped);
+
+        assert!(session(0, Some(500), false).finish().is_none());
+    }
+
+    #[test]
+    fn count_listened_time_from_progress() {
+        let mut session = session(0, Some(10_000), false);
+        session.progress_updated = Instant::now().checked_sub(Duration::from_secs(5)).unwrap();
+        session.update_progress(Some(15_000));
+        assert_eq!(session.listened, Duration::from_secs(5));
+
+        // seeks aren't listened time
+        session.update_progress(Some(100_000));
+        session.update_progress(Some(20_000));
+        assert_eq!(session.listened, Duration::from_secs(5));
+
+        // the progress of an item is never past its end
+        session.progress_updated = Instant::now()
+            .checked_sub(Duration::from_secs(200))
+            .unwrap();
+        session.update_progress(Some(230_000));
+        assert_eq!(session.listened, Duration::from_secs(5 + 193));
+    }
+
+    #[test]
+    #[cfg(feature = "streaming")]
+    fn end_of_track_finishes_the_matching_session() {
+        let mut recorder = HistoryRecorder {
+            current: Some(session(60, Some(60_000), false)),
+        };
+        recorder.end_of_track("spotify:track:other");
+        assert!(!recorder.current.as_ref().unwrap().ended);
+
+        recorder.end_of_track("spotify:track:4uLU6hMCjMI75M1A2tKUQC");
+        let update = recorder.observe(&PlayerState::default());
+        assert!(!update.finished.unwrap().skipped);
+        assert!(update.started.is_none());
+        assert!(recorder.current.is_none());
+    }
+
+    #[test]
+    fn announce_playing_item_once() {
+        let mut session = session(0, None, false);
+        assert!(session.announce().is_none());
+        session.is_playing = true;
+        assert!(session.announce().is_some());
+        assert!(session.announce().is_none());
+    }
+}
@@ -1,18 +1,26 @@
 mod constant;
 mod data;
+mod history;
 mod model;
 mod player;
 mod queue;
+mod sleep_timer;
+mod stats;
+mod subscription;
 mod ui;
 
 use std::{collections::VecDeque, sync::Arc};
 
 pub use constant::*;
 pub use data::*;
+pub use history::*;
 pub use model::*;
 pub use player::*;
 #[allow(unused_imports)]
 pub use queue::*;
+pub use sleep_timer::*;
+pub use stats::*;
+pub use subscription::*;
 pub use ui::*;
 
 use crate::config;
@@ -27,9 +35,13 @@ pub struct State {
     pub ui: Mutex<UIState>,
     pub player: RwLock<PlayerState>,
     pub data: RwLock<AppData>,
+    pub history: Mutex<HistoryRecorder>,
 
     pub is_daemon: bool,
 
+    /// Subscribers to the application's state events
+    pub subscribers: Subscribers,
+
     /// Shared FFT frequency-band data written by the audio sink and read by the UI.
     /// `Some` only when `enable_audio_visualization` is `true`; avoids allocating
     /// the mutex/state entirely when the feature is not in use.
@@ -55,7 +67,9 @@ impl State {
             ui: Mutex::new(ui),
             player: RwLock::new(PlayerState::default()),
             data: RwLock::new(app_data),
+            history: Mutex::new(HistoryRecorder::default()),
             is_daemon,
+            subscribers: Subscribers::default(),
             #[cfg(feature = "streaming")]
             vis_bands: if configs.app_config.enable_audio_visualization {
                 Some(Arc::new(Mutex::new(
@@ -77,12 +91,24 @@ impl State {
                 && self.is_daemon)
     }
 
+    /// Update the player's state, publishing events describing the changes to subscribers
+    pub fn update_player<T>(&self, f: impl FnOnce(&mut PlayerState) -> T) -> T {
+        let mut player = self.player.write();
+        if self.subscribers.is_empty() {
+            return f(&mut player);
+        }
+
+        let prev = player.snapshot();
+        let result = f(&mut player);
+        self.subscribers.publish(&prev.changes(&player.snapshot()));
+        result
+    }
+
     /// Returns `true` when the custom queue system should be used for new playback.
     ///
     /// Requires streaming to be enabled and the `custom_queue` config option
     /// to be `true`.
     #[cfg(feature = "streaming")]
-    #[allow(dead_code)]
     pub fn should_use_custom_queue(&self) -> bool {
         self.is_streaming_enabled() && config::get_config().app_config.custom_queue
     }
@@ -1,3 +1,4 @@
+use super::USER_TOP_TRACKS_URI;
 use crate::config;
 use crate::ui::utils::to_bidi_string;
 use crate::utils::map_join;
@@ -43,15 +44,19 @@ pub enum Context {
         show: Show,
         episodes: Vec<Episode>,
     },
+    Audiobook {
+        audiobook: Audiobook,
+        chapters: Vec<Chapter>,
+    },
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TracksId {
     pub uri: String,
     pub kind: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 /// A context Id
 pub enum ContextId {
     Playlist(PlaylistId<'static>),
@@ -59,6 +64,7 @@ pub enum ContextId {
     Artist(ArtistId<'static>),
     Tracks(TracksId),
     Show(ShowId<'static>),
+    Audiobook(AudiobookId),
 }
 
 /// Data used to start a new playback.
@@ -82,18 +88,36 @@ pub struct SearchResults {
     pub playlists: Vec<Playlist>,
     pub shows: Vec<Show>,
     pub episodes: Vec<Episode>,
+    #[serde(default)]
+    pub audiobooks: Vec<Audiobook>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
 /// A track order
 pub enum TrackOrder {
     AddedAt,
+    #[value(name = "title")]
     TrackName,
     Album,
     Artists,
     Duration,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+/// Time range of the user's top items
+pub enum TimeRange {
+    /// approximately the last 4 weeks
+    #[value(name = "short-term")]
+    Short,
+    /// approximately the last 6 months
+    #[default]
+    #[value(name = "medium-term")]
+    Medium,
+    /// approximately the last year
+    #[value(name = "long-term")]
+    Long,
+}
+
 #[derive(Debug, Clone)]
 /// A Spotify item (track, album, artist, playlist)
 pub enum Item {
@@ -134,14 +158,41 @@ pub struct Device {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 /// A Spotify track
 pub struct Track {
-    pub id: TrackId<'static>,
+    /// the track's ID, `None` for an episode or a local file
+    pub id: Option<TrackId<'static>>,
     pub name: String,
     pub artists: Vec<Artist>,
     pub album: Option<Album>,
     pub duration: std::time::Duration,
     pub explicit: bool,
-    #[serde(skip)]
+    #[serde(default)]
     pub added_at: u64,
+    /// International Standard Recording Code, only available for tracks converted from a `FullTrack`
+    #[serde(default)]
+    pub isrc: Option<String>,
+    #[serde(default)]
+    pub kind: TrackKind,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+/// The kind of a playlist entry stored as a `Track`.
+///
+/// Playlists can contain episodes and local files besides tracks,
+/// which are kept as `Track`s so that an entry's index is its position in the playlist.
+pub enum TrackKind {
+    #[default]
+    Track,
+    /// a podcast episode
+    Episode {
+        id: EpisodeId<'static>,
+        show: String,
+    },
+    /// a local file, which has no Spotify ID
+    Local {
+        uri: String,
+        artists: String,
+        album: String,
+    },
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -181,6 +232,11 @@ pub struct Playlist {
 pub struct Show {
     pub id: ShowId<'static>,
     pub name: String,
+    #[serde(default)]
+    pub added_at: u64,
+    /// number of unplayed episodes among the show's latest episodes, `None` if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unplayed_episodes: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -194,6 +250,35 @@ pub struct Episode {
     pub release_date: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+/// A Spotify audiobook ID, `rspotify` doesn't support audiobooks yet
+pub struct AudiobookId(String);
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+/// A Spotify audiobook
+pub struct Audiobook {
+    pub id: AudiobookId,
+    pub name: String,
+    pub authors: Vec<String>,
+    pub narrators: Vec<String>,
+    pub publisher: String,
+    pub total_chapters: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+/// A Spotify audiobook chapter
+pub struct Chapter {
+    /// chapters are played as episodes, so they are identified by episode IDs
+    pub id: EpisodeId<'static>,
+    pub name: String,
+    pub number: usize,
+    pub duration: std::time::Duration,
+    /// position to resume the chapter from, `None` if the chapter hasn't been started
+    pub resume_position: Option<std::time::Duration>,
+    pub fully_played: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 /// A playlist folder, not related to Spotify API yet
 pub struct PlaylistFolder {
@@ -263,6 +348,15 @@ impl Context {
                 ref show,
                 ref episodes,
             } => format!("{} | {} episodes", show.name, episodes.len()),
+            Context::Audiobook {
+                ref audiobook,
+                ref chapters,
+            } => format!(
+                "{} | {} | {} chapters",
+                audiobook.name,
+                audiobook.authors.join(", "),
+                chapters.len()
+            ),
         }
     }
 }
@@ -300,13 +394,14 @@ impl ContextId {
             Self::Playlist(id) => id.uri(),
             Self::Tracks(id) => id.uri.clone(),
             Self::Show(id) => id.uri(),
+            Self::Audiobook(id) => id.uri(),
         }
     }
 }
 
 impl TrackOrder {
-    pub fn compare(&self, x: &Track, y: &Track) -> std::cmp::Ordering {
-        match *self {
+    pub fn compare(self, x: &Track, y: &Track) -> std::cmp::Ordering {
+        match self {
             Self::AddedAt => x.added_at.cmp(&y.added_at),
             Self::TrackName => x.name.cmp(&y.name),
             Self::Album => x.album_info().cmp(&y.album_info()),
@@ -329,27 +424,63 @@ impl Device {
 impl Track {
     /// gets the track's artists information
     pub fn artists_info(&self) -> String {
-        map_join(&self.artists, |a| &a.name, ", ")
+        match &self.kind {
+            TrackKind::Track => map_join(&self.artists, |a| &a.name, ", "),
+            TrackKind::Episode { show, .. } => show.clone(),
+            TrackKind::Local { artists, .. } => artists.clone(),
+        }
     }
 
     /// gets the track's album information
     pub fn album_info(&self) -> String {
-        self.album
-            .as_ref()
-            .map(|a| a.name.clone())
-            .unwrap_or_default()
+        match &self.kind {
+            TrackKind::Local { album, .. } => album.clone(),
+            _ => self
+                .album
+                .as_ref()
+                .map(|a| a.name.clone())
+                .unwrap_or_default(),
+        }
     }
 
-    /// gets the track's name, including an explicit label
+    /// gets the track's name, including an explicit label and a label for non-track entries
     pub fn display_name(&self) -> Cow<'_, str> {
-        if self.explicit {
-            Cow::Owned(format!(
+        let label = match self.kind {
+            TrackKind::Track => None,
+            TrackKind::Episode { .. } => Some("[episode]"),
+            TrackKind::Local { .. } => Some("[local]"),
+        };
+        match (self.explicit, label) {
+            (false, None) => Cow::Borrowed(self.name.as_str()),
+            (true, None) => Cow::Owned(format!(
                 "{} {}",
                 self.name,
                 config::get_config().app_config.explicit_icon
-            ))
-        } else {
-            Cow::Borrowed(self.name.as_str())
+            )),
+            (false, Some(label)) => Cow::Owned(format!("{} {label}", self.name)),
+            (true, Some(label)) => Cow::Owned(format!(
+                "{} {} {label}",
+                self.name,
+                config::get_config().app_config.explicit_icon
+            )),
+        }
+    }
+
+    /// gets the URI of the playlist entry
+    pub fn uri(&self) -> String {
+        match &self.kind {
+            TrackKind::Track => self.id.as_ref().map(Id::uri).unwrap_or_default(),
+            TrackKind::Episode { id, .. } => id.uri(),
+            TrackKind::Local { uri, .. } => uri.clone(),
+        }
+    }
+
+    /// gets the playable ID of the playlist entry, `None` for a local file
+    pub fn playable_id(&self) -> Option<PlayableId<'static>> {
+        match &self.kind {
+            TrackKind::Track => self.id.clone().map(PlayableId::Track),
+            TrackKind::Episode { id, .. } => Some(PlayableId::Episode(id.clone())),
+            TrackKind::Local { .. } => None,
         }
     }
 
@@ -361,13 +492,15 @@ impl Track {
                 None => track.id?,
             };
             Some(Self {
-                id,
+                id: Some(id),
                 name: track.name,
                 artists: from_simplified_artists_to_artists(track.artists),
                 album: None,
                 duration: track.duration.to_std().expect("valid chrono duration"),
                 explicit: track.explicit,
                 added_at: 0,
+                isrc: None,
+                kind: TrackKind::Track,
             })
         } else {
             None
@@ -384,32 +517,88 @@ impl Track {
                 Some(d) => d.id?,
                 None => track.id?,
             };
+            let isrc = track.external_ids.get("isrc").cloned();
             Some(Self {
-                id,
+                id: Some(id),
                 name: track.name,
                 artists: from_simplified_artists_to_artists(track.artists),
                 album: Album::try_from_simplified_album(track.album),
                 duration: track.duration.to_std().expect("valid chrono duration"),
                 explicit: track.explicit,
                 added_at: added_at.map(|t| t.timestamp() as u64).unwrap_or_default(),
+                isrc,
+                kind: TrackKind::Track,
             })
         } else {
             None
         }
     }
 
+    /// tries to convert from a `rspotify::model::SavedTrack` into `Track`
+    pub fn try_from_saved_track(track: rspotify::model::SavedTrack) -> Option<Self> {
+        Track::try_from_full_track_with_date(track.track, Some(track.added_at))
+    }
+
     /// tries to convert from a `rspotify::model::FullTrack` into `Track`
     pub fn try_from_full_track(track: rspotify::model::FullTrack) -> Option<Self> {
         Track::try_from_full_track_with_date(track, None)
     }
 
-    /// tries to convert from a `rspotify::model::PlaylistItem` into `Track`
+    /// tries to convert from a `rspotify::model::PlaylistItem` into `Track`,
+    /// keeping episodes and local files as `Track`s of the corresponding kind
     pub fn try_from_playlist_item(item: rspotify::model::PlaylistItem) -> Option<Self> {
-        let rspotify::model::PlayableItem::Track(track) = item.track? else {
-            return None;
-        };
+        Track::try_from_playable_item_with_date(item.track?, item.added_at)
+    }
 
-        Track::try_from_full_track_with_date(track, item.added_at)
+    /// tries to convert from a `rspotify::model::PlayableItem` into `Track`,
+    /// keeping episodes and local files as `Track`s of the corresponding kind
+    pub fn try_from_playable_item(item: rspotify::model::PlayableItem) -> Option<Self> {
+        Track::try_from_playable_item_with_date(item, None)
+    }
+
+    fn try_from_playable_item_with_date(
+        item: rspotify::model::PlayableItem,
+        added_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Option<Self> {
+        let timestamp = added_at.map(|t| t.timestamp() as u64).unwrap_or_default();
+        match item {
+            rspotify::model::PlayableItem::Track(track) if track.is_local => {
+                let uri = local_track_uri(&track);
+                Some(Self {
+                    id: None,
+                    name: track.name,
+                    artists: vec![],
+                    album: None,
+                    duration: track.duration.to_std().unwrap_or_default(),
+                    explicit: track.explicit,
+                    added_at: timestamp,
+                    isrc: None,
+                    kind: TrackKind::Local {
+                        artists: map_join(&track.artists, |a| &a.name, ", "),
+                        album: track.album.name,
+                        uri,
+                    },
+                })
+            }
+            rspotify::model::PlayableItem::Track(track) => {
+                Track::try_from_full_track_with_date(track, added_at)
+            }
+            rspotify::model::PlayableItem::Episode(episode) => Some(Self {
+                id: None,
+                name: episode.name,
+                artists: vec![],
+                album: None,
+                duration: episode.duration.to_std().unwrap_or_default(),
+                explicit: episode.explicit,
+                added_at: timestamp,
+                isrc: None,
+                kind: TrackKind::Episode {
+                    id: episode.id,
+                    show: episode.show.name,
+                },
+            }),
+            rspotify::model::PlayableItem::Unknown(_) => None,
+        }
     }
 }
 
@@ -526,6 +715,30 @@ impl std::fmt::Display for Artist {
     }
 }
 
+/// constructs the `spotify:local:` URI of a local file, which isn't returned by Spotify APIs
+fn local_track_uri(track: &rspotify::model::FullTrack) -> String {
+    let encode = |s: &str| form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+    format!(
+        "spotify:local:{}:{}:{}:{}",
+        encode(track.artists.first().map_or("", |a| a.name.as_str())),
+        encode(&track.album.name),
+        encode(&track.name),
+        track.duration.num_seconds(),
+    )
+}
+
+/// gets the URI of a `rspotify::model::PlayableItem`, including local files
+pub fn playable_item_uri(item: &rspotify::model::PlayableItem) -> Option<String> {
+    match item {
+        rspotify::model::PlayableItem::Track(track) if track.is_local => {
+            Some(local_track_uri(track))
+        }
+        rspotify::model::PlayableItem::Track(track) => track.id.as_ref().map(Id::uri),
+        rspotify::model::PlayableItem::Episode(episode) => Some(episode.id.uri()),
+        rspotify::model::PlayableItem::Unknown(_) => None,
+    }
+}
+
 /// a helper function to convert a vector of `rspotify::model::SimplifiedArtist`
 /// into a vector of `Artist`.
 fn from_simplified_artists_to_artists(
@@ -591,15 +804,29 @@ impl From<rspotify::model::SimplifiedShow> for Show {
         Self {
             id: show.id,
             name: show.name,
+            added_at: 0,
+            unplayed_episodes: None,
         }
     }
 }
 
+impl From<rspotify::model::Show> for Show {
+    fn from(saved_show: rspotify::model::Show) -> Self {
+        let mut show: Show = saved_show.show.into();
+        show.added_at = chrono::DateTime::parse_from_rfc3339(&saved_show.added_at)
+            .map(|t| t.timestamp() as u64)
+            .unwrap_or_default();
+        show
+    }
+}
+
 impl From<rspotify::model::FullShow> for Show {
     fn from(show: rspotify::model::FullShow) -> Self {
         Self {
             id: show.id,
             name: show.name,
+            added_at: 0,
+            unplayed_episodes: None,
         }
     }
 }
@@ -612,6 +839,34 @@ impl std::fmt::Display for Show {
 
 impl BidiDisplay for Show {}
 
+impl AudiobookId {
+    pub fn from_id(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+
+    pub fn uri(&self) -> String {
+        format!("spotify:audiobook:{}", self.0)
+    }
+}
+
+impl std::fmt::Display for Audiobook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} • {}", self.name, self.authors.join(", "))
+    }
+}
+
+impl BidiDisplay for Audiobook {}
+
+impl std::fmt::Display for Chapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 impl From<rspotify::model::SimplifiedEpisode> for Episode {
     fn from(episode: rspotify::model::SimplifiedEpisode) -> Self {
         Self {
@@ -693,6 +948,53 @@ impl TracksId {
             kind: kind.into(),
         }
     }
+
+    /// Get the ID of the user's top tracks in a time range
+    pub fn top_tracks(time_range: TimeRange) -> Self {
+        Self::new(
+            format!("{USER_TOP_TRACKS_URI}:{}", time_range.as_str()),
+            "Top Tracks",
+        )
+    }
+
+    /// Get the time range of the user's top tracks with this ID, `None` for other tracks
+    pub fn top_tracks_time_range(&self) -> Option<TimeRange> {
+        TimeRange::ALL
+            .into_iter()
+            .find(|range| Self::top_tracks(*range).uri == self.uri)
+    }
+}
+
+impl TimeRange {
+    pub const ALL: [Self; 3] = [Self::Short, Self::Medium, Self::Long];
+
+    /// Get the time range following the current one, cycling back to the shortest one
+    pub fn next(self) -> Self {
+        match self {
+            Self::Short => Self::Medium,
+            Self::Medium => Self::Long,
+            Self::Long => Self::Short,
+        }
+    }
+
+    /// Get the time range's value in the Spotify API
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Short => "short_term",
+            Self::Medium => "medium_term",
+            Self::Long => "long_term",
+        }
+    }
+}
+
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Short => write!(f, "Last 4 Weeks"),
+            Self::Medium => write!(f, "Last 6 Months"),
+            Self::Long => write!(f, "Last Year"),
+        }
+    }
 }
 
 impl Playback {
@@ -763,3 +1065,147 @@ impl From<librespot_metadata::lyrics::Lyrics> for Lyrics {
         Self { lines }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rspotify::model::{FullTrack, PlayableItem, PlaylistItem};
+    use serde_json::json;
+
+    fn full_track(id: Option<&str>, is_local: bool) -> FullTrack {
+        serde_json::from_value(json!({
+            "album": {
+                "album_type": null,
+                "artists": [],
+                "external_urls": {},
+                "href": null,
+                "id": null,
+                "images": [],
+                "name": "Some Album",
+            },
+            "artists": [{
+                "external_urls": {},
+                "href": null,
+                "id": null,
+                "name": "Some Artist",
+            }],
+            "disc_number": 1,
+            "duration_ms": 201_500,
+            "explicit": false,
+            "external_ids": {},
+            "external_urls": {},
+            "href": null,
+            "id": id,
+            "is_local": is_local,
+            "name": "Some Track",
+            "popularity": 0,
+            "preview_url": null,
+            "track_number": 1,
+            "type": "track",
+        }))
+        .unwrap()
+    }
+
+    fn playlist_item(item: PlayableItem) -> PlaylistItem {
+        PlaylistItem {
+            added_at: chrono::DateTime::from_timestamp(1_700_000_000, 0),
+            added_by: None,
+            is_local: false,
+            track: Some(item),
+        }
+    }
+
+    #[test]
+    fn local_track_uri_is_encoded() {
+        assert_eq!(
+            local_track_uri(&full_track(None, true)),
+            "spotify:local:Some+Artist:Some+Album:Some+Track:201"
+        );
+    }
+
+    #[test]
+    fn playlist_item_track() {
+        let item = playlist_item(PlayableItem::Track(full_track(
+            Some("4uLU6hMCjMI75M1A2tKUQC"),
+            false,
+        )));
+        let track = Track::try_from_playlist_item(item).unwrap();
+        assert_eq!(track.kind, TrackKind::Track);
+        assert_eq!(track.uri(), "spotify:track:4uLU6hMCjMI75M1A2tKUQC");
+        assert_eq!(track.added_at, 1_700_000_000);
+        assert!(track.playable_id().is_some());
+    }
+
+    #[test]
+    fn playlist_item_local_file() {
+        let item = playlist_item(PlayableItem::Track(full_track(None, true)));
+        let track = Track::try_from_playlist_item(item.clone()).unwrap();
+        assert!(matches!(track.kind, TrackKind::Local { .. }));
+        assert_eq!(
+            track.uri(),
+            "spotify:local:Some+Artist:Some+Album:Some+Track:201"
+        );
+        assert_eq!(track.added_at, 1_700_000_000);
+        assert!(track.playable_id().is_none());
+        assert_eq!(
+            playable_item_uri(item.track.as_ref().unwrap()),
+            Some(track.uri())
+        );
+        assert!(track.id.is_none());
+    }
+
+    #[test]
+    fn playlist_item_episode() {
+        let episode = serde_json::from_value(json!({
+            "audio_preview_url": null,
+            "description": "",
+            "duration_ms": 60_000,
+            "explicit": false,
+            "external_urls": {},
+            "href": "",
+            "id": "512ojhOuo1ktJprKbVcKyQ",
+            "images": [],
+            "is_externally_hosted": false,
+            "is_playable": true,
+            "language": "en",
+            "languages": ["en"],
+            "name": "Some Episode",
+            "release_date": "2024-01-01",
+            "release_date_precision": "day",
+            "resume_point": null,
+            "show": {
+                "available_markets": [],
+                "copyrights": [],
+                "description": "",
+                "explicit": false,
+                "external_urls": {},
+                "href": "",
+                "id": "38bS44xjbVVZ3No3ByF1dJ",
+                "images": [],
+                "languages": ["en"],
+                "media_type": "audio",
+                "name": "Some Show",
+                "publisher": "Someone",
+            },
+            "type": "episode",
+        }))
+        .unwrap();
+        let track =
+            Track::try_from_playlist_item(playlist_item(PlayableItem::Episode(episode))).unwrap();
+        assert!(matches!(track.kind, TrackKind::Episode { ref show, .. } if show == "Some Show"));
+        assert_eq!(track.uri(), "spotify:episode:512ojhOuo1ktJprKbVcKyQ");
+        assert!(track.id.is_none());
+        assert_eq!(track.playable_id().map(|id| id.uri()), Some(track.uri()));
+    }
+
+    #[test]
+    fn playlist_item_without_track() {
+        let item = PlaylistItem {
+            added_at: None,
+            added_by: None,
+            is_local: false,
+            track: None,
+        };
+        assert!(Track::try_from_playlist_item(item).is_none());
+    }
+}
@@ -2,6 +2,9 @@ use super::model::{
     AlbumId, ArtistId, ContextId, Device, PlaybackMetadata, PlaylistId, ShowId, TracksId,
 };
 use super::queue::CustomQueue;
+#[cfg(feature = "streaming")]
+use super::queue::SavedCustomQueue;
+use super::sleep_timer::SleepTimer;
 
 /// Player state
 #[derive(Default, Debug)]
@@ -23,6 +26,9 @@ pub struct PlayerState {
     /// Active when the integrated librespot player is streaming and the user
     /// started playback from a track-table context.
     pub custom_queue: Option<CustomQueue>,
+
+    /// The sleep timer pausing the playback when it expires
+    pub sleep_timer: Option<SleepTimer>,
 }
 
 impl PlayerState {
@@ -79,6 +85,24 @@ impl PlayerState {
         }
     }
 
+    /// Get the custom queue to save into the cache folder,
+    /// with the playback progress of the queue's current track
+    #[cfg(feature = "streaming")]
+    pub fn saved_custom_queue(&self) -> Option<SavedCustomQueue> {
+        let queue = self.custom_queue.as_ref()?;
+        let progress = match self
+            .currently_playing()
+            .and_then(rspotify::model::PlayableItem::id)
+        {
+            Some(id) if id == *queue.current_track() => self.playback_progress(),
+            _ => None,
+        };
+        Some(SavedCustomQueue {
+            queue: queue.clone(),
+            progress_ms: progress.map_or(0, |p| p.num_milliseconds()),
+        })
+    }
+
     pub fn playing_context_id(&self) -> Option<ContextId> {
         match self.playback {
             Some(ref playback) => match playback.context {
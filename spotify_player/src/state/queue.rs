@@ -1,4 +1,5 @@
 use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
 use super::model::{ContextId, PlayableId};
@@ -32,7 +33,7 @@ pub enum RetreatResult {
 }
 
 /// Shuffle mode for the custom queue.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum ShuffleMode {
     #[default]
@@ -41,7 +42,7 @@ pub enum ShuffleMode {
     Shuffle,
     /// Smart shuffle — shuffle + interleave radio recommendations.
     /// Carries the radio tracks used for interleaving.
-    SmartShuffle(Vec<PlayableId<'static>>),
+    SmartShuffle(#[serde(with = "playable_uris")] Vec<PlayableId<'static>>),
 }
 
 /// App-managed playback queue that replaces spirc-managed queueing.
@@ -50,15 +51,17 @@ pub enum ShuffleMode {
 /// (playlist, album, etc.) and sends batches of URIs to Spotify. It only
 /// intervenes at batch boundaries — within a batch, librespot handles
 /// next/previous natively.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct CustomQueue {
     /// Original ordered track list (from the context, respecting client-side sort).
+    #[serde(with = "playable_uris")]
     original_tracks: Vec<PlayableId<'static>>,
     /// The effective play order.
     /// When shuffle is off this is a clone of `original_tracks`; when on it's a
     /// permutation. When smart-shuffle is on, extra recommendation track IDs are
     /// interleaved.
+    #[serde(with = "playable_uris")]
     play_order: Vec<PlayableId<'static>>,
     /// Current position within `play_order`.
     position: usize,
@@ -81,9 +84,78 @@ pub struct CustomQueue {
     /// Sourced from `DeviceConfig.autoplay`.
     autoplay: bool,
     /// Timestamp of last batch transition, used for consistency-check cooldown.
+    #[serde(skip)]
     last_batch_transition: Option<Instant>,
 }
 
+/// A custom queue saved into the cache folder, used to resume the play session
+/// after the application restarts.
+#[cfg(feature = "streaming")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedCustomQueue {
+    pub queue: CustomQueue,
+    /// Playback progress of the queue's current track, in milliseconds
+    pub progress_ms: i64,
+}
+
+/// Store a custom queue into the cache folder,
+/// removing the stored queue if there is no custom queue anymore
+#[cfg(feature = "streaming")]
+pub fn store_custom_queue(saved: Option<&SavedCustomQueue>) -> std::io::Result<()> {
+    let cache_folder = &crate::config::get_config().cache_folder;
+    match saved {
+        Some(saved) => {
+            super::store_data_into_file_cache(super::FileCacheKey::CustomQueue, cache_folder, saved)
+        }
+        None => super::remove_data_from_file_cache(super::FileCacheKey::CustomQueue, cache_folder),
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl super::State {
+    /// Save the custom queue with its current progress, e.g. when the application exits
+    pub fn save_custom_queue(&self) -> std::io::Result<()> {
+        if !crate::config::get_config().app_config.resume_custom_queue {
+            return Ok(());
+        }
+        let saved = self.player.read().saved_custom_queue();
+        store_custom_queue(saved.as_ref())
+    }
+}
+
+/// (De)serialize a list of playable IDs as Spotify URIs, because
+/// `rspotify`'s `PlayableId` can't be deserialized.
+mod playable_uris {
+    use rspotify::model::{EpisodeId, IdError, TrackId};
+    use rspotify::prelude::Id;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::PlayableId;
+
+    pub fn serialize<S: Serializer>(
+        ids: &[PlayableId<'static>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids.iter().map(Id::uri))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PlayableId<'static>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|uri| {
+                let id = match TrackId::from_uri(uri) {
+                    Ok(id) => PlayableId::from(id),
+                    Err(_) => PlayableId::from(EpisodeId::from_uri(uri)?),
+                };
+                Ok(id.into_static())
+            })
+            .collect::<Result<_, IdError>>()
+            .map_err(D::Error::custom)
+    }
+}
+
 #[allow(dead_code)]
 impl CustomQueue {
     /// Create a new custom queue.
@@ -378,6 +450,107 @@ impl CustomQueue {
         self.play_order.extend(tracks);
     }
 
+    // ── Queue editing ──────────────────────────────────────────────────
+    //
+    // Upcoming tracks are addressed by their index within `remaining_tracks()`.
+    // Edits touching the current batch truncate it so they take effect at the
+    // next batch boundary without interrupting the currently playing track.
+    // When shuffle is off, `original_tracks` mirrors `play_order` so that the
+    // edits survive toggling shuffle.
+
+    /// Append tracks to the end of the queue.
+    pub fn append(&mut self, tracks: Vec<PlayableId<'static>>) {
+        self.original_tracks.extend(tracks.iter().cloned());
+        self.play_order.extend(tracks);
+    }
+
+    /// Insert tracks right after the current track.
+    pub fn insert_next(&mut self, tracks: Vec<PlayableId<'static>>) {
+        let index = self.position + 1;
+        if self.shuffle_mode == ShuffleMode::Off {
+            let original_index = index.min(self.original_tracks.len());
+            self.original_tracks
+                .splice(original_index..original_index, tracks.iter().cloned());
+        } else {
+            self.original_tracks.extend(tracks.iter().cloned());
+        }
+        self.play_order.splice(index..index, tracks);
+        self.truncate_batch_to_current();
+    }
+
+    /// Remove the `index`-th upcoming track, returning the removed track.
+    pub fn remove_upcoming(&mut self, index: usize) -> Option<PlayableId<'static>> {
+        let index = self.position + 1 + index;
+        if index >= self.play_order.len() {
+            return None;
+        }
+
+        let track = self.play_order.remove(index);
+        if self.shuffle_mode == ShuffleMode::Off {
+            if index < self.original_tracks.len() {
+                self.original_tracks.remove(index);
+            }
+        } else if let Some(i) = self.original_tracks.iter().position(|t| *t == track) {
+            self.original_tracks.remove(i);
+        }
+        if index < self.batch_end {
+            self.truncate_batch_to_current();
+        }
+        Some(track)
+    }
+
+    /// Move the `from`-th upcoming track so that it becomes the `to`-th upcoming track.
+    /// Returns `false` if either index is out of range.
+    pub fn move_upcoming(&mut self, from: usize, to: usize) -> bool {
+        let offset = self.position + 1;
+        let (from, to) = (offset + from, offset + to);
+        if from >= self.play_order.len() || to >= self.play_order.len() {
+            return false;
+        }
+
+        let track = self.play_order.remove(from);
+        self.play_order.insert(to, track);
+        if self.shuffle_mode == ShuffleMode::Off
+            && from < self.original_tracks.len()
+            && to < self.original_tracks.len()
+        {
+            let track = self.original_tracks.remove(from);
+            self.original_tracks.insert(to, track);
+        }
+        if from.min(to) < self.batch_end {
+            self.truncate_batch_to_current();
+        }
+        true
+    }
+
+    /// Remove all tracks after the current track.
+    pub fn clear_upcoming(&mut self) {
+        let played = &self.play_order[..=self.position];
+        if self.shuffle_mode == ShuffleMode::Off {
+            self.original_tracks
+                .truncate((self.position + 1).min(self.original_tracks.len()));
+        } else {
+            self.original_tracks.retain(|t| played.contains(t));
+        }
+        self.play_order.truncate(self.position + 1);
+        self.truncate_batch_to_current();
+    }
+
+    /// Jump to the `index`-th upcoming track, skipping the tracks before it.
+    /// Returns the new batch, starting at the jumped-to track, to send to Spotify,
+    /// or `None` if the index is out of range.
+    pub fn jump_to_upcoming(&mut self, index: usize) -> Option<Vec<PlayableId<'static>>> {
+        let position = self.position + 1 + index;
+        if position >= self.play_order.len() {
+            return None;
+        }
+        self.position = position;
+        self.batch_start = position;
+        self.batch_end = (self.batch_start + self.max_batch_size).min(self.play_order.len());
+        self.mark_batch_transition();
+        Some(self.current_batch().to_vec())
+    }
+
     /// Compute and load the next batch. Returns the batch URIs to send to
     /// Spotify, or `None` if the queue is exhausted.
     pub fn next_batch(&mut self) -> Option<Vec<PlayableId<'static>>> {
@@ -657,4 +830,151 @@ mod tests {
         assert_eq!(*q.current_track(), tracks[3]);
         assert_eq!(q.position(), 3);
     }
+
+    #[test]
+    fn insert_next_after_current_track() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks.clone(), 2, 5, None, false);
+
+        let extra = make_track_id(100);
+        q.insert_next(vec![extra.clone()]);
+
+        assert_eq!(q.remaining_tracks()[0], extra);
+        assert_eq!(q.len(), 11);
+        // Batch should be truncated so the insertion takes effect at the next boundary.
+        assert_eq!(q.batch_end(), 3);
+
+        // The insertion survives toggling shuffle off.
+        q.set_shuffle_mode(ShuffleMode::Off);
+        assert_eq!(q.remaining_tracks()[0], extra);
+    }
+
+    #[test]
+    fn append_outside_current_batch() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks, 0, 5, None, false);
+
+        let extra = make_track_id(100);
+        q.append(vec![extra.clone()]);
+
+        assert_eq!(q.len(), 11);
+        assert_eq!(*q.remaining_tracks().last().unwrap(), extra);
+        // Appending doesn't touch the current batch.
+        assert_eq!(q.batch_end(), 5);
+    }
+
+    #[test]
+    fn remove_upcoming_track() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks.clone(), 0, 5, None, false);
+
+        assert_eq!(q.remove_upcoming(7), Some(tracks[8].clone()));
+        assert_eq!(q.len(), 9);
+        assert_eq!(q.batch_end(), 5);
+
+        assert_eq!(q.remove_upcoming(0), Some(tracks[1].clone()));
+        assert_eq!(q.remaining_tracks()[0], tracks[2]);
+        assert_eq!(q.batch_end(), 1);
+
+        assert_eq!(q.remove_upcoming(100), None);
+    }
+
+    #[test]
+    fn move_upcoming_track() {
+        let tracks = make_tracks(5);
+        let mut q = CustomQueue::new(tracks.clone(), 0, 10, None, false);
+
+        assert!(q.move_upcoming(3, 0));
+        assert_eq!(
+            q.remaining_tracks(),
+            &[
+                tracks[4].clone(),
+                tracks[1].clone(),
+                tracks[2].clone(),
+                tracks[3].clone()
+            ]
+        );
+        assert_eq!(q.batch_end(), 1);
+        assert!(!q.move_upcoming(0, 4));
+    }
+
+    #[test]
+    fn clear_upcoming_tracks() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks.clone(), 3, 5, None, false);
+
+        q.clear_upcoming();
+
+        assert!(q.remaining_tracks().is_empty());
+        assert_eq!(*q.current_track(), tracks[3]);
+        assert_eq!(q.advance(), AdvanceResult::EndOfQueue);
+    }
+
+    #[cfg(feature = "streaming")]
+    #[test]
+    fn saved_queue_round_trip() {
+        let mut tracks = make_tracks(6);
+        tracks.push(PlayableId::Episode(
+            rspotify::model::EpisodeId::from_id("episode0000000000000000")
+                .unwrap()
+                .into_static(),
+        ));
+        let context = ContextId::Playlist(
+            rspotify::model::PlaylistId::from_id("playlist00000000000000")
+                .unwrap()
+                .into_static(),
+        );
+        let mut q = CustomQueue::new(tracks, 2, 3, Some(context), true);
+        q.set_repeat(rspotify::model::RepeatState::Context);
+        q.set_shuffle_mode(ShuffleMode::SmartShuffle(vec![make_track_id(42)]));
+        let saved = SavedCustomQueue {
+            queue: q,
+            progress_ms: 12_345,
+        };
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let restored: SavedCustomQueue = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, saved);
+    }
+
+    #[test]
+    fn jump_to_upcoming_track() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks.clone(), 1, 3, None, false);
+
+        let batch = q.jump_to_upcoming(4).unwrap();
+        assert_eq!(batch, tracks[6..9].to_vec());
+        assert_eq!(*q.current_track(), tracks[6]);
+        assert_eq!(q.remaining_tracks(), &tracks[7..]);
+        assert!(q.jump_to_upcoming(3).is_none());
+    }
+
+    #[test]
+    fn jump_to_next_and_last_track() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks.clone(), 0, 3, None, false);
+
+        let batch = q.jump_to_upcoming(0).unwrap();
+        assert_eq!(batch, tracks[1..4].to_vec());
+        assert_eq!(*q.current_track(), tracks[1]);
+
+        // the batch is clamped at the end of the queue
+        let batch = q.jump_to_upcoming(7).unwrap();
+        assert_eq!(batch, tracks[9..].to_vec());
+        assert!(q.remaining_tracks().is_empty());
+        assert!(q.jump_to_upcoming(0).is_none());
+    }
+
+    #[test]
+    fn jump_to_upcoming_shuffled_track() {
+        let tracks = make_tracks(10);
+        let mut q = CustomQueue::new(tracks, 0, 3, None, false);
+        q.set_shuffle_mode(ShuffleMode::Shuffle);
+        let upcoming = q.remaining_tracks().to_vec();
+
+        q.jump_to_upcoming(2).unwrap();
+        assert_eq!(*q.current_track(), upcoming[2]);
+        assert_eq!(q.remaining_tracks(), &upcoming[3..]);
+    }
 }
@@ -0,0 +1,241 @@
+use std::time::{Duration, Instant};
+
+use rspotify::model::PlayableItem;
+use rspotify::prelude::Id;
+use serde::{Deserialize, Serialize};
+
+use super::{ContextId, PlayerState};
+
+/// Presets cycled through by the `SleepTimer` command
+const SLEEP_TIMER_PRESETS: [SleepMode; 6] = [
+    SleepMode::Duration(Duration::from_mins(15)),
+    SleepMode::Duration(Duration::from_mins(30)),
+    SleepMode::Duration(Duration::from_mins(45)),
+    SleepMode::Duration(Duration::from_hours(1)),
+    SleepMode::EndOfTrack,
+    SleepMode::EndOfContext,
+];
+
+/// Remaining time of the playing item under which it's considered as ended,
+/// so that the playback is paused before the next item starts
+const END_OF_ITEM_MARGIN: Duration = Duration::from_secs(1);
+
+/// When a sleep timer expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SleepMode {
+    /// after a duration
+    Duration(Duration),
+    /// at the end of the current track
+    EndOfTrack,
+    /// at the end of the current context
+    EndOfContext,
+}
+
+/// A sleep timer, pausing the playback when it expires
+#[derive(Debug, Clone)]
+pub struct SleepTimer {
+    pub mode: SleepMode,
+    expiry: SleepExpiry,
+    /// duration of the volume fade-out before the timer expires
+    pub fade: Duration,
+    /// volume before the fade-out started, restored after the playback is paused
+    pub volume: Option<u8>,
+    /// the last time the volume was lowered during the fade-out
+    pub last_fade: Option<Instant>,
+    /// whether the timer has expired, an expired timer is removed once the playback is paused
+    pub expired: bool,
+    /// URI of the last item of the playing context, if known
+    pub context_last_uri: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum SleepExpiry {
+    At(Instant),
+    /// the end of the track with the given URI
+    EndOfTrack(Option<String>),
+    /// the end of the given context
+    EndOfContext(Option<ContextId>),
+}
+
+impl SleepMode {
+    /// Get the sleep timer preset following `current`, `None` meaning no sleep timer
+    pub fn next_preset(current: Option<SleepMode>) -> Option<SleepMode> {
+        match current {
+            None => Some(SLEEP_TIMER_PRESETS[0]),
+            Some(mode) => SLEEP_TIMER_PRESETS
+                .iter()
+                .position(|&m| m == mode)
+                .and_then(|i| SLEEP_TIMER_PRESETS.get(i + 1).copied()),
+        }
+    }
+}
+
+impl std::str::FromStr for SleepMode {
+    type Err = anyhow::Error;
+
+    /// Parse a sleep mode from `end-of-track`, `end-of-context` or a duration
+    /// like `90s`, `45m`, `1h30m` (a number without unit is a number of minutes)
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "end-of-track" => return Ok(Self::EndOfTrack),
+            "end-of-context" => return Ok(Self::EndOfContext),
+            _ => {}
+        }
+        if let Ok(mins) = s.parse::<u64>() {
+            return Ok(Self::Duration(Duration::from_secs(60 * mins)));
+        }
+
+        let mut secs = 0;
+        let mut rest = s;
+        while !rest.is_empty() {
+            let unit_pos = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(|| anyhow::anyhow!("missing unit in sleep duration {s}"))?;
+            let value = rest[..unit_pos]
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("invalid sleep duration {s}"))?;
+            let unit = rest[unit_pos..].chars().next().unwrap_or_default();
+            secs += value
+                * match unit {
+                    'h' => 3600,
+                    'm' => 60,
+                    's' => 1,
+                    _ => anyhow::bail!("invalid unit '{unit}' in sleep duration {s}"),
+                };
+            rest = &rest[unit_pos + unit.len_utf8()..];
+        }
+        if secs == 0 {
+            anyhow::bail!("invalid sleep duration {s}");
+        }
+        Ok(Self::Duration(Duration::from_secs(secs)))
+    }
+}
+
+impl SleepTimer {
+    /// Get the volume to set during the fade-out, `remaining` before the timer expires
+    pub fn fade_volume(&self, remaining: Duration) -> Option<u8> {
+        let volume = u128::from(self.volume?);
+        u8::try_from(volume * remaining.as_millis() / self.fade.as_millis().max(1)).ok()
+    }
+}
+
+impl PlayerState {
+    /// Start a sleep timer, or cancel the current one if `mode` is `None`.
+    ///
+    /// Returns the volume to restore if the current timer was fading out the playback.
+    pub fn set_sleep_timer(&mut self, mode: Option<SleepMode>, fade: Duration) -> Option<u8> {
+        let volume = self.sleep_timer.take().and_then(|t| t.volume);
+        self.sleep_timer = mode.map(|mode| SleepTimer {
+            mode,
+            expiry: match mode {
+                SleepMode::Duration(duration) => SleepExpiry::At(Instant::now() + duration),
+                SleepMode::EndOfTrack => SleepExpiry::EndOfTrack(self.playing_uri()),
+                SleepMode::EndOfContext => SleepExpiry::EndOfContext(self.playing_context_id()),
+            },
+            fade,
+            volume: None,
+            last_fade: None,
+            expired: false,
+            context_last_uri: None,
+        });
+        volume
+    }
+
+    /// Get the remaining time before the sleep timer expires.
+    ///
+    /// The end of a track or a context is detected from the playing item's progress,
+    /// falling back to a change of the playing track or context.
+    ///
+    /// Returns `None` if there is no sleep timer or its remaining time is unknown,
+    /// e.g. the timer expires at the end of the current context whose last item is not playing.
+    pub fn sleep_timer_remaining(&self) -> Option<Duration> {
+        let timer = self.sleep_timer.as_ref()?;
+        let remaining = match timer.expiry {
+            SleepExpiry::At(deadline) => {
+                return Some(deadline.saturating_duration_since(Instant::now()))
+            }
+            SleepExpiry::EndOfTrack(ref uri) => {
+                if self.playing_uri() == *uri {
+                    self.playing_remaining()
+                } else {
+                    Some(Duration::ZERO)
+                }
+            }
+            SleepExpiry::EndOfContext(ref id) => {
+                let is_last_item = match self.custom_queue {
+                    Some(ref queue) => queue.remaining_tracks().is_empty(),
+                    None => {
+                        timer.context_last_uri.is_some()
+                            && self.playing_uri() == timer.context_last_uri
+                    }
+                };
+                if self.playing_context_id() != *id {
+                    Some(Duration::ZERO)
+                } else if is_last_item {
+                    self.playing_remaining()
+                } else {
+                    None
+                }
+            }
+        };
+        remaining.map(|r| r.saturating_sub(END_OF_ITEM_MARGIN))
+    }
+
+    fn playing_uri(&self) -> Option<String> {
+        match self.currently_playing()? {
+            PlayableItem::Track(track) => Some(track.id.as_ref()?.uri()),
+            PlayableItem::Episode(episode) => Some(episode.id.uri()),
+            PlayableItem::Unknown(_) => None,
+        }
+    }
+
+    /// Get the remaining time of the currently playing item
+    fn playing_remaining(&self) -> Option<Duration> {
+        let duration = match self.currently_playing()? {
+            PlayableItem::Track(track) => track.duration,
+            PlayableItem::Episode(episode) => episode.duration,
+            PlayableItem::Unknown(_) => return None,
+        };
+        Some(
+            (duration - self.playback_progress()?)
+                .to_std()
+                .unwrap_or_default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sleep_mode() {
+        let parse = |s: &str| s.parse::<SleepMode>().ok();
+        assert_eq!(parse("end-of-track"), Some(SleepMode::EndOfTrack));
+        assert_eq!(
+            parse("45"),
+            Some(SleepMode::Duration(Duration::from_mins(45)))
+        );
+        assert_eq!(
+            parse("1h30m15s"),
+            Some(SleepMode::Duration(Duration::from_secs(5415)))
+        );
+        assert_eq!(parse("30x"), None);
+        assert_eq!(parse("h"), None);
+        assert_eq!(parse("0m"), None);
+    }
+
+    #[test]
+    fn cycle_sleep_presets() {
+        let mut mode = None;
+        let mut modes = vec![];
+        loop {
+            mode = SleepMode::next_preset(mode);
+            match mode {
+                Some(m) => modes.push(m),
+                None => break,
+            }
+        }
+        assert_eq!(modes, SLEEP_TIMER_PRESETS);
+    }
+}
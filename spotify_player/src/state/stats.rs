@@ -0,0 +1,350 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDate, TimeZone};
+use rspotify::prelude::Id;
+
+use super::{Album, Artist, HistoryRecord, Track};
+
+/// Maximum number of entries in a top list
+const MAX_TOP_ENTRIES: usize = 50;
+
+/// Time window of the listening statistics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StatsWindow {
+    Day,
+    #[default]
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+/// Listening statistics of an item
+#[derive(Debug, Clone)]
+pub struct StatsEntry<T> {
+    pub item: T,
+    pub plays: usize,
+    pub skips: usize,
+    pub listened: Duration,
+}
+
+/// Listening statistics computed from the listening history
+#[derive(Debug, Clone)]
+pub struct ListeningStats {
+    pub window: StatsWindow,
+    pub plays: usize,
+    pub skips: usize,
+    pub listened: Duration,
+    pub top_tracks: Vec<StatsEntry<Track>>,
+    pub top_artists: Vec<StatsEntry<Artist>>,
+    pub top_albums: Vec<StatsEntry<Album>>,
+    pub top_genres: Vec<StatsEntry<String>>,
+    /// listening time of each day of the window, oldest day first
+    pub daily: Vec<(NaiveDate, Duration)>,
+    /// the longest number of consecutive days with some listening in the window
+    pub longest_streak: usize,
+    /// the number of consecutive days with some listening up to today,
+    /// or up to yesterday if nothing was played today yet
+    pub current_streak: usize,
+}
+
+impl StatsWindow {
+    /// Get the window following the current one, cycling back to the first window
+    pub fn next(self) -> Self {
+        match self {
+            Self::Day => Self::Week,
+            Self::Week => Self::Month,
+            Self::Month => Self::Year,
+            Self::Year => Self::All,
+            Self::All => Self::Day,
+        }
+    }
+
+    /// Get the number of days in the window, including today, `None` for the whole history
+    fn days(self) -> Option<u64> {
+        match self {
+            Self::Day => Some(1),
+            Self::Week => Some(7),
+            Self::Month => Some(30),
+            Self::Year => Some(365),
+            Self::All => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StatsWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.days() {
+            Some(1) => write!(f, "Today"),
+            Some(days) => write!(f, "Last {days} days"),
+            None => write!(f, "All time"),
+        }
+    }
+}
+
+impl<T> StatsEntry<T> {
+    fn new(item: T) -> Self {
+        Self {
+            item,
+            plays: 0,
+            skips: 0,
+            listened: Duration::ZERO,
+        }
+    }
+
+    fn add(&mut self, record: &HistoryRecord) {
+        self.plays += 1;
+        self.skips += usize::from(record.skipped);
+        self.listened += Duration::from_millis(record.listened_ms);
+    }
+
+    /// Get the percentage of the plays which were skipped
+    pub fn skip_rate(&self) -> usize {
+        skip_rate(self.skips, self.plays)
+    }
+}
+
+/// Listening statistics of the items of a kind, keyed by their URI
+struct Tally<T>(HashMap<String, StatsEntry<T>>);
+
+impl<T> Tally<T> {
+    fn add(&mut self, key: String, item: impl FnOnce() -> T, record: &HistoryRecord) {
+        self.0
+            .entry(key)
+            .or_insert_with(|| StatsEntry::new(item()))
+            .add(record);
+    }
+
+    /// Get the most played items, the most listened ones first in case of a tie
+    fn top(self) -> Vec<StatsEntry<T>> {
+        let mut entries = self.0.into_values().collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            b.plays
+                .cmp(&a.plays)
+                .then_with(|| b.listened.cmp(&a.listened))
+        });
+        entries.truncate(MAX_TOP_ENTRIES);
+        entries
+    }
+}
+
+impl<T> Default for Tally<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl ListeningStats {
+    /// Compute the listening statistics of a window from the listening history.
+    ///
+    /// Days are calendar days in the timezone of `now`.
+    pub fn new<Tz: TimeZone>(
+        records: &[HistoryRecord],
+        window: StatsWindow,
+        now: &DateTime<Tz>,
+    ) -> Self {
+        let tz = now.timezone();
+        let date = |record: &HistoryRecord| record.started_at.with_timezone(&tz).date_naive();
+        let today = now.date_naive();
+
+        let start = match window.days() {
+            Some(days) => today - chrono::Days::new(days - 1),
+            None => records.iter().map(date).min().unwrap_or(today),
+        };
+        let all_listening_days = records.iter().map(date).collect::<BTreeSet<_>>();
+        let records = records
+            .iter()
+            .filter(|r| (start..=today).contains(&date(r)))
+            .collect::<Vec<_>>();
+
+        let mut tracks = Tally::default();
+        let mut artists = Tally::default();
+        let mut albums = Tally::default();
+        let mut genres = Tally::default();
+        let mut daily = HashMap::<NaiveDate, Duration>::new();
+        for record in &records {
+            if let Some(track) = &record.track {
+                tracks.add(track.uri(), || track.clone(), record);
+                for artist in &track.artists {
+                    artists.add(artist.id.uri(), || artist.clone(), record);
+                }
+                if let Some(album) = &track.album {
+                    albums.add(album.id.uri(), || album.clone(), record);
+                }
+            }
+            for genre in &record.genres {
+                genres.add(genre.clone(), || genre.clone(), record);
+            }
+            *daily.entry(date(record)).or_default() += Duration::from_millis(record.listened_ms);
+        }
+
+        let listening_days = daily.keys().copied().collect::<BTreeSet<_>>();
+        Self {
+            window,
+            plays: records.len(),
+            skips: records.iter().filter(|r| r.skipped).count(),
+            listened: daily.values().sum(),
+            top_tracks: tracks.top(),
+            top_artists: artists.top(),
+            top_albums: albums.top(),
+            top_genres: genres.top(),
+            daily: start
+                .iter_days()
+                .take_while(|d| *d <= today)
+                .map(|d| (d, daily.get(&d).copied().unwrap_or_default()))
+                .collect(),
+            longest_streak: longest_streak(&listening_days),
+            current_streak: current_streak(&all_listening_days, today),
+        }
+    }
+
+    /// Get the percentage of the plays which were skipped
+    pub fn skip_rate(&self) -> usize {
+        skip_rate(self.skips, self.plays)
+    }
+}
+
+fn skip_rate(skips: usize, plays: usize) -> usize {
+    (skips * 100).checked_div(plays).unwrap_or_default()
+}
+
+fn longest_streak(days: &BTreeSet<NaiveDate>) -> usize {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for &day in days {
+        streak = match prev {
+            Some(prev) if prev.succ_opt() == Some(day) => streak + 1,
+            _ => 1,
+        };
+        longest = longest.max(streak);
+        prev = Some(day);
+    }
+    longest
+}
+
+fn current_streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> usize {
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        match today.pred_opt() {
+            Some(yesterday) => yesterday,
+            None => return 0,
+        }
+    };
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        match day.pred_opt() {
+            Some(prev) => day = prev,
+            None => break,
+        }
+    }
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rspotify::model::{AlbumId, ArtistId, TrackId};
+
+    use crate::state::TrackKind;
+
+    use super::*;
+
+    fn record(track: &str, artist: &str, started_at: &str, skipped: bool) -> HistoryRecord {
+        let artist = Artist {
+            id: ArtistId::from_id(artist).unwrap().into_static(),
+            name: artist.to_string(),
+        };
+        HistoryRecord {
+            uri: format!("spotify:track:{track}"),
+            name: track.to_string(),
+            artists: vec![artist.name.clone()],
+            duration_ms: 180_000,
+            context: None,
+            device: None,
+            started_at: started_at.parse().unwrap(),
+            listened_ms: if skipped { 30_000 } else { 180_000 },
+            skipped,
+            track: Some(Track {
+                id: Some(TrackId::from_id(track).unwrap().into_static()),
+                name: track.to_string(),
+                artists: vec![artist.clone()],
+                album: Some(Album {
+                    id: AlbumId::from_id(format!("{track}Album"))
+                        .unwrap()
+                        .into_static(),
+                    release_date: String::new(),
+                    name: format!("{track} Album"),
+                    artists: vec![artist],
+                    typ: None,
+                    added_at: 0,
+                }),
+                duration: Duration::from_mins(3),
+                explicit: false,
+                added_at: 0,
+                isrc: None,
+                kind: TrackKind::Track,
+            }),
+            genres: vec!["synthpop".to_string()],
+        }
+    }
+
+    #[test]
+    fn compute_listening_stats() {
+        let records = vec![
+            record("one", "alice", "2024-03-01T10:00:00Z", false),
+            record("two", "bob", "2024-03-05T10:00:00Z", true),
+            record("two", "bob", "2024-03-06T10:00:00Z", false),
+            record("one", "alice", "2024-03-07T10:00:00Z", false),
+            record("two", "bob", "2024-03-08T10:00:00Z", false),
+        ];
+        let now = "2024-03-08T20:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let stats = ListeningStats::new(&records, StatsWindow::Week, &now);
+        assert_eq!(stats.plays, 4);
+        assert_eq!(stats.skip_rate(), 25);
+        assert_eq!(stats.listened, Duration::from_secs(570));
+        assert_eq!(stats.top_tracks[0].item.name, "two");
+        assert_eq!(stats.top_tracks[0].plays, 3);
+        assert_eq!(stats.top_tracks[0].skip_rate(), 33);
+        assert_eq!(stats.top_artists[1].item.name, "alice");
+        assert_eq!(stats.top_albums.len(), 2);
+        assert_eq!(stats.top_genres[0].plays, 4);
+        assert_eq!(stats.daily.len(), 7);
+        assert_eq!(stats.daily[6].1, Duration::from_mins(3));
+        assert_eq!(stats.longest_streak, 4);
+        assert_eq!(stats.current_streak, 4);
+
+        let stats = ListeningStats::new(&records, StatsWindow::All, &now);
+        assert_eq!(stats.plays, 5);
+        assert_eq!(stats.daily.len(), 8);
+
+        let stats = ListeningStats::new(&records, StatsWindow::Day, &now);
+        assert_eq!(stats.plays, 1);
+        assert_eq!(stats.longest_streak, 1);
+        assert_eq!(stats.current_streak, 4);
+    }
+
+    #[test]
+    fn listening_streaks() {
+        let days = [
+            "2024-02-28",
+            "2024-02-29",
+            "2024-03-01",
+            "2024-03-04",
+            "2024-03-05",
+        ]
+        .iter()
+        .map(|d| d.parse::<NaiveDate>().unwrap())
+        .collect::<BTreeSet<_>>();
+        assert_eq!(longest_streak(&days), 3);
+        assert_eq!(current_streak(&days, "2024-03-06".parse().unwrap()), 2);
+        assert_eq!(current_streak(&days, "2024-03-07".parse().unwrap()), 0);
+        assert_eq!(longest_streak(&BTreeSet::new()), 0);
+    }
+}
@@ -0,0 +1,278 @@
+use super::{Mutex, PlayerState};
+use rspotify::model::{PlayableItem, RepeatState};
+use rspotify::prelude::Id;
+use serde::Serialize;
+
+/// Minimum difference (in milliseconds) between the estimated and the actual
+/// playback's progress to be considered as a seek
+const SEEK_THRESHOLD_MS: i64 = 3000;
+
+/// Maximum number of events buffered for a subscriber before it's considered too slow
+/// to keep up and gets disconnected
+const SUBSCRIBER_BUFFER_SIZE: usize = 256;
+
+/// An event describing a change of the application's state,
+/// streamed to subscribers of the client socket (e.g. `spotify_player watch`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StateEvent {
+    TrackChanged(PlayingItem),
+    PlayPause { is_playing: bool },
+    Seek { position_ms: i64 },
+    Volume { percent: Option<u32> },
+    Shuffle { enabled: bool },
+    Repeat { state: RepeatState },
+    DeviceChanged { id: Option<String>, name: String },
+    LikeToggled { uri: String, liked: bool },
+    QueueChanged { uris: Vec<String> },
+}
+
+/// The currently playing item
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayingItem {
+    pub uri: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// A snapshot of the player's state, used to detect the changes to publish to subscribers
+#[derive(Debug, Default, Clone)]
+pub struct PlayerSnapshot {
+    item: Option<PlayingItem>,
+    is_playing: bool,
+    progress_ms: Option<i64>,
+    volume: Option<u32>,
+    shuffle: bool,
+    repeat: Option<RepeatState>,
+    device: Option<(Option<String>, String)>,
+    queue: Option<Vec<String>>,
+}
+
+impl PlayingItem {
+    pub fn from_playable(item: &PlayableItem) -> Option<Self> {
+        match item {
+            PlayableItem::Track(track) => Some(Self {
+                uri: track.id.as_ref()?.uri(),
+                name: track.name.clone(),
+                artists: track.artists.iter().map(|a| a.name.clone()).collect(),
+                album: Some(track.album.name.clone()),
+                duration_ms: track.duration.num_milliseconds(),
+            }),
+            PlayableItem::Episode(episode) => Some(Self {
+                uri: episode.id.uri(),
+                name: episode.name.clone(),
+                artists: vec![episode.show.publisher.clone()],
+                album: Some(episode.show.name.clone()),
+                duration_ms: episode.duration.num_milliseconds(),
+            }),
+            PlayableItem::Unknown(_) => None,
+        }
+    }
+}
+
+impl PlayerState {
+    pub fn snapshot(&self) -> PlayerSnapshot {
+        let queue = match self.custom_queue {
+            Some(ref q) => Some(q.remaining_tracks().iter().map(Id::uri).collect()),
+            None => self.queue.as_ref().map(|q| {
+                q.queue
+                    .iter()
+                    .filter_map(|item| item.id().map(|id| id.uri()))
+                    .collect()
+            }),
+        };
+
+        match self.current_playback() {
+            None => PlayerSnapshot {
+                queue,
+                ..Default::default()
+            },
+            Some(playback) => PlayerSnapshot {
+                item: playback.item.as_ref().and_then(PlayingItem::from_playable),
+                is_playing: playback.is_playing,
+                progress_ms: playback.progress.map(|p| p.num_milliseconds()),
+                volume: playback.device.volume_percent,
+                shuffle: playback.shuffle_state,
+                repeat: Some(playback.repeat_state),
+                device: Some((playback.device.id, playback.device.name)),
+                queue,
+            },
+        }
+    }
+}
+
+impl PlayerSnapshot {
+    /// Events describing the changes from `self` to `other`
+    pub fn changes(&self, other: &PlayerSnapshot) -> Vec<StateEvent> {
+        let mut events = vec![];
+
+        if self.device != other.device {
+            if let Some((id, name)) = other.device.clone() {
+                events.push(StateEvent::DeviceChanged { id, name });
+            }
+        }
+        if self.item != other.item {
+            if let Some(item) = other.item.clone() {
+                events.push(StateEvent::TrackChanged(item));
+            }
+        } else if let (Some(prev), Some(curr)) = (self.progress_ms, other.progress_ms) {
+            if (curr - prev).abs() >= SEEK_THRESHOLD_MS {
+                events.push(StateEvent::Seek { position_ms: curr });
+            }
+        }
+        if self.is_playing != other.is_playing {
+            events.push(StateEvent::PlayPause {
+                is_playing: other.is_playing,
+            });
+        }
+        if self.volume != other.volume {
+            events.push(StateEvent::Volume {
+                percent: other.volume,
+            });
+        }
+        if self.shuffle != other.shuffle {
+            events.push(StateEvent::Shuffle {
+                enabled: other.shuffle,
+            });
+        }
+        if self.repeat != other.repeat {
+            if let Some(state) = other.repeat {
+                events.push(StateEvent::Repeat { state });
+            }
+        }
+        if self.queue != other.queue {
+            if let Some(uris) = other.queue.clone() {
+                events.push(StateEvent::QueueChanged { uris });
+            }
+        }
+
+        events
+    }
+}
+
+/// Subscribers to the application's state events
+#[derive(Default)]
+pub struct Subscribers {
+    senders: Mutex<Vec<flume::Sender<StateEvent>>>,
+}
+
+impl Subscribers {
+    pub fn subscribe(&self) -> flume::Receiver<StateEvent> {
+        let (tx, rx) = flume::bounded(SUBSCRIBER_BUFFER_SIZE);
+        self.senders.lock().push(tx);
+        rx
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.lock().is_empty()
+    }
+
+    /// Publish events to all subscribers, dropping the ones that have disconnected
+    /// or fallen too far behind
+    pub fn publish(&self, events: &[StateEvent]) {
+        if events.is_empty() {
+            return;
+        }
+        self.senders.lock().retain(|tx| {
+            events.iter().all(|e| match tx.try_send(e.clone()) {
+                Ok(()) => true,
+                Err(flume::TrySendError::Full(_)) => {
+                    tracing::warn!("Dropping a subscriber that is too slow to receive events");
+                    false
+                }
+                Err(flume::TrySendError::Disconnected(_)) => false,
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(uri: &str) -> PlayingItem {
+        PlayingItem {
+            uri: uri.to_string(),
+            name: String::new(),
+            artists: vec![],
+            album: None,
+            duration_ms: 200_000,
+        }
+    }
+
+    fn snapshot(uri: &str, progress_ms: i64) -> PlayerSnapshot {
+        PlayerSnapshot {
+            item: Some(item(uri)),
+            is_playing: true,
+            progress_ms: Some(progress_ms),
+            volume: Some(50),
+            repeat: Some(RepeatState::Off),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_changes() {
+        let prev = snapshot("spotify:track:a", 1000);
+        // progress moving forward slightly isn't a seek
+        let curr = snapshot("spotify:track:a", 2000);
+        assert!(prev.changes(&curr).is_empty());
+    }
+
+    #[test]
+    fn track_change_and_seek() {
+        let prev = snapshot("spotify:track:a", 1000);
+
+        let events = prev.changes(&snapshot("spotify:track:b", 0));
+        assert!(
+            matches!(&events[..], [StateEvent::TrackChanged(item)] if item.uri == "spotify:track:b")
+        );
+
+        let events = prev.changes(&snapshot("spotify:track:a", 60_000));
+        assert!(matches!(
+            events[..],
+            [StateEvent::Seek {
+                position_ms: 60_000
+            }]
+        ));
+    }
+
+    #[test]
+    fn player_settings_changes() {
+        let prev = snapshot("spotify:track:a", 1000);
+        let curr = PlayerSnapshot {
+            is_playing: false,
+            volume: Some(20),
+            shuffle: true,
+            repeat: Some(RepeatState::Track),
+            queue: Some(vec!["spotify:track:c".to_string()]),
+            ..prev.clone()
+        };
+
+        let events = prev.changes(&curr);
+        assert!(matches!(
+            events[..],
+            [
+                StateEvent::PlayPause { is_playing: false },
+                StateEvent::Volume { percent: Some(20) },
+                StateEvent::Shuffle { enabled: true },
+                StateEvent::Repeat {
+                    state: RepeatState::Track
+                },
+                StateEvent::QueueChanged { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn slow_subscriber_is_dropped() {
+        let subscribers = Subscribers::default();
+        let _rx = subscribers.subscribe();
+        let events = vec![StateEvent::Shuffle { enabled: true }; SUBSCRIBER_BUFFER_SIZE + 1];
+
+        subscribers.publish(&events);
+        assert!(subscribers.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    state::model::{Category, ContextId},
+    state::{
+        model::{Category, ContextId, TimeRange},
+        StatsWindow,
+    },
     ui::single_line_input::LineInput,
 };
 use ratatui::widgets::{ListState, TableState};
@@ -28,7 +31,7 @@ pub enum PageState {
         state: BrowsePageUIState,
     },
     Queue {
-        scroll_offset: usize,
+        queue_table: TableState,
     },
     CommandHelp {
         scroll_offset: usize,
@@ -36,6 +39,14 @@ pub enum PageState {
     Logs {
         scroll_offset: usize,
     },
+    Stats {
+        window: StatsWindow,
+        state: StatsPageUIState,
+    },
+    TopArtists {
+        time_range: TimeRange,
+        artist_list: ListState,
+    },
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -48,6 +59,8 @@ pub enum PageType {
     Queue,
     CommandHelp,
     Logs,
+    Stats,
+    TopArtists,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -55,6 +68,8 @@ pub struct LibraryPageUIState {
     pub playlist_list: ListState,
     pub saved_album_list: ListState,
     pub followed_artist_list: ListState,
+    pub saved_show_list: ListState,
+    pub saved_audiobook_list: ListState,
     pub focus: LibraryFocusState,
     pub playlist_folder_id: usize,
 }
@@ -67,9 +82,18 @@ pub struct SearchPageUIState {
     pub playlist_list: ListState,
     pub show_list: ListState,
     pub episode_list: ListState,
+    pub audiobook_list: ListState,
     pub focus: SearchFocusState,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsPageUIState {
+    pub track_table: TableState,
+    pub artist_list: ListState,
+    pub album_list: ListState,
+    pub focus: StatsFocusState,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ContextPageType {
     CurrentPlaying,
@@ -97,6 +121,9 @@ pub enum ContextPageUIState {
     Show {
         episode_table: TableState,
     },
+    Audiobook {
+        chapter_table: TableState,
+    },
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -104,6 +131,8 @@ pub enum LibraryFocusState {
     Playlists,
     SavedAlbums,
     FollowedArtists,
+    SavedShows,
+    SavedAudiobooks,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -114,6 +143,13 @@ pub enum ArtistFocusState {
     LikedSongs,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StatsFocusState {
+    Tracks,
+    Artists,
+    Albums,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SearchFocusState {
     Input,
@@ -123,6 +159,7 @@ pub enum SearchFocusState {
     Playlists,
     Shows,
     Episodes,
+    Audiobooks,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -154,6 +191,8 @@ impl PageState {
             PageState::Queue { .. } => PageType::Queue,
             PageState::CommandHelp { .. } => PageType::CommandHelp,
             PageState::Logs { .. } => PageType::Logs,
+            PageState::Stats { .. } => PageType::Stats,
+            PageState::TopArtists { .. } => PageType::TopArtists,
         }
     }
 
@@ -179,6 +218,8 @@ impl PageState {
                         playlist_list,
                         saved_album_list,
                         followed_artist_list,
+                        saved_show_list,
+                        saved_audiobook_list,
                         focus,
                         ..
                     },
@@ -188,6 +229,10 @@ impl PageState {
                 LibraryFocusState::FollowedArtists => {
                     MutableWindowState::List(followed_artist_list)
                 }
+                LibraryFocusState::SavedShows => MutableWindowState::List(saved_show_list),
+                LibraryFocusState::SavedAudiobooks => {
+                    MutableWindowState::List(saved_audiobook_list)
+                }
             }),
             Self::Search {
                 state:
@@ -198,6 +243,7 @@ impl PageState {
                         playlist_list,
                         show_list,
                         episode_list,
+                        audiobook_list,
                         focus,
                     },
                 ..
@@ -209,6 +255,7 @@ impl PageState {
                 SearchFocusState::Playlists => Some(MutableWindowState::List(playlist_list)),
                 SearchFocusState::Shows => Some(MutableWindowState::List(show_list)),
                 SearchFocusState::Episodes => Some(MutableWindowState::List(episode_list)),
+                SearchFocusState::Audiobooks => Some(MutableWindowState::List(audiobook_list)),
             },
             Self::Context { state, .. } => state.as_mut().map(|state| match state {
                 ContextPageUIState::Tracks { track_table }
@@ -233,6 +280,9 @@ impl PageState {
                 ContextPageUIState::Show { episode_table } => {
                     MutableWindowState::Table(episode_table)
                 }
+                ContextPageUIState::Audiobook { chapter_table } => {
+                    MutableWindowState::Table(chapter_table)
+                }
             }),
             Self::Browse { state } => match state {
                 BrowsePageUIState::CategoryList { state } => Some(MutableWindowState::List(state)),
@@ -240,10 +290,26 @@ impl PageState {
                     Some(MutableWindowState::List(state))
                 }
             },
+            Self::Stats {
+                state:
+                    StatsPageUIState {
+                        track_table,
+                        artist_list,
+                        album_list,
+                        focus,
+                    },
+                ..
+            } => Some(match focus {
+                StatsFocusState::Tracks => MutableWindowState::Table(track_table),
+                StatsFocusState::Artists => MutableWindowState::List(artist_list),
+                StatsFocusState::Albums => MutableWindowState::List(album_list),
+            }),
+            Self::TopArtists { artist_list, .. } => Some(MutableWindowState::List(artist_list)),
+            Self::Queue { queue_table } => Some(MutableWindowState::Table(queue_table)),
             Self::Lyrics { .. } => None,
-            Self::CommandHelp { scroll_offset }
-            | Self::Queue { scroll_offset }
-            | Self::Logs { scroll_offset } => Some(MutableWindowState::Scroll(scroll_offset)),
+            Self::CommandHelp { scroll_offset } | Self::Logs { scroll_offset } => {
+                Some(MutableWindowState::Scroll(scroll_offset))
+            }
         }
     }
 }
@@ -254,6 +320,8 @@ impl LibraryPageUIState {
             playlist_list: ListState::default(),
             saved_album_list: ListState::default(),
             followed_artist_list: ListState::default(),
+            saved_show_list: ListState::default(),
+            saved_audiobook_list: ListState::default(),
             focus: LibraryFocusState::Playlists,
             playlist_folder_id: 0,
         }
@@ -269,11 +337,23 @@ impl SearchPageUIState {
             playlist_list: ListState::default(),
             show_list: ListState::default(),
             episode_list: ListState::default(),
+            audiobook_list: ListState::default(),
             focus: SearchFocusState::Input,
         }
     }
 }
 
+impl StatsPageUIState {
+    pub fn new() -> Self {
+        Self {
+            track_table: TableState::default(),
+            artist_list: ListState::default(),
+            album_list: ListState::default(),
+            focus: StatsFocusState::Tracks,
+        }
+    }
+}
+
 impl ContextPageType {
     pub fn title(&self) -> String {
         match self {
@@ -284,6 +364,7 @@ impl ContextPageType {
                 ContextId::Artist(_) => String::from("Artist"),
                 ContextId::Tracks(id) => id.kind.clone(),
                 ContextId::Show(_) => String::from("Show"),
+                ContextId::Audiobook(_) => String::from("Audiobook"),
             },
         }
     }
@@ -323,6 +404,12 @@ impl ContextPageUIState {
             episode_table: TableState::default(),
         }
     }
+
+    pub fn new_audiobook() -> Self {
+        Self::Audiobook {
+            chapter_table: TableState::default(),
+        }
+    }
 }
 
 impl MutableWindowState<'_> {
@@ -365,6 +452,10 @@ impl Focusable for PageState {
                 state: Some(ContextPageUIState::Artist { focus, .. }),
                 ..
             } => focus.next(),
+            Self::Stats {
+                state: StatsPageUIState { focus, .. },
+                ..
+            } => focus.next(),
             _ => {}
         }
 
@@ -388,6 +479,10 @@ impl Focusable for PageState {
                 state: Some(ContextPageUIState::Artist { focus, .. }),
                 ..
             } => focus.previous(),
+            Self::Stats {
+                state: StatsPageUIState { focus, .. },
+                ..
+            } => focus.previous(),
             _ => {}
         }
 
@@ -424,7 +519,9 @@ impl_focusable!(
     LibraryFocusState,
     [Playlists, SavedAlbums],
     [SavedAlbums, FollowedArtists],
-    [FollowedArtists, Playlists]
+    [FollowedArtists, SavedShows],
+    [SavedShows, SavedAudiobooks],
+    [SavedAudiobooks, Playlists]
 );
 
 impl_focusable!(
@@ -435,6 +532,13 @@ impl_focusable!(
     [RelatedArtists, TopTracks]
 );
 
+impl_focusable!(
+    StatsFocusState,
+    [Tracks, Artists],
+    [Artists, Albums],
+    [Albums, Tracks]
+);
+
 impl_focusable!(
     SearchFocusState,
     [Input, Tracks],
@@ -443,5 +547,6 @@ impl_focusable!(
     [Artists, Playlists],
     [Playlists, Shows],
     [Shows, Episodes],
-    [Episodes, Input]
+    [Episodes, Audiobooks],
+    [Audiobooks, Input]
 );
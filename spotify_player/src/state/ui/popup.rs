@@ -1,7 +1,7 @@
 use crate::{
     command,
     state::{
-        model::{Album, Artist, Episode, EpisodeId, Playlist, Show, Track, TrackId},
+        model::{Album, Artist, Episode, EpisodeId, PlayableId, Playlist, Show, Track, TrackId},
         ItemId,
     },
     ui::single_line_input::LineInput,
@@ -31,20 +31,26 @@ pub enum PopupState {
         name: LineInput,
         desc: LineInput,
         current_field: PlaylistCreateCurrentField,
+        /// whether to fill the new playlist with the items of the current queue
+        from_queue: bool,
     },
     ConfirmAction {
         message: String,
         action: ConfirmableAction,
     },
+    /// an informational message, closed by any key
+    Message(String),
 }
 
 #[derive(Debug, Clone)]
 pub enum ConfirmableAction {
     DeleteTrackFromPlaylist {
         playlist_id: PlaylistId<'static>,
-        track_id: TrackId<'static>,
+        playable_id: PlayableId<'static>,
     },
     DeleteFromLibrary(ItemId),
+    DedupePlaylist(PlaylistId<'static>),
+    CommitPlaylistOrder(PlaylistId<'static>),
 }
 
 #[derive(Debug, Clone)]
@@ -94,7 +100,10 @@ impl PopupState {
             | Self::ArtistList(.., list_state)
             | Self::ThemeList(.., list_state)
             | Self::ActionList(.., list_state) => Some(list_state),
-            Self::Search { .. } | Self::PlaylistCreate { .. } | Self::ConfirmAction { .. } => None,
+            Self::Search { .. }
+            | Self::PlaylistCreate { .. }
+            | Self::ConfirmAction { .. }
+            | Self::Message(_) => None,
         }
     }
 
@@ -108,7 +117,10 @@ impl PopupState {
             | Self::ArtistList(.., list_state)
             | Self::ThemeList(.., list_state)
             | Self::ActionList(.., list_state) => Some(list_state),
-            Self::Search { .. } | Self::PlaylistCreate { .. } | Self::ConfirmAction { .. } => None,
+            Self::Search { .. }
+            | Self::PlaylistCreate { .. }
+            | Self::ConfirmAction { .. }
+            | Self::Message(_) => None,
         }
     }
 
@@ -138,6 +138,23 @@ impl PlayerEvent {
     }
 }
 
+/// Update the listening history from an event of the integrated player,
+/// which is more accurate than the periodically refreshed playback
+fn record_listening_history(state: &SharedState, event: &PlayerEvent) {
+    match event {
+        PlayerEvent::Playing { playable_id, .. } => {
+            state.history.lock().set_playing(&playable_id.uri(), true);
+        }
+        PlayerEvent::Paused { playable_id, .. } => {
+            state.history.lock().set_playing(&playable_id.uri(), false);
+        }
+        PlayerEvent::EndOfTrack { playable_id } => {
+            state.history.lock().end_of_track(&playable_id.uri());
+        }
+        PlayerEvent::Changed { .. } => {}
+    }
+}
+
 fn execute_player_event_hook_command(
     cmd: &config::Command,
     event: &PlayerEvent,
@@ -270,25 +287,32 @@ pub async fn new_connection(
                         tracing::info!("Got a new player event: {event:?}");
                         match event {
                             PlayerEvent::Playing { .. } => {
-                                let mut player = state.player.write();
-                                if let Some(playback) = player.buffered_playback.as_mut() {
-                                    playback.is_playing = true;
-                                }
+                                state.update_player(|player| {
+                                    if let Some(playback) = player.buffered_playback.as_mut() {
+                                        playback.is_playing = true;
+                                    }
+                                });
                                 if let Some(ref bands) = state.vis_bands {
                                     bands.lock().is_active = true;
                                 }
                             }
                             PlayerEvent::Paused { .. } => {
-                                let mut player = state.player.write();
-                                if let Some(playback) = player.buffered_playback.as_mut() {
-                                    playback.is_playing = false;
-                                }
+                                state.update_player(|player| {
+                                    if let Some(playback) = player.buffered_playback.as_mut() {
+                                        playback.is_playing = false;
+                                    }
+                                });
                                 if let Some(ref bands) = state.vis_bands {
                                     bands.lock().is_active = false;
                                 }
                             }
                             _ => {}
                         }
+                        if configs.app_config.enable_listening_history
+                            || crate::scrobble::is_enabled()
+                        {
+                            record_listening_history(&state, &event);
+                        }
                         client.update_playback(&state);
 
                         // execute a player event hook command
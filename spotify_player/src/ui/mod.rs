@@ -4,7 +4,7 @@ use crate::{
         Album, Artist, ArtistFocusState, BrowsePageUIState, Context, ContextPageUIState,
         DataReadGuard, Id, LibraryFocusState, MutableWindowState, PageState, PageType,
         PlaybackMetadata, PlaylistCreateCurrentField, PlaylistFolderItem, PlaylistPopupAction,
-        PopupState, SearchFocusState, SharedState, Track, UIStateGuard,
+        PopupState, SearchFocusState, SharedState, StatsFocusState, Track, UIStateGuard,
     },
 };
 use anyhow::{Context as AnyhowContext, Result};
@@ -45,6 +45,13 @@ pub fn run(state: &SharedState, mut terminal: Terminal) -> Result<()> {
         {
             let mut ui = state.ui.lock();
             if !ui.is_running {
+                if let Err(err) = state.save_current_history_record() {
+                    tracing::error!("Failed to save the current history record: {err:#}");
+                }
+                #[cfg(feature = "streaming")]
+                if let Err(err) = state.save_custom_queue() {
+                    tracing::error!("Failed to save the custom queue: {err:#}");
+                }
                 clean_up(terminal).context("clean up UI resources")?;
                 std::process::exit(0);
             }
@@ -166,9 +173,11 @@ fn render_main_layout(
         PageType::Context => page::render_context_page(is_active, frame, state, ui, rect),
         PageType::Browse => page::render_browse_page(is_active, frame, state, ui, rect),
         PageType::Lyrics => page::render_lyrics_page(is_active, frame, state, ui, rect),
-        PageType::Queue => page::render_queue_page(frame, state, ui, rect),
+        PageType::Queue => page::render_queue_page(is_active, frame, state, ui, rect),
         PageType::CommandHelp => page::render_commands_help_page(frame, ui, rect),
         PageType::Logs => page::render_logs_page(frame, state, ui, rect),
+        PageType::Stats => page::render_stats_page(is_active, frame, state, ui, rect),
+        PageType::TopArtists => page::render_top_artists_page(is_active, frame, state, ui, rect),
     }
 }
 
@@ -4,16 +4,22 @@ use std::{
 };
 
 use chrono_humanize::HumanTime;
-use ratatui::text::Line;
+use ratatui::{
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Tabs},
+};
 
-use crate::{state::Episode, utils::format_duration};
+use crate::{
+    state::{Chapter, ContextId, Episode, TimeRange},
+    utils::format_duration,
+};
 
 use super::{
     config, utils, utils::construct_and_render_block, Album, Alignment, Artist, ArtistFocusState,
     Borders, BrowsePageUIState, Cell, Constraint, Context, ContextPageUIState, DataReadGuard,
     Frame, Id, Layout, LibraryFocusState, MutableWindowState, Orientation, PageState, Paragraph,
-    PlaylistFolderItem, Rect, Row, SearchFocusState, SharedState, Style, Table, Text, Track,
-    UIStateGuard,
+    PlaylistFolderItem, Rect, Row, SearchFocusState, SharedState, StatsFocusState, Style, Table,
+    Text, Track, UIStateGuard,
 };
 use crate::state::BidiDisplay;
 use crate::ui::utils::to_bidi_string;
@@ -67,30 +73,33 @@ pub fn render_search_page(
     let search_input_rect = chunks[0];
     let rect = chunks[1];
 
-    // track/album/artist/playlist/show/episode search results layout
+    // track/album/artist/playlist/show/episode/audiobook search results layout
     let chunks = match ui.orientation {
-        // 1x6
+        // 1x7
         Orientation::Vertical => {
             let constraints = if focus_state == SearchFocusState::Input {
-                [Constraint::Ratio(1, 6); 6]
+                [Constraint::Ratio(1, 7); 7]
             } else {
-                let mut constraints = [Constraint::Percentage(15); 6];
-                constraints[focus_state as usize - 1] = Constraint::Percentage(25);
+                let mut constraints = [Constraint::Percentage(12); 7];
+                constraints[focus_state as usize - 1] = Constraint::Percentage(28);
                 constraints
             };
 
-            Layout::vertical(constraints).split(rect)
+            Layout::vertical(constraints).split(rect).to_vec()
+        }
+        // 2x3, with the audiobook window spanning the last row
+        Orientation::Horizontal => {
+            let rows = Layout::vertical([Constraint::Ratio(1, 4); 4]).split(rect);
+            rows[..3]
+                .iter()
+                .flat_map(|rect| {
+                    Layout::horizontal([Constraint::Ratio(1, 2); 2])
+                        .split(*rect)
+                        .to_vec()
+                })
+                .chain([rows[3]])
+                .collect()
         }
-        // 2x3
-        Orientation::Horizontal => Layout::vertical([Constraint::Ratio(1, 3); 3])
-            .split(rect)
-            .iter()
-            .flat_map(|rect| {
-                Layout::horizontal([Constraint::Ratio(1, 2); 2])
-                    .split(*rect)
-                    .to_vec()
-            })
-            .collect(),
     };
 
     let track_rect = construct_and_render_block(
@@ -132,6 +141,8 @@ pub fn render_search_page(
     );
     let episode_rect =
         construct_and_render_block("Episodes", &ui.theme, Borders::TOP, frame, chunks[5]);
+    let audiobook_rect =
+        construct_and_render_block("Audiobooks", &ui.theme, Borders::TOP, frame, chunks[6]);
 
     // 3. Construct the page's widgets
     let (track_list, n_tracks) = {
@@ -223,6 +234,21 @@ pub fn render_search_page(
         utils::construct_list_widget(&ui.theme, episode_items, is_active, selected_index)
     };
 
+    let (audiobook_list, n_audiobooks) = {
+        let audiobook_items = search_results
+            .map(|s| search_items(&s.audiobooks))
+            .unwrap_or_default();
+
+        let is_active = is_active && focus_state == SearchFocusState::Audiobooks;
+        let selected_index = if is_active {
+            ui.current_page_mut().selected()
+        } else {
+            None
+        };
+
+        utils::construct_list_widget(&ui.theme, audiobook_items, is_active, selected_index)
+    };
+
     // 4. Render the page's widgets
     // Need mutable access to the list/table states stored inside the page state for rendering.
     let PageState::Search {
@@ -281,6 +307,13 @@ pub fn render_search_page(
         n_episodes,
         &mut page_state.episode_list,
     );
+    utils::render_list_window(
+        frame,
+        audiobook_list,
+        audiobook_rect,
+        n_audiobooks,
+        &mut page_state.audiobook_list,
+    );
 }
 
 pub fn render_context_page(
@@ -318,6 +351,11 @@ pub fn render_context_page(
         return;
     };
 
+    let top_tracks_time_range = match id {
+        ContextId::Tracks(tracks_id) => tracks_id.top_tracks_time_range(),
+        _ => None,
+    };
+
     let data = state.data.read();
     match data.caches.context.get(&id.uri()) {
         Some(context) => {
@@ -386,6 +424,19 @@ pub fn render_context_page(
                     );
                 }
                 Context::Tracks { tracks, .. } | Context::Album { tracks, .. } => {
+                    let rect = match top_tracks_time_range {
+                        Some(time_range) => {
+                            let chunks =
+                                Layout::vertical([Constraint::Length(1), Constraint::Fill(0)])
+                                    .split(rect);
+                            frame.render_widget(
+                                construct_time_range_tabs(time_range, &ui.theme),
+                                chunks[0],
+                            );
+                            chunks[1]
+                        }
+                        None => rect,
+                    };
                     render_track_table(
                         frame,
                         rect,
@@ -407,6 +458,16 @@ pub fn render_context_page(
                         ui,
                     );
                 }
+                Context::Audiobook { chapters, .. } => {
+                    render_chapter_table(
+                        frame,
+                        rect,
+                        is_active,
+                        state,
+                        ui.search_filtered_items(chapters),
+                        ui,
+                    );
+                }
             }
         }
         None => {
@@ -433,20 +494,23 @@ pub fn render_library_page(
     };
 
     // 2. Construct the page's layout
-    // Split the library page into 3 windows:
+    // Split the library page into 5 windows:
     // - a playlists window
     // - a saved albums window
     // - a followed artists window
+    // - a saved shows window, sharing the remaining space with the followed artists window
+    // - a saved audiobooks window, stacked with the saved shows window
 
+    let remaining_percent = 100
+        - (configs.app_config.layout.library.album_percent
+            + configs.app_config.layout.library.playlist_percent);
     let chunks = ui
         .orientation
         .layout([
             Constraint::Percentage(configs.app_config.layout.library.playlist_percent),
             Constraint::Percentage(configs.app_config.layout.library.album_percent),
-            Constraint::Percentage(
-                100 - (configs.app_config.layout.library.album_percent
-                    + configs.app_config.layout.library.playlist_percent),
-            ),
+            Constraint::Percentage(remaining_percent.div_ceil(2)),
+            Constraint::Percentage(remaining_percent / 2),
         ])
         .split(rect);
 
@@ -470,8 +534,30 @@ pub fn render_library_page(
         frame,
         chunks[1],
     );
-    let artist_rect =
-        construct_and_render_block("Artists", &ui.theme, Borders::ALL, frame, chunks[2]);
+    let artist_rect = construct_and_render_block(
+        "Artists",
+        &ui.theme,
+        match ui.orientation {
+            Orientation::Horizontal => Borders::TOP | Borders::LEFT | Borders::BOTTOM,
+            Orientation::Vertical => Borders::ALL,
+        },
+        frame,
+        chunks[2],
+    );
+    let podcast_chunks = match ui.orientation {
+        Orientation::Horizontal => Layout::vertical([Constraint::Ratio(1, 2); 2]),
+        Orientation::Vertical => Layout::horizontal([Constraint::Ratio(1, 2); 2]),
+    }
+    .split(chunks[3]);
+    let show_rect =
+        construct_and_render_block("Shows", &ui.theme, Borders::ALL, frame, podcast_chunks[0]);
+    let audiobook_rect = construct_and_render_block(
+        "Audiobooks",
+        &ui.theme,
+        Borders::ALL,
+        frame,
+        podcast_chunks[1],
+    );
 
     // 3. Construct the page's widgets
     // Construct the playlist window
@@ -486,9 +572,7 @@ pub fn render_library_page(
         })
         .collect::<Vec<_>>();
 
-    let is_playlist_active = is_active
-        && focus_state != LibraryFocusState::SavedAlbums
-        && focus_state != LibraryFocusState::FollowedArtists;
+    let is_playlist_active = is_active && focus_state == LibraryFocusState::Playlists;
     let playlist_selected = if is_playlist_active {
         ui.current_page_mut().selected()
     } else {
@@ -528,6 +612,45 @@ pub fn render_library_page(
         is_artist_active,
         artist_selected,
     );
+    // Construct the saved show window
+    let is_show_active = is_active && focus_state == LibraryFocusState::SavedShows;
+    let show_selected = if is_show_active {
+        ui.current_page_mut().selected()
+    } else {
+        None
+    };
+    let (show_list, n_shows) = utils::construct_list_widget(
+        &ui.theme,
+        ui.search_filtered_items(&data.user_data.saved_shows)
+            .into_iter()
+            .map(|s| {
+                let name = s.to_bidi_string();
+                let desc = match s.unplayed_episodes {
+                    Some(0) | None => name,
+                    Some(n) => format!("{name} ({n} unplayed)"),
+                };
+                (desc, curr_context_uri == Some(s.id.uri()))
+            })
+            .collect(),
+        is_show_active,
+        show_selected,
+    );
+    // Construct the saved audiobook window
+    let is_audiobook_active = is_active && focus_state == LibraryFocusState::SavedAudiobooks;
+    let audiobook_selected = if is_audiobook_active {
+        ui.current_page_mut().selected()
+    } else {
+        None
+    };
+    let (audiobook_list, n_audiobooks) = utils::construct_list_widget(
+        &ui.theme,
+        ui.search_filtered_items(&data.user_data.saved_audiobooks)
+            .into_iter()
+            .map(|a| (a.to_bidi_string(), false))
+            .collect(),
+        is_audiobook_active,
+        audiobook_selected,
+    );
 
     // 4. Render the page's widgets
     // Render the library page's windows.
@@ -557,6 +680,20 @@ pub fn render_library_page(
         n_artists,
         &mut page_state.followed_artist_list,
     );
+    utils::render_list_window(
+        frame,
+        show_list,
+        show_rect,
+        n_shows,
+        &mut page_state.saved_show_list,
+    );
+    utils::render_list_window(
+        frame,
+        audiobook_list,
+        audiobook_rect,
+        n_audiobooks,
+        &mut page_state.saved_audiobook_list,
+    );
 }
 
 pub fn render_browse_page(
@@ -622,6 +759,56 @@ pub fn render_browse_page(
     utils::render_list_window(frame, list, rect, len, list_state);
 }
 
+pub fn render_top_artists_page(
+    is_active: bool,
+    frame: &mut Frame,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+    rect: Rect,
+) {
+    // 1. Get data
+    let data = state.data.read();
+    let PageState::TopArtists { time_range, .. } = ui.current_page() else {
+        return;
+    };
+    let time_range = *time_range;
+
+    // 2. Construct the page's layout
+    let rect = construct_and_render_block("Top Artists", &ui.theme, Borders::ALL, frame, rect);
+    let chunks = Layout::vertical([Constraint::Length(1), Constraint::Fill(0)]).split(rect);
+    frame.render_widget(construct_time_range_tabs(time_range, &ui.theme), chunks[0]);
+
+    // 3. Construct the page's widgets
+    let Some(artists) = data.caches.top_artists.get(time_range.as_str()) else {
+        frame.render_widget(Paragraph::new("Loading..."), chunks[1]);
+        return;
+    };
+    let (list, len) = utils::construct_list_widget(
+        &ui.theme,
+        artists
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (format!("{}. {}", i + 1, a.to_bidi_string()), false))
+            .collect(),
+        is_active,
+        None,
+    );
+
+    // 4. Render the page's widgets
+    let PageState::TopArtists { artist_list, .. } = ui.current_page_mut() else {
+        return;
+    };
+    utils::render_list_window(frame, list, chunks[1], len, artist_list);
+}
+
+/// Construct the tabs of the time ranges of the user's top items, highlighting the current one
+fn construct_time_range_tabs(time_range: TimeRange, theme: &config::Theme) -> Tabs<'static> {
+    Tabs::new(TimeRange::ALL.map(|r| r.to_string()))
+        .select(TimeRange::ALL.iter().position(|r| *r == time_range))
+        .style(theme.page_desc())
+        .highlight_style(theme.selection(true))
+}
+
 pub fn render_lyrics_page(
     _is_active: bool,
     frame: &mut Frame,
@@ -774,6 +961,7 @@ pub fn render_commands_help_page(frame: &mut Frame, ui: &mut UIStateGuard, rect:
 }
 
 pub fn render_queue_page(
+    is_active: bool,
     frame: &mut Frame,
     state: &SharedState,
     ui: &mut UIStateGuard,
@@ -812,17 +1000,6 @@ pub fn render_queue_page(
         Some(ref q) => &q.queue,
         None => return,
     };
-    let scroll_offset = match ui.current_page_mut() {
-        PageState::Queue {
-            ref mut scroll_offset,
-        } => {
-            if !queue.is_empty() && *scroll_offset >= queue.len() {
-                *scroll_offset = queue.len() - 1;
-            }
-            *scroll_offset
-        }
-        _ => return,
-    };
 
     // 2. Construct the page's layout
     let rect = construct_and_render_block("Queue", &ui.theme, Borders::ALL, frame, rect);
@@ -832,7 +1009,6 @@ pub fn render_queue_page(
         queue
             .iter()
             .enumerate()
-            .skip(scroll_offset)
             .map(|(i, x)| {
                 Row::new(vec![
                     Cell::from(format!("{}", i + 1)),
@@ -857,10 +1033,16 @@ pub fn render_queue_page(
             Cell::from("Duration"),
         ])
         .style(ui.theme.table_header()),
-    );
+    )
+    .row_highlight_style(ui.theme.selection(is_active));
 
     // 4. Render page's widget
-    frame.render_widget(queue_table, rect);
+    if let PageState::Queue {
+        queue_table: table_state,
+    } = ui.current_page_mut()
+    {
+        utils::render_table_window(frame, queue_table, rect, queue.len(), table_state);
+    }
 }
 
 /// Render windows for an artist context page, which includes
@@ -1041,12 +1223,16 @@ fn render_track_table(
     let mut playing_track_uri = String::new();
     let mut playing_id = "";
     if let Some(ref playback) = state.player.read().playback {
-        if let Some(rspotify::model::PlayableItem::Track(ref track)) = playback.item {
-            playing_track_uri = track
-                .id
-                .as_ref()
-                .map(rspotify::prelude::Id::uri)
-                .unwrap_or_default();
+        let playing_uri = match playback.item {
+            Some(rspotify::model::PlayableItem::Track(ref track)) => {
+                track.id.as_ref().map(rspotify::prelude::Id::uri)
+            }
+            // episodes can be inside a playlist's track table
+            Some(rspotify::model::PlayableItem::Episode(ref episode)) => Some(episode.id.uri()),
+            _ => None,
+        };
+        if let Some(uri) = playing_uri {
+            playing_track_uri = uri;
 
             playing_id = if playback.is_playing {
                 &configs.app_config.play_icon
@@ -1080,7 +1266,7 @@ fn render_track_table(
                 }
                 None => (id + 1).to_string(),
             };
-            let (play_pause, style) = if playing_track_uri == t.id.uri() {
+            let (play_pause, style) = if playing_track_uri == t.uri() {
                 (playing_id.to_string(), ui.theme.current_playing())
             } else {
                 (String::new(), Style::default())
@@ -1187,6 +1373,9 @@ fn render_track_table(
             ContextPageUIState::Show { .. } => {
                 unreachable!("show's episode table should be handled by render_episode_table")
             }
+            ContextPageUIState::Audiobook { .. } => {
+                unreachable!("audiobook's chapter table should be handled by render_chapter_table")
+            }
         };
         utils::render_table_window(frame, track_table, rect, n_tracks, playable_table_state);
     }
@@ -1288,6 +1477,92 @@ fn render_episode_table(
     }
 }
 
+fn render_chapter_table(
+    frame: &mut Frame,
+    rect: Rect,
+    is_active: bool,
+    state: &SharedState,
+    chapters: Vec<&Chapter>,
+    ui: &mut UIStateGuard,
+) {
+    fn min_sec(duration: std::time::Duration) -> String {
+        format!("{}:{:02}", duration.as_secs() / 60, duration.as_secs() % 60)
+    }
+
+    let configs = config::get_config();
+    // get the current playing chapter's URI to decorate such chapter (if exists) in the chapter table
+    let mut playing_chapter_uri = String::new();
+    let mut playing_id = "";
+    if let Some(ref playback) = state.player.read().playback {
+        if let Some(rspotify::model::PlayableItem::Episode(ref episode)) = playback.item {
+            playing_chapter_uri = episode.id.uri();
+
+            playing_id = if playback.is_playing {
+                &configs.app_config.play_icon
+            } else {
+                &configs.app_config.pause_icon
+            };
+        }
+    }
+
+    let n_chapters = chapters.len();
+    let rows = chapters
+        .into_iter()
+        .map(|c| {
+            let (id_str, style) = if playing_chapter_uri == c.id.uri() {
+                (playing_id.to_string(), ui.theme.current_playing())
+            } else {
+                (c.number.to_string(), Style::default())
+            };
+            let progress = if c.fully_played {
+                String::from("Played")
+            } else {
+                c.resume_position
+                    .map(|p| format!("{} left", min_sec(c.duration.saturating_sub(p))))
+                    .unwrap_or_default()
+            };
+            Row::new(vec![
+                Cell::from(id_str),
+                Cell::from(to_bidi_string(&c.name)),
+                Cell::from(progress),
+                Cell::from(min_sec(c.duration)),
+            ])
+            .style(style)
+        })
+        .collect::<Vec<_>>();
+    let chapter_table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Fill(6),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            Cell::from("#"),
+            Cell::from("Title"),
+            Cell::from("Progress"),
+            Cell::from("Duration"),
+        ])
+        .style(ui.theme.table_header()),
+    )
+    .column_spacing(2)
+    .row_highlight_style(ui.theme.selection(is_active));
+
+    if let PageState::Context {
+        state: Some(state), ..
+    } = ui.current_page_mut()
+    {
+        let playable_table_state = match state {
+            ContextPageUIState::Audiobook { chapter_table } => chapter_table,
+            s => unreachable!("unexpected state: {s:?}"),
+        };
+        utils::render_table_window(frame, chapter_table, rect, n_chapters, playable_table_state);
+    }
+}
+
 pub fn render_logs_page(frame: &mut Frame, state: &SharedState, ui: &mut UIStateGuard, rect: Rect) {
     let rect = construct_and_render_block("Logs", &ui.theme, Borders::ALL, frame, rect);
 
@@ -1320,3 +1595,238 @@ pub fn render_logs_page(frame: &mut Frame, state: &SharedState, ui: &mut UIState
     let paragraph = Paragraph::new(lines);
     frame.render_widget(paragraph, rect);
 }
+
+pub fn render_stats_page(
+    is_active: bool,
+    frame: &mut Frame,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+    rect: Rect,
+) {
+    // 1. Get data
+    let data = state.data.read();
+    let (window, focus_state) = match ui.current_page() {
+        PageState::Stats { window, state } => (*window, state.focus),
+        _ => return,
+    };
+
+    let title = format!("Listening Statistics ({window})");
+    let Some(stats) = data.stats.as_ref().filter(|s| s.window == window) else {
+        let rect = construct_and_render_block(&title, &ui.theme, Borders::ALL, frame, rect);
+        frame.render_widget(Paragraph::new("Loading..."), rect);
+        return;
+    };
+
+    // 2. Construct the page's layout
+    // Split the stats page into
+    // - a summary window
+    // - a chart of the listening time per day
+    // - top tracks, top artists and top albums windows
+    let [summary_rect, chart_rect, top_rect] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(10),
+        Constraint::Fill(1),
+    ])
+    .areas(rect);
+    let chunks = ui
+        .orientation
+        .layout([
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ])
+        .split(top_rect);
+
+    let summary_rect =
+        construct_and_render_block(&title, &ui.theme, Borders::ALL, frame, summary_rect);
+    let chart_rect = construct_and_render_block(
+        "Listening Time per Day",
+        &ui.theme,
+        Borders::ALL,
+        frame,
+        chart_rect,
+    );
+    let track_rect = construct_and_render_block(
+        "Top Tracks",
+        &ui.theme,
+        match ui.orientation {
+            Orientation::Horizontal => Borders::TOP | Borders::LEFT | Borders::BOTTOM,
+            Orientation::Vertical => Borders::ALL,
+        },
+        frame,
+        chunks[0],
+    );
+    let artist_rect = construct_and_render_block(
+        "Top Artists",
+        &ui.theme,
+        match ui.orientation {
+            Orientation::Horizontal => Borders::TOP | Borders::LEFT | Borders::BOTTOM,
+            Orientation::Vertical => Borders::ALL,
+        },
+        frame,
+        chunks[1],
+    );
+    let album_rect =
+        construct_and_render_block("Top Albums", &ui.theme, Borders::ALL, frame, chunks[2]);
+
+    // 3. Construct the page's widgets
+    let genres = stats
+        .top_genres
+        .iter()
+        .take(5)
+        .map(|g| g.item.as_str())
+        .collect::<Vec<_>>();
+    let summary = Paragraph::new(vec![
+        Line::from(format!(
+            "{} listened • {} plays • {}% skipped",
+            format_listening_time(stats.listened),
+            stats.plays,
+            stats.skip_rate(),
+        )),
+        Line::from(format!(
+            "Longest streak: {} days • Current streak: {} days • Top genres: {}",
+            stats.longest_streak,
+            stats.current_streak,
+            if genres.is_empty() {
+                "-".to_string()
+            } else {
+                genres.join(", ")
+            },
+        )),
+    ]);
+
+    // only render the most recent days fitting in the chart
+    let n_bars = usize::from(chart_rect.width.div_ceil(STATS_BAR_WIDTH + 1));
+    let bars = stats
+        .daily
+        .iter()
+        .skip(stats.daily.len().saturating_sub(n_bars))
+        .map(|(day, listened)| {
+            Bar::default()
+                .value(listened.as_secs() / 60)
+                .text_value(format_listening_time(*listened).replace(' ', ""))
+                .label(Line::from(day.format("%m/%d").to_string()))
+        })
+        .collect::<Vec<_>>();
+    let chart = BarChart::default()
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(STATS_BAR_WIDTH)
+        .bar_gap(1)
+        .bar_style(ui.theme.playback_progress_bar())
+        .value_style(ui.theme.playback_progress_bar().reversed());
+
+    let is_track_active = is_active && focus_state == StatsFocusState::Tracks;
+    let track_table = Table::new(
+        stats
+            .top_tracks
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                Row::new(vec![
+                    Cell::from(Text::from((i + 1).to_string()).alignment(Alignment::Right)),
+                    Cell::from(to_bidi_string(&e.item.display_name())),
+                    Cell::from(to_bidi_string(&e.item.artists_info())),
+                    Cell::from(e.plays.to_string()),
+                    Cell::from(format!("{}%", e.skip_rate())),
+                ])
+            })
+            .collect::<Vec<_>>(),
+        [
+            Constraint::Length(2),
+            Constraint::Fill(4),
+            Constraint::Fill(3),
+            Constraint::Length(5),
+            Constraint::Length(7),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            Cell::from(Text::from("#").alignment(Alignment::Right)),
+            Cell::from("Title"),
+            Cell::from("Artists"),
+            Cell::from("Plays"),
+            Cell::from("Skipped"),
+        ])
+        .style(ui.theme.table_header()),
+    )
+    .column_spacing(2)
+    .row_highlight_style(ui.theme.selection(is_track_active));
+
+    let is_artist_active = is_active && focus_state == StatsFocusState::Artists;
+    let (artist_list, n_artists) = utils::construct_list_widget(
+        &ui.theme,
+        stats
+            .top_artists
+            .iter()
+            .map(|e| {
+                (
+                    format!("{} • {} plays", e.item.to_bidi_string(), e.plays),
+                    false,
+                )
+            })
+            .collect(),
+        is_artist_active,
+        None,
+    );
+    let is_album_active = is_active && focus_state == StatsFocusState::Albums;
+    let (album_list, n_albums) = utils::construct_list_widget(
+        &ui.theme,
+        stats
+            .top_albums
+            .iter()
+            .map(|e| {
+                (
+                    format!("{} • {} plays", to_bidi_string(&e.item.name), e.plays),
+                    false,
+                )
+            })
+            .collect(),
+        is_album_active,
+        None,
+    );
+
+    // 4. Render the page's widgets
+    frame.render_widget(summary, summary_rect);
+    frame.render_widget(chart, chart_rect);
+
+    let PageState::Stats {
+        state: page_state, ..
+    } = ui.current_page_mut()
+    else {
+        return;
+    };
+    utils::render_table_window(
+        frame,
+        track_table,
+        track_rect,
+        stats.top_tracks.len(),
+        &mut page_state.track_table,
+    );
+    utils::render_list_window(
+        frame,
+        artist_list,
+        artist_rect,
+        n_artists,
+        &mut page_state.artist_list,
+    );
+    utils::render_list_window(
+        frame,
+        album_list,
+        album_rect,
+        n_albums,
+        &mut page_state.album_list,
+    );
+}
+
+/// Width of a bar of the stats page's chart, fitting a `MM/DD` label and a `12h34m` value
+const STATS_BAR_WIDTH: u16 = 6;
+
+/// formats a listening time into a "{hours}h {minutes}m" format
+fn format_listening_time(duration: std::time::Duration) -> String {
+    let mins = duration.as_secs() / 60;
+    if mins >= 60 {
+        format!("{}h {:02}m", mins / 60, mins % 60)
+    } else {
+        format!("{mins}m")
+    }
+}
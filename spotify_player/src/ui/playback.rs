@@ -6,7 +6,7 @@ use super::{
 #[cfg(feature = "image")]
 use crate::state::ImageRenderInfo;
 use crate::{
-    state::Track,
+    state::{PlayerState, SleepMode, Track},
     ui::utils::{format_genres, to_bidi_string},
 };
 use rspotify::model::Id;
@@ -116,7 +116,7 @@ pub fn render_playback_window(
             };
 
             if let Some(ref playback) = player.buffered_playback {
-                let playback_text = construct_playback_text(ui, state, item, playback);
+                let playback_text = construct_playback_text(ui, state, &player, item, playback);
                 let playback_desc = Paragraph::new(playback_text);
                 frame.render_widget(playback_desc, metadata_rect);
             }
@@ -224,6 +224,7 @@ fn cover_img_length(configs: &config::Configs, picker: &ratatui_image::picker::P
 fn construct_playback_text(
     ui: &UIStateGuard,
     state: &SharedState,
+    player: &PlayerState,
     playable: &rspotify::model::PlayableItem,
     playback: &PlaybackMetadata,
 ) -> Text<'static> {
@@ -354,6 +355,19 @@ fn construct_playback_text(
                     continue;
                 }
             },
+            "{sleep}" => {
+                let Some(timer) = player.sleep_timer.as_ref() else {
+                    continue;
+                };
+                let remaining = match (player.sleep_timer_remaining(), timer.mode) {
+                    (Some(remaining), _) => crate::utils::format_duration(
+                        &chrono::Duration::from_std(remaining).unwrap_or_default(),
+                    ),
+                    (None, SleepMode::EndOfContext) => "end of context".to_string(),
+                    (None, _) => continue,
+                };
+                (format!("sleep: {remaining}"), ui.theme.playback_metadata())
+            }
             "{metadata}" => {
                 let repeat_value = <&'static str>::from(playback.repeat_state).to_string();
 
@@ -28,6 +28,7 @@ pub fn render_popup(
                 name,
                 desc,
                 current_field,
+                ..
             } => {
                 let chunks =
                     Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).split(rect);
@@ -210,6 +211,17 @@ pub fn render_popup(
 
                 frame.render_widget(Paragraph::new(format!("{message} (y/n)")), confirm_rect);
 
+                (chunks[0], true)
+            }
+            PopupState::Message(message) => {
+                let chunks =
+                    Layout::vertical([Constraint::Fill(0), Constraint::Length(3)]).split(rect);
+
+                let message_rect =
+                    construct_and_render_block("Info", &ui.theme, Borders::ALL, frame, chunks[1]);
+
+                frame.render_widget(Paragraph::new(message.as_str()), message_rect);
+
                 (chunks[0], true)
             }
         },